@@ -1,14 +1,16 @@
 #version 330 core
 
-in vec4 a_Position;
-in vec3 a_Normal;
-in vec2 a_TexCoord;
+layout(location = 0) in vec4 a_Position;
+layout(location = 1) in vec3 a_Normal;
+layout(location = 2) in vec2 a_TexCoord;
 
 out vec3 v_Normal;
 out vec2 v_TexCoord;
 
-uniform mat4 u_ProjectionMatrix = mat4(1.0);
-uniform mat4 u_ViewMatrix = mat4(1.0);
+layout(std140) uniform Camera {
+  mat4 u_ProjectionMatrix;
+  mat4 u_ViewMatrix;
+};
 uniform mat4 u_ModelMatrix = mat4(1.0);
 
 void main() {
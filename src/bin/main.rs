@@ -2,13 +2,75 @@
 
 use thallium::{math::*, platform::*, renderer::*, scene::*, *};
 
+/// The same as basic.vert.glsl
+fn basic_vertex(
+    inputs: &[Vector4<f32>],
+    uniforms: &SoftwareShaderUniforms,
+    varyings: &mut Vec<f32>,
+) -> Vector4<f32> {
+    let projection_matrix = uniforms.get_matrix("u_ProjectionMatrix").unwrap();
+    let view_matrix = uniforms.get_matrix("u_ViewMatrix").unwrap();
+    let model_matrix = uniforms.get_matrix("u_ModelMatrix").unwrap();
+
+    let (x, y, z, _) = inputs[1].into();
+    let normal = model_matrix * Vector4::<f32>::from((x, y, z, 0.0));
+    varyings.extend([normal.x, normal.y, normal.z, inputs[2].x, inputs[2].y]);
+    projection_matrix * (view_matrix.inverse().unwrap() * (model_matrix * inputs[0]))
+}
+
+/// The same as basic.frag.glsl
+fn basic_fragment(
+    varyings: &[f32],
+    uniforms: &SoftwareShaderUniforms,
+    outputs: &mut [Vector4<f32>],
+) {
+    let light_dir = Vector3::<f32>::from((0.2, -1.0, 0.4)).normalized();
+    let normal = Vector3::<f32>::from((varyings[0], varyings[1], varyings[2])).normalized();
+    let light_intensity = normal.dot(-light_dir) * 0.5 + 0.5;
+
+    let color = uniforms.get_vector3("u_Color").unwrap();
+    let texture = uniforms
+        .sample("u_Texture", (varyings[3], varyings[4]).into())
+        .unwrap();
+    let color: Vector4<f32> = (color.x, color.y, color.z, 1.0).into();
+    outputs[0] = color * texture * Vector4::<f32>::from(light_intensity);
+}
+
 fn main() {
-    let mut renderer = Surface::new((640, 480).into(), "Test").into_renderer(RendererAPI::OpenGL);
+    let mut renderer = Surface::new((640, 480).into(), "Test")
+        .into_renderer(RendererAPI::OpenGL)
+        .unwrap();
 
     let shader = renderer
-        .create_shader(
-            include_str!("./basic.vert.glsl"),
-            include_str!("./basic.frag.glsl"),
+        .create_shader_from_files(
+            ShaderDescription {
+                inputs: &[
+                    ShaderInput {
+                        name: "a_Position",
+                        typ: ShaderDataType::Float4,
+                    },
+                    ShaderInput {
+                        name: "a_Normal",
+                        typ: ShaderDataType::Float3,
+                    },
+                    ShaderInput {
+                        name: "a_TexCoord",
+                        typ: ShaderDataType::Float2,
+                    },
+                ],
+                uniforms: BUILTIN_UNIFORMS,
+                uniform_blocks: BUILTIN_UNIFORM_BLOCKS,
+                glsl: None,
+                software: Some(SoftwareShaderSource {
+                    vertex: basic_vertex,
+                    fragment: basic_fragment,
+                }),
+            },
+            GlslShaderFiles {
+                vertex: concat!(env!("CARGO_MANIFEST_DIR"), "/src/bin/basic.vert.glsl").as_ref(),
+                fragment: concat!(env!("CARGO_MANIFEST_DIR"), "/src/bin/basic.frag.glsl").as_ref(),
+                preprocessor: GlslPreprocessor::default(),
+            },
         )
         .unwrap();
 
@@ -140,14 +202,16 @@ fn main() {
             tex_coord: (0.0, 0.0).into(),
         },
     ];
-    let vertex_buffer = renderer.create_vertex_buffer(
-        &[
-            VertexBufferElement::Float3,
-            VertexBufferElement::Float3,
-            VertexBufferElement::Float2,
-        ],
-        slice_to_bytes(vertices),
-    );
+    let vertex_buffer = renderer
+        .create_vertex_buffer(
+            &[
+                VertexBufferElement::Float3,
+                VertexBufferElement::Float3,
+                VertexBufferElement::Float2,
+            ],
+            slice_to_bytes(vertices),
+        )
+        .unwrap();
 
     let index_buffer = renderer.create_index_buffer(&[
         2, 1, 0, 3, 2, 0, // front face
@@ -158,6 +222,11 @@ fn main() {
         22, 21, 20, 23, 22, 20, // bottom face
     ]);
 
+    let stars_sampler = SamplerDescription {
+        min_filter: TextureFilter::Trilinear,
+        mipmaps: true,
+        ..Default::default()
+    };
     let stars_texture = match stb_image::image::load_from_memory_with_depth(
         include_bytes!("./stars.png"),
         4,
@@ -166,11 +235,25 @@ fn main() {
         stb_image::image::LoadResult::Error(error) => panic!("{error}"),
         stb_image::image::LoadResult::ImageU8(image) => {
             let pixels = Pixels::RGBA(unsafe { slice_data_cast(&image.data) });
-            renderer.create_texture((image.width, image.height).into(), pixels)
+            renderer
+                .create_texture(
+                    (image.width, image.height).into(),
+                    pixels,
+                    None,
+                    stars_sampler,
+                )
+                .unwrap()
         }
         stb_image::image::LoadResult::ImageF32(image) => {
             let pixels = Pixels::RGBAF(unsafe { slice_data_cast(&image.data) });
-            renderer.create_texture((image.width, image.height).into(), pixels)
+            renderer
+                .create_texture(
+                    (image.width, image.height).into(),
+                    pixels,
+                    None,
+                    stars_sampler,
+                )
+                .unwrap()
         }
     };
 
@@ -178,10 +261,7 @@ fn main() {
         transform: Transform::default(),
         projection_type: CameraProjectionType::Perspective {
             fov: 60.0,
-            aspect: {
-                let (width, height) = renderer.get_surface().get_size().into();
-                width as f32 / height as f32
-            },
+            aspect: Rect::new(Vector2::zero(), renderer.get_surface().get_size()).aspect_ratio(),
             near: 0.001,
             far: 1000.0,
         },
@@ -193,6 +273,7 @@ fn main() {
     };
 
     renderer.get_surface_mut().show();
+    let mut take_screenshot = false;
     let mut fixed_update_time = 0.0;
     let mut last_now = std::time::Instant::now();
     'main_loop: loop {
@@ -204,23 +285,12 @@ fn main() {
             match event {
                 SurfaceEvent::Close => break 'main_loop,
                 SurfaceEvent::Resize(size) => {
-                    renderer.resize(size);
-                    let aspect_ratio = size.x as f32 / size.y as f32;
-                    match &mut camera.projection_type {
-                        CameraProjectionType::None => {}
-                        CameraProjectionType::Orthographic {
-                            left,
-                            right,
-                            top,
-                            bottom,
-                            ..
-                        } => {
-                            *left = -aspect_ratio * *bottom;
-                            *right = aspect_ratio * *top;
-                        }
-                        CameraProjectionType::Perspective { aspect, .. } => *aspect = aspect_ratio,
-                    }
+                    renderer.resize(size).unwrap();
+                    camera
+                        .projection_type
+                        .set_aspect(Rect::new(Vector2::zero(), size).aspect_ratio());
                 }
+                SurfaceEvent::KeyPressed(Keycode::P) => take_screenshot = true,
                 SurfaceEvent::KeyPressed(key) => println!("{key:?} was pressed"),
                 SurfaceEvent::KeyReleased(key) => println!("{key:?} was released"),
                 SurfaceEvent::MousePressed(button, coord) => {
@@ -233,6 +303,13 @@ fn main() {
             }
         }
 
+        for (_, result) in renderer.reload_shaders() {
+            match result {
+                Ok(()) => println!("Reloaded the shader"),
+                Err(error) => println!("Failed to reload the shader:\n{error}"),
+            }
+        }
+
         const FIXED_UPDATE_INTERVAL: f32 = 1.0 / 60.0;
         fixed_update_time += ts;
         while fixed_update_time > FIXED_UPDATE_INTERVAL {
@@ -277,18 +354,47 @@ fn main() {
             cube_transform.rotation.z += 25.0 * ts;
         }
 
-        renderer.clear((0.2, 0.4, 0.8).into());
+        renderer
+            .clear(
+                None,
+                ClearDescription {
+                    color: Some((0.2, 0.4, 0.8, 1.0).into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
         {
-            let mut draw_context = renderer.drawing_context(camera, true, CullFace::Clockwise);
-            draw_context.draw_indexed(
-                PrimitiveType::Triangle,
-                shader,
-                vertex_buffer,
-                index_buffer,
-                Some(stars_texture),
-                cube_transform.into(),
-                Vector3::one(),
-            );
+            let mut draw_context = renderer
+                .drawing_context(
+                    None,
+                    None,
+                    None,
+                    camera,
+                    PipelineState {
+                        depth: Some(DepthState::default()),
+                        ..Default::default()
+                    },
+                    CullFace::Clockwise,
+                )
+                .unwrap();
+            draw_context
+                .draw_indexed(
+                    PrimitiveType::Triangle,
+                    shader,
+                    vertex_buffer,
+                    index_buffer,
+                    Some(stars_texture),
+                    cube_transform.into(),
+                    Vector3::one(),
+                )
+                .unwrap();
+        }
+        if take_screenshot {
+            take_screenshot = false;
+            match renderer.read_pixels().save_png("screenshot.png") {
+                Ok(()) => println!("Saved screenshot.png"),
+                Err(error) => println!("Failed to save screenshot.png: {error}"),
+            }
         }
         renderer.present();
     }
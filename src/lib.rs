@@ -10,6 +10,10 @@ pub fn slice_to_bytes<T>(slice: &[T]) -> &[u8] {
     unsafe { slice_data_cast(slice) }
 }
 
+/// # Safety
+///
+/// Every bit pattern of the bytes in `slice` must be a valid `U`, and the slice must be aligned
+/// and sized so that it can be reinterpreted as a whole number of `U`s
 pub unsafe fn slice_data_cast<U, T>(slice: &[T]) -> &[U] {
     let (start, result, end) = slice.align_to();
     assert_eq!((start.len(), end.len()), (0, 0));
@@ -1,5 +1,6 @@
 use crate::math::{Cos, One, Sin, ToRadians, Vector3, Vector4, Zero};
 
+pub type Matrix3x3<T> = Matrix<T, 3, 3>;
 pub type Matrix4x4<T> = Matrix<T, 4, 4>;
 
 pub struct Matrix<T, const R: usize, const C: usize> {
@@ -135,6 +136,70 @@ impl<T> Matrix4x4<T> {
     {
         Self::rotation_z(degrees.z) * Self::rotation_x(degrees.x) * Self::rotation_y(degrees.y)
     }
+
+    /// Returns `None` if the matrix is singular
+    pub fn inverse(&self) -> Option<Self>
+    where
+        T: Copy
+            + Zero
+            + PartialEq
+            + std::ops::Add<T, Output = T>
+            + std::ops::Sub<T, Output = T>
+            + std::ops::Mul<T, Output = T>
+            + std::ops::Div<T, Output = T>
+            + std::ops::Neg<Output = T>,
+    {
+        let [[a00, a01, a02, a03], [a10, a11, a12, a13], [a20, a21, a22, a23], [a30, a31, a32, a33]] =
+            self.elements;
+
+        let s0 = a00 * a11 - a10 * a01;
+        let s1 = a00 * a12 - a10 * a02;
+        let s2 = a00 * a13 - a10 * a03;
+        let s3 = a01 * a12 - a11 * a02;
+        let s4 = a01 * a13 - a11 * a03;
+        let s5 = a02 * a13 - a12 * a03;
+
+        let c0 = a20 * a31 - a30 * a21;
+        let c1 = a20 * a32 - a30 * a22;
+        let c2 = a20 * a33 - a30 * a23;
+        let c3 = a21 * a32 - a31 * a22;
+        let c4 = a21 * a33 - a31 * a23;
+        let c5 = a22 * a33 - a32 * a23;
+
+        let determinant = s0 * c5 - s1 * c4 + s2 * c3 + s3 * c2 - s4 * c1 + s5 * c0;
+        if determinant == T::zero() {
+            return None;
+        }
+
+        Some(Self {
+            elements: [
+                [
+                    (a11 * c5 - a12 * c4 + a13 * c3) / determinant,
+                    (-a01 * c5 + a02 * c4 - a03 * c3) / determinant,
+                    (a31 * s5 - a32 * s4 + a33 * s3) / determinant,
+                    (-a21 * s5 + a22 * s4 - a23 * s3) / determinant,
+                ],
+                [
+                    (-a10 * c5 + a12 * c2 - a13 * c1) / determinant,
+                    (a00 * c5 - a02 * c2 + a03 * c1) / determinant,
+                    (-a30 * s5 + a32 * s2 - a33 * s1) / determinant,
+                    (a20 * s5 - a22 * s2 + a23 * s1) / determinant,
+                ],
+                [
+                    (a10 * c4 - a11 * c2 + a13 * c0) / determinant,
+                    (-a00 * c4 + a01 * c2 - a03 * c0) / determinant,
+                    (a30 * s4 - a31 * s2 + a33 * s0) / determinant,
+                    (-a20 * s4 + a21 * s2 - a23 * s0) / determinant,
+                ],
+                [
+                    (-a10 * c3 + a11 * c1 - a12 * c0) / determinant,
+                    (a00 * c3 - a01 * c1 + a02 * c0) / determinant,
+                    (-a30 * s3 + a31 * s1 - a32 * s0) / determinant,
+                    (a20 * s3 - a21 * s1 + a22 * s0) / determinant,
+                ],
+            ],
+        })
+    }
 }
 
 impl<T, const R1: usize, const C1R2: usize, const C2: usize> std::ops::Mul<Matrix<T, C1R2, C2>>
@@ -212,3 +277,52 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Matrix4x4<f32>, b: Matrix4x4<f32>) {
+        for row in 0..4 {
+            for column in 0..4 {
+                assert!(
+                    (a[row][column] - b[row][column]).abs() < 1e-5,
+                    "element ({row}, {column}): {} != {}",
+                    a[row][column],
+                    b[row][column]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn inverse_of_identity() {
+        assert_close(
+            Matrix4x4::identity().inverse().unwrap(),
+            Matrix4x4::identity(),
+        );
+    }
+
+    #[test]
+    fn inverse_of_transform() {
+        let scale = Matrix4x4::scale((2.0, 3.0, 0.5).into());
+        let rotation = Matrix4x4::rotation((30.0, 45.0, 60.0).into());
+        let translation = Matrix4x4::translation((1.0, -2.0, 5.0).into());
+        let transform = scale * rotation * translation;
+        let inverse = transform.inverse().unwrap();
+        assert_close(transform * inverse, Matrix4x4::identity());
+        assert_close(inverse * transform, Matrix4x4::identity());
+        assert_close(
+            inverse,
+            translation.inverse().unwrap() * rotation.inverse().unwrap() * scale.inverse().unwrap(),
+        );
+    }
+
+    #[test]
+    fn inverse_of_singular() {
+        assert!(Matrix4x4::<f32>::scale((1.0, 0.0, 1.0).into())
+            .inverse()
+            .is_none());
+        assert!(Matrix4x4::<f32>::default().inverse().is_none());
+    }
+}
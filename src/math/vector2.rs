@@ -239,9 +239,9 @@ impl<T> From<(T, T)> for Vector2<T> {
     }
 }
 
-impl<T> Into<(T, T)> for Vector2<T> {
-    fn into(self) -> (T, T) {
-        (self.x, self.y)
+impl<T> From<Vector2<T>> for (T, T) {
+    fn from(vector: Vector2<T>) -> Self {
+        (vector.x, vector.y)
     }
 }
 
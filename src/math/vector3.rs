@@ -266,9 +266,9 @@ impl<T> From<(T, T, T)> for Vector3<T> {
     }
 }
 
-impl<T> Into<(T, T, T)> for Vector3<T> {
-    fn into(self) -> (T, T, T) {
-        (self.x, self.y, self.z)
+impl<T> From<Vector3<T>> for (T, T, T) {
+    fn from(vector: Vector3<T>) -> Self {
+        (vector.x, vector.y, vector.z)
     }
 }
 
@@ -271,9 +271,9 @@ impl<T> From<(T, T, T, T)> for Vector4<T> {
     }
 }
 
-impl<T> Into<(T, T, T, T)> for Vector4<T> {
-    fn into(self) -> (T, T, T, T) {
-        (self.x, self.y, self.z, self.w)
+impl<T> From<Vector4<T>> for (T, T, T, T) {
+    fn from(vector: Vector4<T>) -> Self {
+        (vector.x, vector.y, vector.z, vector.w)
     }
 }
 
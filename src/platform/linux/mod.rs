@@ -0,0 +1,3 @@
+mod surface;
+
+pub use surface::*;
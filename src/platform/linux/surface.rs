@@ -0,0 +1,446 @@
+#![allow(non_upper_case_globals)]
+
+use std::{ffi::CString, mem::MaybeUninit, pin::Pin};
+
+use enum_map::EnumMap;
+use x11::{
+    keysym::{
+        XK_Alt_L, XK_Alt_R, XK_Control_L, XK_Control_R, XK_Down, XK_Left, XK_Right, XK_Shift_L,
+        XK_Shift_R, XK_Up, XK_a, XK_b, XK_c, XK_d, XK_e, XK_f, XK_g, XK_h, XK_i, XK_j, XK_k, XK_l,
+        XK_m, XK_n, XK_o, XK_p, XK_q, XK_r, XK_s, XK_t, XK_u, XK_v, XK_w, XK_x, XK_y, XK_z, XK_0,
+        XK_1, XK_2, XK_3, XK_4, XK_5, XK_6, XK_7, XK_8, XK_9,
+    },
+    xlib::{
+        Atom, Button1, Button2, Button3, ButtonPress, ButtonPressMask, ButtonRelease,
+        ButtonReleaseMask, CWBackPixel, CWEventMask, ClientMessage, ConfigureNotify,
+        CopyFromParent, Display, InputOutput, KeyPress, KeyPressMask, KeyRelease, KeyReleaseMask,
+        MotionNotify, PointerMotionMask, StructureNotifyMask, Window, XBlackPixel, XCloseDisplay,
+        XCreateImage, XCreateWindow, XDefaultDepth, XDefaultGC, XDefaultScreen, XDefaultVisual,
+        XDestroyWindow, XEvent, XFlush, XFree, XInternAtom, XLookupKeysym, XMapWindow, XNextEvent,
+        XOpenDisplay, XPending, XPutImage, XRootWindow, XSetWMProtocols, XSetWindowAttributes,
+        XStoreName, XUnmapWindow, XkbSetDetectableAutoRepeat, ZPixmap,
+    },
+};
+
+use crate::{
+    math::Vector2,
+    platform::{Keycode, MouseButton, SurfaceEvent},
+    renderer::{new_renderer, Renderer, RendererAPI, RendererError},
+};
+
+pub struct Surface {
+    pub(crate) display: *mut Display,
+    pub(crate) window: Window,
+    wm_delete_window: Atom,
+    size: Vector2<usize>,
+    events: Vec<SurfaceEvent>,
+    key_states: EnumMap<Keycode, bool>,
+    mouse_button_states: EnumMap<MouseButton, bool>,
+    mouse_position: Vector2<isize>,
+}
+
+impl Surface {
+    pub fn new(size: Vector2<usize>, title: &str) -> Pin<Box<Surface>> {
+        let display = unsafe { XOpenDisplay(std::ptr::null()) };
+        if display.is_null() {
+            panic!("Failed to open the X display");
+        }
+
+        // Without this X sends a release before every repeated press, which
+        // would make held keys flicker between pressed and released
+        unsafe { XkbSetDetectableAutoRepeat(display, true as _, std::ptr::null_mut()) };
+
+        let screen = unsafe { XDefaultScreen(display) };
+        let root = unsafe { XRootWindow(display, screen) };
+
+        let mut attributes = XSetWindowAttributes {
+            background_pixel: unsafe { XBlackPixel(display, screen) },
+            event_mask: StructureNotifyMask
+                | KeyPressMask
+                | KeyReleaseMask
+                | ButtonPressMask
+                | ButtonReleaseMask
+                | PointerMotionMask,
+            ..unsafe { MaybeUninit::zeroed().assume_init() }
+        };
+
+        let window = unsafe {
+            XCreateWindow(
+                display,
+                root,
+                100,
+                100,
+                size.x as _,
+                size.y as _,
+                0,
+                CopyFromParent,
+                InputOutput as _,
+                std::ptr::null_mut(),
+                CWBackPixel | CWEventMask,
+                &mut attributes,
+            )
+        };
+        if window == 0 {
+            panic!("Failed to create window");
+        }
+
+        let window_title = CString::new(title).unwrap();
+        unsafe { XStoreName(display, window, window_title.as_ptr()) };
+
+        let mut wm_delete_window =
+            unsafe { XInternAtom(display, c"WM_DELETE_WINDOW".as_ptr(), false as _) };
+        if unsafe { XSetWMProtocols(display, window, &mut wm_delete_window, 1) } == 0 {
+            panic!("Failed to set the window manager protocols");
+        }
+
+        Pin::new(Box::new(Surface {
+            display,
+            window,
+            wm_delete_window,
+            size,
+            events: vec![],
+            key_states: EnumMap::default(),
+            mouse_button_states: EnumMap::default(),
+            mouse_position: (0, 0).into(),
+        }))
+    }
+
+    /// Creates a surface that has no window, renderers created from it draw into an offscreen
+    /// framebuffer of `size` that never changes size. Only Linux has headless surfaces, the opengl
+    /// renderer needs mesa's surfaceless egl platform for them
+    pub fn new_headless(size: Vector2<usize>) -> Pin<Box<Surface>> {
+        Pin::new(Box::new(Surface {
+            display: std::ptr::null_mut(),
+            window: 0,
+            wm_delete_window: 0,
+            size,
+            events: vec![],
+            key_states: EnumMap::default(),
+            mouse_button_states: EnumMap::default(),
+            mouse_position: (0, 0).into(),
+        }))
+    }
+
+    /// Only Linux has headless surfaces
+    pub fn is_headless(&self) -> bool {
+        self.display.is_null()
+    }
+
+    pub fn show(&mut self) {
+        if self.is_headless() {
+            return;
+        }
+        unsafe {
+            XMapWindow(self.display, self.window);
+            XFlush(self.display);
+        }
+    }
+    pub fn hide(&mut self) {
+        if self.is_headless() {
+            return;
+        }
+        unsafe {
+            XUnmapWindow(self.display, self.window);
+            XFlush(self.display);
+        }
+    }
+
+    pub fn get_size(&self) -> Vector2<usize> {
+        self.size
+    }
+
+    pub fn get_key_state(&self, key: Keycode) -> bool {
+        self.key_states[key]
+    }
+
+    pub fn get_mouse_button_state(&self, button: MouseButton) -> bool {
+        self.mouse_button_states[button]
+    }
+
+    pub fn get_mouse_position(&self) -> Vector2<isize> {
+        self.mouse_position
+    }
+
+    pub fn events(&mut self) -> impl Iterator<Item = SurfaceEvent> {
+        unsafe {
+            while !self.is_headless() && XPending(self.display) > 0 {
+                let mut event = XEvent { pad: [0; 24] };
+                XNextEvent(self.display, &mut event);
+                self.handle_event(&mut event);
+            }
+            std::mem::take(&mut self.events).into_iter()
+        }
+    }
+
+    pub fn into_renderer(
+        self: Pin<Box<Surface>>,
+        api: RendererAPI,
+    ) -> Result<Box<dyn Renderer>, RendererError> {
+        new_renderer(self, api)
+    }
+
+    /// Copies pixels packed as `0x00RRGGBB` onto the window, the rows start at the bottom
+    pub(crate) fn present_pixels(&mut self, size: Vector2<usize>, pixels: &[u32]) {
+        assert_eq!(size.x * size.y, pixels.len());
+        if self.is_headless() || pixels.is_empty() {
+            return;
+        }
+
+        // X images start at the top
+        let mut data: Vec<u32> = pixels
+            .chunks_exact(size.x)
+            .rev()
+            .flatten()
+            .copied()
+            .collect();
+        unsafe {
+            let screen = XDefaultScreen(self.display);
+            let image = XCreateImage(
+                self.display,
+                XDefaultVisual(self.display, screen),
+                XDefaultDepth(self.display, screen) as _,
+                ZPixmap,
+                0,
+                data.as_mut_ptr().cast(),
+                size.x as _,
+                size.y as _,
+                32,
+                0,
+            );
+            if image.is_null() {
+                panic!("Failed to create image");
+            }
+            XPutImage(
+                self.display,
+                self.window,
+                XDefaultGC(self.display, screen),
+                image,
+                0,
+                0,
+                0,
+                0,
+                size.x as _,
+                size.y as _,
+            );
+            // The data is owned by `data`, so only the image itself gets freed
+            XFree(image.cast());
+            XFlush(self.display);
+        }
+    }
+
+    unsafe fn handle_event(&mut self, event: &mut XEvent) {
+        match event.get_type() {
+            ClientMessage
+                if event.client_message.data.get_long(0) as Atom == self.wm_delete_window =>
+            {
+                self.events.push(SurfaceEvent::Close);
+            }
+            ConfigureNotify => {
+                let width = event.configure.width;
+                let height = event.configure.height;
+                if width > 0
+                    && height > 0
+                    && Vector2::<usize>::from((width as _, height as _)) != self.size
+                {
+                    self.size = (width as _, height as _).into();
+                    self.events.push(SurfaceEvent::Resize(self.size));
+                }
+            }
+            KeyPress | KeyRelease => 'key_handling: {
+                let pressed = event.get_type() == KeyPress;
+                let keycode = match XLookupKeysym(&mut event.key, 0) as _ {
+                    XK_0 => Keycode::Num0,
+                    XK_1 => Keycode::Num1,
+                    XK_2 => Keycode::Num2,
+                    XK_3 => Keycode::Num3,
+                    XK_4 => Keycode::Num4,
+                    XK_5 => Keycode::Num5,
+                    XK_6 => Keycode::Num6,
+                    XK_7 => Keycode::Num7,
+                    XK_8 => Keycode::Num8,
+                    XK_9 => Keycode::Num9,
+                    XK_a => Keycode::A,
+                    XK_b => Keycode::B,
+                    XK_c => Keycode::C,
+                    XK_d => Keycode::D,
+                    XK_e => Keycode::E,
+                    XK_f => Keycode::F,
+                    XK_g => Keycode::G,
+                    XK_h => Keycode::H,
+                    XK_i => Keycode::I,
+                    XK_j => Keycode::J,
+                    XK_k => Keycode::K,
+                    XK_l => Keycode::L,
+                    XK_m => Keycode::M,
+                    XK_n => Keycode::N,
+                    XK_o => Keycode::O,
+                    XK_p => Keycode::P,
+                    XK_q => Keycode::Q,
+                    XK_r => Keycode::R,
+                    XK_s => Keycode::S,
+                    XK_t => Keycode::T,
+                    XK_u => Keycode::U,
+                    XK_v => Keycode::V,
+                    XK_w => Keycode::W,
+                    XK_x => Keycode::X,
+                    XK_y => Keycode::Y,
+                    XK_z => Keycode::Z,
+                    XK_Left => Keycode::Left,
+                    XK_Up => Keycode::Up,
+                    XK_Right => Keycode::Right,
+                    XK_Down => Keycode::Down,
+                    XK_Control_L | XK_Control_R => Keycode::Control,
+                    XK_Shift_L | XK_Shift_R => Keycode::Shift,
+                    XK_Alt_L | XK_Alt_R => Keycode::Alt,
+                    _ => break 'key_handling,
+                };
+                self.key_states[keycode] = pressed;
+                self.events.push(if pressed {
+                    SurfaceEvent::KeyPressed
+                } else {
+                    SurfaceEvent::KeyReleased
+                }(keycode));
+            }
+            ButtonPress | ButtonRelease => 'mouse_button_handling: {
+                let pressed = event.get_type() == ButtonPress;
+                let button = match event.button.button {
+                    Button1 => MouseButton::Left,
+                    Button2 => MouseButton::Middle,
+                    Button3 => MouseButton::Right,
+                    _ => break 'mouse_button_handling,
+                };
+                let (x, y) = (event.button.x as _, event.button.y as _);
+                self.mouse_button_states[button] = pressed;
+                self.mouse_position = (x, y).into();
+                self.events.push(if pressed {
+                    SurfaceEvent::MousePressed
+                } else {
+                    SurfaceEvent::MouseReleased
+                }(button, (x, y).into()));
+            }
+            MotionNotify => {
+                let (x, y) = (event.motion.x as _, event.motion.y as _);
+                self.mouse_position = (x, y).into();
+                self.events.push(SurfaceEvent::MouseMoved((x, y).into()));
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Drop for Surface {
+    fn drop(&mut self) {
+        if self.is_headless() {
+            return;
+        }
+        unsafe {
+            XDestroyWindow(self.display, self.window);
+            XCloseDisplay(self.display);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::c_long;
+
+    use x11::xlib::{XButtonEvent, XKeyEvent, XKeysymToKeycode, XMotionEvent, XSendEvent, XSync};
+
+    use super::*;
+
+    /// Sends `event` to the window like the X server would, and waits until it arrived
+    unsafe fn send_event(surface: &Surface, mask: c_long, event: impl FnOnce(&mut XEvent)) {
+        let mut x_event = XEvent { pad: [0; 24] };
+        event(&mut x_event);
+        XSendEvent(
+            surface.display,
+            surface.window,
+            false as _,
+            mask,
+            &mut x_event,
+        );
+        XSync(surface.display, false as _);
+    }
+
+    unsafe fn send_key(surface: &Surface, pressed: bool) {
+        send_event(surface, KeyPressMask | KeyReleaseMask, |event| {
+            event.key = XKeyEvent {
+                type_: if pressed { KeyPress } else { KeyRelease },
+                display: surface.display,
+                window: surface.window,
+                keycode: XKeysymToKeycode(surface.display, XK_a as _) as _,
+                same_screen: true as _,
+                ..MaybeUninit::zeroed().assume_init()
+            };
+        });
+    }
+
+    unsafe fn send_button(surface: &Surface, pressed: bool, position: (i32, i32)) {
+        send_event(surface, ButtonPressMask | ButtonReleaseMask, |event| {
+            event.button = XButtonEvent {
+                type_: if pressed { ButtonPress } else { ButtonRelease },
+                display: surface.display,
+                window: surface.window,
+                x: position.0,
+                y: position.1,
+                button: Button1,
+                same_screen: true as _,
+                ..MaybeUninit::zeroed().assume_init()
+            };
+        });
+    }
+
+    #[test]
+    fn key_and_mouse_states() {
+        // Only runs where there is an X server to connect to
+        if std::env::var_os("DISPLAY").is_none() {
+            return;
+        }
+        let mut surface = Surface::new((64, 48).into(), "Test");
+        surface.show();
+
+        unsafe {
+            send_key(&surface, true);
+            send_button(&surface, true, (10, 20));
+            send_event(&surface, PointerMotionMask, |event| {
+                event.motion = XMotionEvent {
+                    type_: MotionNotify,
+                    display: surface.display,
+                    window: surface.window,
+                    x: 30,
+                    y: 40,
+                    same_screen: true as _,
+                    ..MaybeUninit::zeroed().assume_init()
+                };
+            });
+        }
+        let events: Vec<SurfaceEvent> = surface.events().collect();
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, SurfaceEvent::KeyPressed(Keycode::A))));
+        assert!(events.iter().any(|event| matches!(
+            event,
+            SurfaceEvent::MousePressed(MouseButton::Left, position) if *position == (10, 20).into()
+        )));
+        assert!(events.iter().any(|event| matches!(
+            event,
+            SurfaceEvent::MouseMoved(position) if *position == (30, 40).into()
+        )));
+        assert!(surface.get_key_state(Keycode::A));
+        assert!(!surface.get_key_state(Keycode::B));
+        assert!(surface.get_mouse_button_state(MouseButton::Left));
+        assert_eq!(surface.get_mouse_position(), (30, 40).into());
+
+        unsafe {
+            send_key(&surface, false);
+            send_button(&surface, false, (50, 5));
+        }
+        let events: Vec<SurfaceEvent> = surface.events().collect();
+        assert!(events
+            .iter()
+            .any(|event| matches!(event, SurfaceEvent::KeyReleased(Keycode::A))));
+        assert!(!surface.get_key_state(Keycode::A));
+        assert!(!surface.get_mouse_button_state(MouseButton::Left));
+        assert_eq!(surface.get_mouse_position(), (50, 5).into());
+    }
+}
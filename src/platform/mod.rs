@@ -1,6 +1,9 @@
 #[cfg(target_os = "windows")]
 mod windows;
 
+#[cfg(target_os = "linux")]
+mod linux;
+
 mod surface;
 
 pub use surface::*;
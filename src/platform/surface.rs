@@ -6,7 +6,7 @@ use enum_map::Enum;
 #[allow(unused_imports)]
 use crate::{
     math::Vector2,
-    renderer::{new_renderer, Renderer, RendererAPI},
+    renderer::{new_renderer, Renderer, RendererAPI, RendererError},
 };
 
 pub enum SurfaceEvent {
@@ -76,10 +76,13 @@ pub enum Keycode {
 #[cfg(target_os = "windows")]
 pub use crate::platform::windows::*;
 
-#[cfg(not(target_os = "windows"))]
+#[cfg(target_os = "linux")]
+pub use crate::platform::linux::*;
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
 pub struct Surface(PhantomData<()>);
 
-#[cfg(not(target_os = "windows"))]
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
 impl Surface {
     pub fn new(_size: Vector2<usize>, _title: &str) -> Pin<Box<Surface>> {
         unimplemented!()
@@ -112,7 +115,14 @@ impl Surface {
         std::iter::empty()
     }
 
-    pub fn into_renderer(self: Pin<Box<Surface>>, api: RendererAPI) -> Box<dyn Renderer> {
+    pub fn into_renderer(
+        self: Pin<Box<Surface>>,
+        api: RendererAPI,
+    ) -> Result<Box<dyn Renderer>, RendererError> {
         new_renderer(self, api)
     }
+
+    pub(crate) fn present_pixels(&mut self, _size: Vector2<usize>, _pixels: &[u32]) {
+        unimplemented!()
+    }
 }
@@ -10,6 +10,10 @@ use windows::{
     core::PCWSTR,
     Win32::{
         Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, RECT, WPARAM},
+        Graphics::Gdi::{
+            GetDC, ReleaseDC, SetDIBitsToDevice, BITMAPINFO, BITMAPINFOHEADER, BI_RGB,
+            DIB_RGB_COLORS, HDC,
+        },
         System::LibraryLoader::GetModuleHandleW,
         UI::{
             Input::KeyboardAndMouse::{
@@ -35,7 +39,7 @@ use windows::{
 use crate::{
     math::Vector2,
     platform::{Keycode, MouseButton, SurfaceEvent},
-    renderer::{new_renderer, Renderer, RendererAPI},
+    renderer::{new_renderer, Renderer, RendererAPI, RendererError},
 };
 
 pub struct Surface {
@@ -161,9 +165,52 @@ impl Surface {
         }
     }
 
-    pub fn into_renderer(self: Pin<Box<Surface>>, api: RendererAPI) -> Box<dyn Renderer> {
+    pub fn into_renderer(
+        self: Pin<Box<Surface>>,
+        api: RendererAPI,
+    ) -> Result<Box<dyn Renderer>, RendererError> {
         new_renderer(self, api)
     }
+
+    /// Copies pixels packed as `0x00RRGGBB` onto the window, the rows start at the bottom
+    pub(crate) fn present_pixels(&mut self, size: Vector2<usize>, pixels: &[u32]) {
+        assert_eq!(size.x * size.y, pixels.len());
+
+        // A positive height makes the bitmap start at the bottom
+        let bitmap_info = BITMAPINFO {
+            bmiHeader: BITMAPINFOHEADER {
+                biSize: size_of::<BITMAPINFOHEADER>() as _,
+                biWidth: size.x as _,
+                biHeight: size.y as _,
+                biPlanes: 1,
+                biBitCount: 32,
+                biCompression: BI_RGB,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        unsafe {
+            let device_context = GetDC(self.window_handle);
+            if device_context == HDC::default() {
+                panic!("Failed to get device context");
+            }
+            SetDIBitsToDevice(
+                device_context,
+                0,
+                0,
+                size.x as _,
+                size.y as _,
+                0,
+                0,
+                0,
+                size.y as _,
+                pixels.as_ptr().cast(),
+                &bitmap_info,
+                DIB_RGB_COLORS,
+            );
+            ReleaseDC(self.window_handle, device_context);
+        }
+    }
 }
 
 impl Drop for Surface {
@@ -274,8 +321,8 @@ unsafe extern "system" fn window_message_callback(
                 .take((l_param.0 & 0xFF) as _),
             );
         }
-        WM_LBUTTONDOWN | WM_LBUTTONUP | WM_MBUTTONDOWN | WM_MBUTTONUP | WM_RBUTTONDOWN
-        | WM_RBUTTONUP => 'mouse_button_handling: {
+        WM_LBUTTONDOWN | WM_LBUTTONUP | WM_MBUTTONDOWN | WM_MBUTTONUP
+        | WM_RBUTTONDOWN | WM_RBUTTONUP => 'mouse_button_handling: {
             let pressed =
                 message == WM_LBUTTONDOWN || message == WM_MBUTTONDOWN || message == WM_RBUTTONDOWN;
             let key = match message {
@@ -0,0 +1,277 @@
+use std::{fmt, path::PathBuf};
+
+use crate::{
+    math::Vector2,
+    renderer::{
+        FramebufferID, IndexBufferID, MaterialID, PrimitiveType, ShaderDataType, ShaderID,
+        ShaderStage, TextureFormat, TextureID, UniformBufferID, VertexBufferElement,
+        VertexBufferID,
+    },
+};
+
+#[derive(Debug, Clone)]
+pub enum RendererError {
+    /// The message says which step of creating the context failed
+    ContextCreationFailed(String),
+    /// The `ShaderDescription` has no source for the api of the renderer
+    MissingShaderSource,
+    /// The `ShaderDescription` contradicts itself, like declaring an input twice
+    InvalidShaderDescription(String),
+    ShaderFileUnreadable {
+        path: PathBuf,
+        message: String,
+    },
+    /// A glsl source has a broken `#include`
+    ShaderPreprocessingFailed(String),
+    /// The line numbers in `log` point into the original sources and includes
+    ShaderCompilationFailed {
+        stage: ShaderStage,
+        log: String,
+    },
+    ShaderLinkingFailed(String),
+    /// The compiled shader does not match its `ShaderDescription`
+    ShaderInterfaceMismatch(String),
+    UndeclaredUniform(String),
+    UniformTypeMismatch {
+        name: String,
+        declared: ShaderDataType,
+        given: ShaderDataType,
+    },
+    /// An array value has more elements than the uniform, which has an `array_size` of `1` if it
+    /// is not an array
+    UniformArraySizeMismatch {
+        name: String,
+        length: usize,
+        array_size: usize,
+    },
+    /// `element_type` is `None` when neither the vertex buffer nor the instance buffer has an
+    /// element at the location of the input
+    VertexLayoutMismatch {
+        input: String,
+        input_type: ShaderDataType,
+        location: usize,
+        element_type: Option<VertexBufferElement>,
+    },
+    /// The locations of a vertex buffer layout go down or past `MAX_VERTEX_LOCATIONS`, or an
+    /// instance buffer uses the same locations as the vertex buffer
+    InvalidVertexLayout(String),
+    /// The data of a vertex buffer is not a whole number of vertices
+    VertexDataSizeMismatch {
+        stride: usize,
+        length: usize,
+    },
+    InvalidVertexCount {
+        typ: PrimitiveType,
+        count: usize,
+    },
+    /// The instance buffer has fewer vertices than the number of instances that are drawn
+    InstanceCountOutOfBounds {
+        count: usize,
+        available: usize,
+    },
+    /// An index points past the last vertex of the vertex buffer
+    IndexOutOfBounds {
+        index: u32,
+        vertex_count: usize,
+    },
+    /// The software vertex shader pushed a different number of varyings for some vertices
+    VaryingCountMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    InvalidShader(ShaderID),
+    InvalidVertexBuffer(VertexBufferID),
+    InvalidIndexBuffer(IndexBufferID),
+    InvalidUniformBuffer(UniformBufferID),
+    InvalidTexture(TextureID),
+    InvalidFramebuffer(FramebufferID),
+    InvalidMaterial(MaterialID),
+    /// The bindings of `BUILTIN_UNIFORM_BLOCKS` are set by the renderer
+    BuiltinUniformBufferBinding(usize),
+    /// `Std140Writer` was given a texture
+    TextureInUniformBlock,
+    BufferRangeOutOfBounds {
+        offset: usize,
+        length: usize,
+        size: usize,
+    },
+    /// The number of pixels does not match the size they are for
+    PixelCountMismatch {
+        expected: usize,
+        actual: usize,
+    },
+    /// The pixels have a different number of channels or a different precision than the format of
+    /// the texture
+    PixelFormatMismatch(TextureFormat),
+    RegionOutOfBounds {
+        offset: Vector2<usize>,
+        size: Vector2<usize>,
+        texture_size: Vector2<usize>,
+    },
+    AttachmentSizeMismatch {
+        expected: Vector2<usize>,
+        actual: Vector2<usize>,
+    },
+    /// A depth format was used as a color attachment or the other way around
+    InvalidAttachmentFormat(TextureFormat),
+    /// The same texture is attached to a framebuffer more than once
+    DuplicateAttachment(TextureID),
+    FramebufferIncomplete(String),
+    /// The api of the renderer can not share resources with other renderers
+    ResourceSharingUnsupported,
+    /// `DepthRange::ZeroToOne` needs clip control, which the context does not have
+    ClipControlUnsupported,
+    /// The offscreen framebuffer of a headless surface keeps the size the surface was created with
+    HeadlessResizeUnsupported {
+        size: Vector2<usize>,
+        surface_size: Vector2<usize>,
+    },
+}
+
+impl fmt::Display for RendererError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RendererError::ContextCreationFailed(message) => {
+                write!(f, "Failed to create the context: {message}")
+            }
+            RendererError::MissingShaderSource => {
+                write!(f, "The shader has no source for this renderer api")
+            }
+            RendererError::InvalidShaderDescription(message)
+            | RendererError::ShaderPreprocessingFailed(message)
+            | RendererError::ShaderInterfaceMismatch(message)
+            | RendererError::InvalidVertexLayout(message) => write!(f, "{message}"),
+            RendererError::ShaderFileUnreadable { path, message } => {
+                write!(f, "Could not read `{}`: {message}", path.display())
+            }
+            RendererError::ShaderCompilationFailed { stage, log } => {
+                write!(f, "Failed to compile the {stage:?} shader:\n{log}")
+            }
+            RendererError::ShaderLinkingFailed(log) => {
+                write!(f, "Failed to link the shader:\n{log}")
+            }
+            RendererError::UndeclaredUniform(name) => write!(f, "Uniform `{name}` is not declared"),
+            RendererError::UniformTypeMismatch {
+                name,
+                declared,
+                given,
+            } => write!(
+                f,
+                "Uniform `{name}` is declared as a {declared:?} but a {given:?} was given"
+            ),
+            RendererError::UniformArraySizeMismatch {
+                name,
+                length,
+                array_size,
+            } => write!(
+                f,
+                "Uniform `{name}` has room for {array_size} elements but {length} were given"
+            ),
+            RendererError::VertexLayoutMismatch {
+                input,
+                input_type,
+                location,
+                element_type: Some(element_type),
+            } => write!(
+                f,
+                "Input `{input}` is a {input_type:?} but the vertex buffer element at location {location} is a {element_type:?}"
+            ),
+            RendererError::VertexLayoutMismatch {
+                input,
+                location,
+                element_type: None,
+                ..
+            } => write!(
+                f,
+                "Input `{input}` reads location {location} but no vertex buffer element is there"
+            ),
+            RendererError::VertexDataSizeMismatch { stride, length } => write!(
+                f,
+                "{length} bytes of vertex data is not a multiple of the stride of {stride} bytes"
+            ),
+            RendererError::InvalidVertexCount { typ, count } => {
+                write!(f, "{count} vertices can not be drawn as {typ:?}")
+            }
+            RendererError::InstanceCountOutOfBounds { count, available } => write!(
+                f,
+                "{count} instances can not be drawn with an instance buffer of {available} vertices"
+            ),
+            RendererError::IndexOutOfBounds {
+                index,
+                vertex_count,
+            } => write!(
+                f,
+                "Index {index} is out of bounds of a vertex buffer of {vertex_count} vertices"
+            ),
+            RendererError::VaryingCountMismatch { expected, actual } => write!(
+                f,
+                "A vertex has {actual} varyings but the ones before it have {expected}"
+            ),
+            RendererError::InvalidShader(_) => write!(f, "The shader does not exist"),
+            RendererError::InvalidVertexBuffer(_) => write!(f, "The vertex buffer does not exist"),
+            RendererError::InvalidIndexBuffer(_) => write!(f, "The index buffer does not exist"),
+            RendererError::InvalidUniformBuffer(_) => {
+                write!(f, "The uniform buffer does not exist")
+            }
+            RendererError::InvalidTexture(_) => write!(f, "The texture does not exist"),
+            RendererError::InvalidFramebuffer(_) => write!(f, "The framebuffer does not exist"),
+            RendererError::InvalidMaterial(_) => write!(f, "The material does not exist"),
+            RendererError::BuiltinUniformBufferBinding(binding) => {
+                write!(f, "Binding {binding} is used by a builtin uniform block")
+            }
+            RendererError::TextureInUniformBlock => {
+                write!(f, "Textures can not be in a uniform block")
+            }
+            RendererError::BufferRangeOutOfBounds {
+                offset,
+                length,
+                size,
+            } => write!(
+                f,
+                "{length} bytes at offset {offset} are outside of the buffer of {size} bytes"
+            ),
+            RendererError::PixelCountMismatch { expected, actual } => {
+                write!(f, "Expected {expected} pixels but {actual} were given")
+            }
+            RendererError::PixelFormatMismatch(format) => {
+                write!(f, "The pixels do not match the texture format {format:?}")
+            }
+            RendererError::RegionOutOfBounds {
+                offset,
+                size,
+                texture_size,
+            } => write!(
+                f,
+                "The {}x{} region at ({}, {}) is outside of the {}x{} texture",
+                size.x, size.y, offset.x, offset.y, texture_size.x, texture_size.y
+            ),
+            RendererError::AttachmentSizeMismatch { expected, actual } => write!(
+                f,
+                "The attachment is {}x{} but the framebuffer is {}x{}",
+                actual.x, actual.y, expected.x, expected.y
+            ),
+            RendererError::InvalidAttachmentFormat(format) => {
+                write!(f, "A {format:?} texture can not be attached there")
+            }
+            RendererError::DuplicateAttachment(_) => {
+                write!(f, "The texture is attached to the framebuffer more than once")
+            }
+            RendererError::FramebufferIncomplete(message) => {
+                write!(f, "The framebuffer is incomplete: {message}")
+            }
+            RendererError::ResourceSharingUnsupported => {
+                write!(f, "This renderer can not share resources")
+            }
+            RendererError::ClipControlUnsupported => {
+                write!(f, "This renderer can not change the depth range of clip space")
+            }
+            RendererError::HeadlessResizeUnsupported { size, surface_size } => write!(
+                f,
+                "Can not resize the headless surface of size {}x{} to {}x{}",
+                surface_size.x, surface_size.y, size.x, size.y
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RendererError {}
@@ -0,0 +1,82 @@
+use std::collections::HashMap;
+
+use crate::{
+    math::Vector2,
+    renderer::{RendererError, Texture, TextureID},
+    PhantomUnsend, PhantomUnsync,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FramebufferID(
+    pub(crate) usize,
+    pub(crate) PhantomUnsend,
+    pub(crate) PhantomUnsync,
+);
+
+pub enum DepthAttachment {
+    None,
+    /// A depth and stencil buffer that belongs to the framebuffer, it can not be sampled
+    Buffer,
+    /// A texture with a depth format, it has no stencil
+    Texture(TextureID),
+}
+
+pub trait Framebuffer {
+    fn get_id(&self) -> FramebufferID;
+    fn get_size(&self) -> Vector2<usize>;
+    /// Output `i` of the fragment shader is drawn to attachment `i`
+    fn get_color_attachments(&self) -> &[TextureID];
+}
+
+/// Checks that every attachment is a texture of `size` with a color or depth format to match where
+/// it is attached, and that no texture is attached twice
+pub(crate) fn check_attachments<T: Texture>(
+    size: Vector2<usize>,
+    color_attachments: &[TextureID],
+    depth_attachment: &DepthAttachment,
+    textures: &HashMap<TextureID, T>,
+) -> Result<(), RendererError> {
+    let depth_texture = match depth_attachment {
+        DepthAttachment::Texture(id) => Some((*id, true)),
+        DepthAttachment::None | DepthAttachment::Buffer => None,
+    };
+    for (i, (id, is_depth)) in color_attachments
+        .iter()
+        .map(|&id| (id, false))
+        .chain(depth_texture)
+        .enumerate()
+    {
+        if color_attachments[..i.min(color_attachments.len())].contains(&id) {
+            return Err(RendererError::DuplicateAttachment(id));
+        }
+        let texture = textures.get(&id).ok_or(RendererError::InvalidTexture(id))?;
+        if texture.get_size() != size {
+            return Err(RendererError::AttachmentSizeMismatch {
+                expected: size,
+                actual: texture.get_size(),
+            });
+        }
+        if texture.get_format().is_depth() != is_depth {
+            return Err(RendererError::InvalidAttachmentFormat(texture.get_format()));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the attached textures still have the size of the framebuffer, `Texture::set_pixels`
+/// can change it after the framebuffer is created. Attachments that were destroyed are skipped
+pub(crate) fn check_attachment_sizes<T: Texture>(
+    size: Vector2<usize>,
+    attachments: impl IntoIterator<Item = TextureID>,
+    textures: &HashMap<TextureID, T>,
+) -> Result<(), RendererError> {
+    for texture in attachments.into_iter().filter_map(|id| textures.get(&id)) {
+        if texture.get_size() != size {
+            return Err(RendererError::AttachmentSizeMismatch {
+                expected: size,
+                actual: texture.get_size(),
+            });
+        }
+    }
+    Ok(())
+}
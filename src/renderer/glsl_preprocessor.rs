@@ -0,0 +1,385 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// Added as `#define name value` right after the `#version` directive
+#[derive(Clone, Copy)]
+pub struct GlslDefine<'a> {
+    pub name: &'a str,
+    pub value: &'a str,
+}
+
+/// A source that `#include "name"` resolves to without reading a file
+#[derive(Clone, Copy)]
+pub struct GlslInclude<'a> {
+    pub name: &'a str,
+    pub source: &'a str,
+}
+
+/// How the glsl sources are preprocessed before they are compiled, `#include "name"` is looked up
+/// in `includes` first, then next to the file that includes it, then in each of `include_paths`
+#[derive(Clone, Copy, Default)]
+pub struct GlslPreprocessor<'a> {
+    pub defines: &'a [GlslDefine<'a>],
+    pub includes: &'a [GlslInclude<'a>],
+    pub include_paths: &'a [&'a Path],
+}
+
+pub(crate) struct PreprocessedGlsl {
+    pub(crate) source: String,
+    /// The files from disk that were included, in the order they were first included
+    pub(crate) included_files: Vec<PathBuf>,
+    names: Vec<String>,
+    /// The index into `names` and the line number of every line of `source`
+    lines: Vec<(usize, usize)>,
+}
+
+impl PreprocessedGlsl {
+    /// Replaces the line numbers in a compile log, like `0:12` or `0(12)`, with the name of the
+    /// source and the line they came from
+    pub(crate) fn map_log(&self, log: &str) -> String {
+        log.lines()
+            .map(|line| {
+                let Some((start, end, number)) = find_line_number(line) else {
+                    return line.to_string();
+                };
+                let Some(&(name, original)) = number.checked_sub(1).and_then(|i| self.lines.get(i))
+                else {
+                    return line.to_string();
+                };
+                format!(
+                    "{}{}:{original}{}",
+                    &line[..start],
+                    self.names[name],
+                    &line[end..]
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+pub(crate) fn preprocess(
+    name: &str,
+    source: &str,
+    preprocessor: &GlslPreprocessor,
+) -> Result<PreprocessedGlsl, String> {
+    let mut state = State {
+        preprocessor,
+        output: PreprocessedGlsl {
+            source: String::new(),
+            included_files: vec![],
+            names: vec![],
+            lines: vec![],
+        },
+        stack: vec![],
+    };
+    state.expand(name, None, source, true)?;
+    Ok(state.output)
+}
+
+struct State<'a> {
+    preprocessor: &'a GlslPreprocessor<'a>,
+    output: PreprocessedGlsl,
+    /// The names of the sources that are currently being expanded, to catch recursive includes
+    stack: Vec<String>,
+}
+
+impl<'a> State<'a> {
+    fn expand(
+        &mut self,
+        name: &str,
+        directory: Option<&Path>,
+        source: &str,
+        main: bool,
+    ) -> Result<(), String> {
+        let index = self.output.names.len();
+        self.output.names.push(name.to_string());
+        self.stack.push(name.to_string());
+
+        let version_line = source
+            .lines()
+            .position(|line| get_directive(line, "version").is_some());
+        if main && version_line.is_none() {
+            self.push_defines();
+        }
+        for (i, line) in source.lines().enumerate() {
+            let line_number = i + 1;
+            if let Some(arguments) = get_directive(line, "include") {
+                let Some(include) = parse_include_name(arguments) else {
+                    return Err(format!(
+                        "{name}:{line_number}: Expected a file name in quotes after #include"
+                    ));
+                };
+                let Some((include_name, include_directory, include_source)) =
+                    self.resolve(include, directory)
+                else {
+                    return Err(format!(
+                        "{name}:{line_number}: Could not find the include `{include}`"
+                    ));
+                };
+                if self.stack.contains(&include_name) {
+                    return Err(format!(
+                        "{name}:{line_number}: `{include_name}` includes itself"
+                    ));
+                }
+                if include_source
+                    .lines()
+                    .any(|line| get_directive(line, "version").is_some())
+                {
+                    return Err(format!(
+                        "{name}:{line_number}: `{include_name}` can not have a #version directive"
+                    ));
+                }
+                self.expand(
+                    &include_name,
+                    include_directory.as_deref(),
+                    &include_source,
+                    false,
+                )?;
+                continue;
+            }
+
+            self.push_line(line, index, line_number);
+            if main && version_line == Some(i) {
+                self.push_defines();
+            }
+        }
+
+        self.stack.pop();
+        Ok(())
+    }
+
+    /// Finds the name, directory and source of an include
+    fn resolve(
+        &mut self,
+        include: &str,
+        directory: Option<&Path>,
+    ) -> Option<(String, Option<PathBuf>, String)> {
+        if let Some(library) = self
+            .preprocessor
+            .includes
+            .iter()
+            .find(|library| library.name == include)
+        {
+            return Some((include.to_string(), None, library.source.to_string()));
+        }
+
+        let path = directory
+            .into_iter()
+            .chain(self.preprocessor.include_paths.iter().copied())
+            .map(|directory| directory.join(include))
+            .find(|path| path.is_file())?;
+        let source = fs::read_to_string(&path).ok()?;
+        if !self.output.included_files.contains(&path) {
+            self.output.included_files.push(path.clone());
+        }
+        Some((
+            path.display().to_string(),
+            path.parent().map(Path::to_path_buf),
+            source,
+        ))
+    }
+
+    fn push_defines(&mut self) {
+        if self.preprocessor.defines.is_empty() {
+            return;
+        }
+        let index = self.output.names.len();
+        self.output.names.push("<defines>".to_string());
+        for (i, define) in self.preprocessor.defines.iter().enumerate() {
+            let line = format!("#define {} {}", define.name, define.value);
+            self.push_line(&line, index, i + 1);
+        }
+    }
+
+    fn push_line(&mut self, line: &str, index: usize, line_number: usize) {
+        self.output.source.push_str(line);
+        self.output.source.push('\n');
+        self.output.lines.push((index, line_number));
+    }
+}
+
+/// Gets the rest of the line if it is the preprocessor directive `name`
+fn get_directive<'a>(line: &'a str, name: &str) -> Option<&'a str> {
+    let rest = line.trim_start().strip_prefix('#')?.trim_start();
+    let arguments = rest.strip_prefix(name)?;
+    if arguments.is_empty() || arguments.starts_with(|c: char| c.is_whitespace() || c == '"') {
+        Some(arguments.trim())
+    } else {
+        None
+    }
+}
+
+fn parse_include_name(arguments: &str) -> Option<&str> {
+    let name = arguments.strip_prefix('"')?.strip_suffix('"')?;
+    (!name.is_empty()).then_some(name)
+}
+
+/// Finds the first `0:line` or `0(line)` in a line of a compile log, every shader is compiled from
+/// a single string so its number is always 0
+fn find_line_number(line: &str) -> Option<(usize, usize, usize)> {
+    let bytes = line.as_bytes();
+    for start in 0..bytes.len() {
+        if bytes[start] != b'0' || (start > 0 && bytes[start - 1].is_ascii_alphanumeric()) {
+            continue;
+        }
+        let (open, close) = match bytes.get(start + 1) {
+            Some(b':') => (start + 2, None),
+            Some(b'(') => (start + 2, Some(b')')),
+            _ => continue,
+        };
+        let digits = bytes[open..]
+            .iter()
+            .take_while(|byte| byte.is_ascii_digit())
+            .count();
+        if digits == 0 {
+            continue;
+        }
+        let mut end = open + digits;
+        if let Some(close) = close {
+            if bytes.get(end) != Some(&close) {
+                continue;
+            }
+            end += 1;
+        }
+        let number = line[open..open + digits].parse().ok()?;
+        return Some((start, end, number));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INCLUDES: &[GlslInclude] = &[
+        GlslInclude {
+            name: "a",
+            source: "float a;\n#include \"b\"",
+        },
+        GlslInclude {
+            name: "b",
+            source: "float b;",
+        },
+        GlslInclude {
+            name: "loop",
+            source: "#include \"loop2\"",
+        },
+        GlslInclude {
+            name: "loop2",
+            source: "#include \"loop\"",
+        },
+    ];
+
+    const MAIN: &str = "#version 330 core\n#include \"a\"\nvoid main() {}";
+
+    fn preprocessor<'a>(defines: &'a [GlslDefine<'a>]) -> GlslPreprocessor<'a> {
+        GlslPreprocessor {
+            defines,
+            includes: INCLUDES,
+            include_paths: &[],
+        }
+    }
+
+    #[test]
+    fn nested_include() {
+        let glsl = preprocess("main", MAIN, &preprocessor(&[])).unwrap();
+        assert_eq!(
+            glsl.source,
+            "#version 330 core\nfloat a;\nfloat b;\nvoid main() {}\n"
+        );
+        assert!(glsl.included_files.is_empty());
+    }
+
+    #[test]
+    fn include_errors() {
+        let error = preprocess("main", "#include \"loop\"", &preprocessor(&[]))
+            .err()
+            .unwrap();
+        assert_eq!(error, "loop2:1: `loop` includes itself");
+        let error = preprocess("main", "\n#include \"missing\"", &preprocessor(&[]))
+            .err()
+            .unwrap();
+        assert_eq!(error, "main:2: Could not find the include `missing`");
+        let error = preprocess("main", "#include missing", &preprocessor(&[]))
+            .err()
+            .unwrap();
+        assert_eq!(
+            error,
+            "main:1: Expected a file name in quotes after #include"
+        );
+    }
+
+    #[test]
+    fn defines_after_version() {
+        let defines = &[
+            GlslDefine {
+                name: "X",
+                value: "1",
+            },
+            GlslDefine {
+                name: "Y",
+                value: "2",
+            },
+        ];
+        let glsl = preprocess(
+            "main",
+            "// header\n#version 330 core\nvoid main() {}",
+            &preprocessor(defines),
+        )
+        .unwrap();
+        assert_eq!(
+            glsl.source,
+            "// header\n#version 330 core\n#define X 1\n#define Y 2\nvoid main() {}\n"
+        );
+        // Without a `#version` they go first
+        let glsl = preprocess("main", "void main() {}", &preprocessor(defines)).unwrap();
+        assert_eq!(glsl.source, "#define X 1\n#define Y 2\nvoid main() {}\n");
+    }
+
+    #[test]
+    fn log_line_numbers() {
+        let glsl = preprocess("main", MAIN, &preprocessor(&[])).unwrap();
+        // Mesa
+        assert_eq!(
+            glsl.map_log("0:3(7): error: `b' redeclared\n0:4(1): error: syntax error"),
+            "b:1(7): error: `b' redeclared\nmain:3(1): error: syntax error"
+        );
+        // Nvidia
+        assert_eq!(
+            glsl.map_log("0(2) : error C1008: undefined variable \"a\""),
+            "a:1 : error C1008: undefined variable \"a\""
+        );
+        // Lines that are not in the source are left alone
+        assert_eq!(glsl.map_log("0:9(1): error"), "0:9(1): error");
+        assert_eq!(glsl.map_log("error: 10:2 vec20:3"), "error: 10:2 vec20:3");
+    }
+
+    #[test]
+    fn file_includes() {
+        let directory =
+            std::env::temp_dir().join(format!("thallium_glsl_preprocessor_{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("outer.glsl"), "#include \"inner.glsl\"").unwrap();
+        fs::write(directory.join("inner.glsl"), "float inner;").unwrap();
+
+        let glsl = preprocess(
+            "main",
+            "#include \"outer.glsl\"\n#include \"inner.glsl\"",
+            &GlslPreprocessor {
+                defines: &[],
+                includes: &[],
+                include_paths: &[&directory],
+            },
+        );
+        fs::remove_dir_all(&directory).unwrap();
+        let glsl = glsl.unwrap();
+        assert_eq!(glsl.source, "float inner;\nfloat inner;\n");
+        assert_eq!(
+            glsl.included_files,
+            [directory.join("outer.glsl"), directory.join("inner.glsl")]
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use std::{fs::File, io::BufWriter, path::Path};
+
+use crate::math::{Vector2, Vector4};
+
+/// Pixels read back from a renderer, the rows start at the bottom of the image
+pub struct Image {
+    pub size: Vector2<usize>,
+    pub pixels: Vec<Vector4<f32>>,
+}
+
+impl Image {
+    pub fn get_pixel(&self, position: Vector2<usize>) -> Vector4<f32> {
+        self.pixels[position.x + position.y * self.size.x]
+    }
+
+    /// Converts every channel to a byte, values outside of 0 to 1 are clamped
+    pub fn to_rgba8(&self) -> Vec<Vector4<u8>> {
+        self.pixels
+            .iter()
+            .map(|pixel| {
+                let to_byte = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+                (
+                    to_byte(pixel.x),
+                    to_byte(pixel.y),
+                    to_byte(pixel.z),
+                    to_byte(pixel.w),
+                )
+                    .into()
+            })
+            .collect()
+    }
+
+    /// Writes the image as an 8 bit RGBA png
+    pub fn save_png(&self, path: impl AsRef<Path>) -> std::io::Result<()> {
+        let mut encoder = png::Encoder::new(
+            BufWriter::new(File::create(path)?),
+            self.size.x as _,
+            self.size.y as _,
+        );
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+
+        // png rows start at the top
+        let pixels = self.to_rgba8();
+        let data: Vec<u8> = pixels
+            .chunks_exact(self.size.x.max(1))
+            .rev()
+            .flat_map(|row| {
+                row.iter()
+                    .flat_map(|pixel| [pixel.x, pixel.y, pixel.z, pixel.w])
+            })
+            .collect();
+        writer.write_image_data(&data)?;
+        writer.finish()?;
+        Ok(())
+    }
+}
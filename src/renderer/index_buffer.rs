@@ -1,6 +1,6 @@
-use crate::{PhantomUnsend, PhantomUnsync};
+use crate::{renderer::RendererError, PhantomUnsend, PhantomUnsync};
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct IndexBufferID(
     pub(crate) usize,
     pub(crate) PhantomUnsend,
@@ -12,3 +12,16 @@ pub trait IndexBuffer {
     fn get_count(&self) -> usize;
     fn set_indices(&mut self, indices: &[u32]);
 }
+
+pub(crate) fn check_indices(indices: &[u32], vertex_count: usize) -> Result<(), RendererError> {
+    match indices
+        .iter()
+        .find(|&&index| index as usize >= vertex_count)
+    {
+        Some(&index) => Err(RendererError::IndexOutOfBounds {
+            index,
+            vertex_count,
+        }),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,135 @@
+use std::{marker::PhantomData, sync::atomic::AtomicUsize};
+
+use crate::{
+    renderer::{
+        shader::{check_uniform_value, StoredUniformValue},
+        RendererError, Shader, ShaderDataType, ShaderID, TextureID, UniformValue, BUILTIN_UNIFORMS,
+    },
+    PhantomUnsend, PhantomUnsync,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MaterialID(
+    pub(crate) usize,
+    pub(crate) PhantomUnsend,
+    pub(crate) PhantomUnsync,
+);
+
+/// A shader with the textures and uniform values to draw it with, which
+/// `RendererDrawContext::bind_material` sets on the shader all at once
+pub trait Material {
+    fn get_id(&self) -> MaterialID;
+    fn get_shader(&self) -> ShaderID;
+    /// The slots are the uniforms the shader declares as `ShaderDataType::Texture`, except for the
+    /// builtin `u_Texture`. An empty slot gets a texture of a single white pixel
+    fn set_texture(&mut self, slot: &str, texture: Option<TextureID>) -> Result<(), RendererError>;
+    fn get_texture(&self, slot: &str) -> Option<TextureID>;
+    /// Checked against the uniforms the shader declared when the material was created, a
+    /// `UniformValue::Texture` fills the texture slot with the same name
+    fn set_uniform(&mut self, name: &str, value: UniformValue) -> Result<(), RendererError>;
+}
+
+/// The `Material` of every api, it only keeps the values until they are set on the shader
+pub(crate) struct StoredMaterial {
+    id: MaterialID,
+    shader: ShaderID,
+    declared_uniforms: Vec<(String, ShaderDataType)>,
+    textures: Vec<(String, Option<TextureID>)>,
+    values: Vec<(String, StoredUniformValue)>,
+    _send: PhantomUnsend,
+    _sync: PhantomUnsync,
+}
+
+impl StoredMaterial {
+    pub(crate) fn new(
+        shader: ShaderID,
+        declared_uniforms: &[(String, ShaderDataType)],
+    ) -> StoredMaterial {
+        StoredMaterial {
+            id: {
+                static ID: AtomicUsize = AtomicUsize::new(1);
+                MaterialID(
+                    ID.fetch_add(1, std::sync::atomic::Ordering::AcqRel),
+                    PhantomData,
+                    PhantomData,
+                )
+            },
+            shader,
+            declared_uniforms: declared_uniforms.to_vec(),
+            textures: declared_uniforms
+                .iter()
+                .filter(|(name, typ)| {
+                    *typ == ShaderDataType::Texture
+                        && !BUILTIN_UNIFORMS.iter().any(|builtin| builtin.name == name)
+                })
+                .map(|(name, _)| (name.clone(), None))
+                .collect(),
+            values: vec![],
+            _send: PhantomData,
+            _sync: PhantomData,
+        }
+    }
+
+    /// Sets every value and texture on `shader`, which has to be the shader of the material.
+    /// Empty texture slots are set to `empty`
+    pub(crate) fn apply(
+        &self,
+        shader: &mut dyn Shader,
+        empty: TextureID,
+    ) -> Result<(), RendererError> {
+        for (name, value) in &self.values {
+            shader.set_uniform(name, value.as_value())?;
+        }
+        for (slot, texture) in &self.textures {
+            shader.set_uniform(slot, UniformValue::Texture(texture.unwrap_or(empty)))?;
+        }
+        Ok(())
+    }
+}
+
+impl Material for StoredMaterial {
+    fn get_id(&self) -> MaterialID {
+        self.id
+    }
+
+    fn get_shader(&self) -> ShaderID {
+        self.shader
+    }
+
+    fn set_texture(&mut self, slot: &str, texture: Option<TextureID>) -> Result<(), RendererError> {
+        let Some((_, stored)) = self.textures.iter_mut().find(|(name, _)| name == slot) else {
+            return match self.declared_uniforms.iter().find(|(name, _)| name == slot) {
+                Some((_, typ)) if *typ != ShaderDataType::Texture => {
+                    Err(RendererError::UniformTypeMismatch {
+                        name: slot.to_string(),
+                        declared: *typ,
+                        given: ShaderDataType::Texture,
+                    })
+                }
+                _ => Err(RendererError::UndeclaredUniform(slot.to_string())),
+            };
+        };
+        *stored = texture;
+        Ok(())
+    }
+
+    fn get_texture(&self, slot: &str) -> Option<TextureID> {
+        self.textures
+            .iter()
+            .find(|(name, _)| name == slot)
+            .and_then(|(_, texture)| *texture)
+    }
+
+    fn set_uniform(&mut self, name: &str, value: UniformValue) -> Result<(), RendererError> {
+        check_uniform_value(&self.declared_uniforms, name, &value)?;
+        if let UniformValue::Texture(texture) = value {
+            return self.set_texture(name, Some(texture));
+        }
+        let value = StoredUniformValue::from(value);
+        match self.values.iter_mut().find(|(uniform, _)| uniform == name) {
+            Some((_, stored)) => *stored = value,
+            None => self.values.push((name.to_string(), value)),
+        }
+        Ok(())
+    }
+}
@@ -1,12 +1,31 @@
+mod error;
+mod framebuffer;
+mod glsl_preprocessor;
+mod image;
 mod index_buffer;
+mod material;
 mod opengl;
+mod pipeline_state;
+#[allow(clippy::module_inception)]
 mod renderer;
 mod shader;
+mod shader_files;
+mod software;
 mod texture;
+mod uniform_buffer;
 mod vertex_buffer;
 
+pub use error::*;
+pub use framebuffer::*;
+pub use glsl_preprocessor::*;
+pub use image::*;
 pub use index_buffer::*;
+pub use material::*;
+pub use pipeline_state::*;
 pub use renderer::*;
 pub use shader::*;
+pub use shader_files::*;
+pub use software::SoftwareShaderUniforms;
 pub use texture::*;
+pub use uniform_buffer::*;
 pub use vertex_buffer::*;
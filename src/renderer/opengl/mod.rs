@@ -1,11 +1,19 @@
+mod opengl_buffer;
+mod opengl_context;
+mod opengl_framebuffer;
 mod opengl_index_buffer;
 mod opengl_renderer;
 mod opengl_shader;
-mod opengl_vertex_buffer;
 mod opengl_texture;
+mod opengl_uniform_buffer;
+mod opengl_vertex_buffer;
 
+pub(crate) use opengl_buffer::*;
+pub(crate) use opengl_context::*;
+pub(crate) use opengl_framebuffer::*;
 pub(crate) use opengl_index_buffer::*;
 pub(crate) use opengl_renderer::*;
 pub(crate) use opengl_shader::*;
-pub(crate) use opengl_vertex_buffer::*;
 pub(crate) use opengl_texture::*;
+pub(crate) use opengl_uniform_buffer::*;
+pub(crate) use opengl_vertex_buffer::*;
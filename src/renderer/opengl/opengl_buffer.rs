@@ -0,0 +1,70 @@
+use std::{cell::Cell, marker::PhantomData, rc::Rc};
+
+use gl::types::{GLenum, GLuint};
+
+use crate::{renderer::opengl::OpenGLContext, PhantomUnsend, PhantomUnsync};
+
+/// The buffer object behind a vertex, index or uniform buffer, renderers that share the buffer
+/// all point to the same one
+pub(crate) struct OpenGLBuffer {
+    /// The context that created the buffer
+    context: Rc<OpenGLContext>,
+    opengl_id: GLuint,
+    size: Cell<usize>,
+    _send: PhantomUnsend,
+    _sync: PhantomUnsync,
+}
+
+impl OpenGLBuffer {
+    pub(crate) fn new(context: &Rc<OpenGLContext>) -> OpenGLBuffer {
+        let mut buffer = 0;
+        unsafe { gl::GenBuffers(1, &mut buffer) };
+        OpenGLBuffer {
+            context: context.clone(),
+            opengl_id: buffer,
+            size: Cell::new(0),
+            _send: PhantomData,
+            _sync: PhantomData,
+        }
+    }
+
+    pub(crate) fn get_opengl_id(&self) -> GLuint {
+        self.opengl_id
+    }
+
+    /// The size of the data in bytes
+    pub(crate) fn get_size(&self) -> usize {
+        self.size.get()
+    }
+
+    pub(crate) fn set_data(&self, target: GLenum, data: &[u8]) {
+        self.size.set(data.len());
+        unsafe {
+            gl::BindBuffer(target, self.opengl_id);
+            gl::BufferData(
+                target,
+                data.len() as _,
+                data.as_ptr().cast(),
+                gl::DYNAMIC_DRAW,
+            );
+            gl::BindBuffer(target, 0);
+        }
+    }
+
+    /// The range has to be checked to be inside of the buffer
+    pub(crate) fn set_sub_data(&self, target: GLenum, offset: usize, data: &[u8]) {
+        unsafe {
+            gl::BindBuffer(target, self.opengl_id);
+            gl::BufferSubData(target, offset as _, data.len() as _, data.as_ptr().cast());
+            gl::BindBuffer(target, 0);
+        }
+    }
+}
+
+impl Drop for OpenGLBuffer {
+    fn drop(&mut self) {
+        if self.context.make_share_group_current() {
+            unsafe { gl::DeleteBuffers(1, &self.opengl_id) };
+        }
+    }
+}
@@ -0,0 +1,732 @@
+#[allow(unused_imports)]
+use std::{
+    cell::{Cell, RefCell},
+    ffi::{c_int, c_void, CString},
+    marker::PhantomData,
+    mem::{size_of, MaybeUninit},
+    rc::{Rc, Weak},
+    sync::{atomic::AtomicUsize, Mutex},
+};
+
+#[cfg(target_os = "linux")]
+use khronos_egl as egl;
+#[cfg(windows)]
+use widestring::U16CString;
+#[cfg(windows)]
+use windows::{
+    core::{PCSTR, PCWSTR},
+    Win32::{
+        Foundation::HINSTANCE,
+        Graphics::{
+            Gdi::{GetDC, HDC},
+            OpenGL::{
+                wglCreateContext, wglDeleteContext, wglGetProcAddress, wglMakeCurrent,
+                ChoosePixelFormat, SetPixelFormat, SwapBuffers, HGLRC, PFD_DOUBLEBUFFER,
+                PFD_DRAW_TO_WINDOW, PFD_MAIN_PLANE, PFD_SUPPORT_OPENGL, PFD_TYPE_RGBA,
+                PIXELFORMATDESCRIPTOR,
+            },
+        },
+        System::LibraryLoader::{FreeLibrary, GetProcAddress, LoadLibraryW},
+    },
+};
+#[cfg(target_os = "linux")]
+use x11::{
+    glx::{
+        arb::{
+            GLX_CONTEXT_CORE_PROFILE_BIT_ARB, GLX_CONTEXT_MAJOR_VERSION_ARB,
+            GLX_CONTEXT_MINOR_VERSION_ARB, GLX_CONTEXT_PROFILE_MASK_ARB,
+        },
+        glXChooseFBConfig, glXDestroyContext, glXGetFBConfigAttrib, glXGetProcAddress,
+        glXMakeCurrent, glXSwapBuffers, GLXContext, GLXFBConfig, GLX_BLUE_SIZE, GLX_DEPTH_SIZE,
+        GLX_DOUBLEBUFFER, GLX_DRAWABLE_TYPE, GLX_GREEN_SIZE, GLX_RED_SIZE, GLX_RENDER_TYPE,
+        GLX_RGBA_BIT, GLX_STENCIL_SIZE, GLX_VISUAL_ID, GLX_WINDOW_BIT, GLX_X_RENDERABLE,
+    },
+    xlib::{
+        Bool, Display, VisualID, Window, XDefaultScreen, XFree, XGetWindowAttributes,
+        XVisualIDFromVisual, XWindowAttributes,
+    },
+};
+
+#[allow(unused_imports)]
+use crate::{
+    math::Vector2, platform::Surface, renderer::RendererError, PhantomUnsend, PhantomUnsync,
+};
+
+/// An opengl context and the surface it draws to. Every renderer has its own, so before any `gl`
+/// function is called the context it is meant for has to be made current
+pub(crate) struct OpenGLContext {
+    id: usize,
+    platform: PlatformContext,
+    destroyed: Cell<bool>,
+    /// Every context that shares objects with this one, including itself
+    share_group: Rc<RefCell<Vec<Weak<OpenGLContext>>>>,
+    _send: PhantomUnsend,
+    _sync: PhantomUnsync,
+}
+
+thread_local! {
+    /// The id of the context that is current on this thread, 0 if there is none
+    static CURRENT_CONTEXT: Cell<usize> = const { Cell::new(0) };
+}
+
+/// The `gl` functions are global, so they are loaded again whenever a context is made current
+/// that gets them from a different place than the previous one
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum FunctionLoader {
+    #[cfg(target_os = "windows")]
+    Wgl,
+    #[cfg(target_os = "linux")]
+    Glx,
+    #[cfg(target_os = "linux")]
+    Egl,
+}
+
+static LOADED_FUNCTIONS: Mutex<Option<FunctionLoader>> = Mutex::new(None);
+
+fn context_error(message: impl Into<String>) -> RendererError {
+    RendererError::ContextCreationFailed(message.into())
+}
+
+impl OpenGLContext {
+    /// Creates a context for `surface` and makes it current, if `share` is given the two contexts
+    /// can use each other's textures and buffers
+    pub(crate) fn new(
+        surface: &Surface,
+        share: Option<&OpenGLContext>,
+    ) -> Result<Rc<OpenGLContext>, RendererError> {
+        if CURRENT_CONTEXT.get() != 0 {
+            unsafe { gl::Flush() };
+        }
+        // Creating a context can leave any context current when it fails
+        let platform = PlatformContext::new(surface, share.map(|share| &share.platform))
+            .inspect_err(|_| CURRENT_CONTEXT.set(0))?;
+        let share_group = share.map_or_else(Rc::default, |share| share.share_group.clone());
+        let context = Rc::new_cyclic(|context| {
+            let mut contexts = share_group.borrow_mut();
+            contexts.retain(|other| other.strong_count() > 0);
+            contexts.push(context.clone());
+            drop(contexts);
+            OpenGLContext {
+                id: {
+                    static ID: AtomicUsize = AtomicUsize::new(1);
+                    ID.fetch_add(1, std::sync::atomic::Ordering::AcqRel)
+                },
+                platform,
+                destroyed: Cell::new(false),
+                share_group,
+                _send: PhantomData,
+                _sync: PhantomData,
+            }
+        });
+        CURRENT_CONTEXT.set(context.id);
+        context.platform.load_functions();
+        Ok(context)
+    }
+
+    pub(crate) fn make_current(&self) {
+        if self.destroyed.get() || CURRENT_CONTEXT.get() == self.id {
+            return;
+        }
+        // So that changes to shared objects are seen by the context that is switched to
+        if CURRENT_CONTEXT.get() != 0 {
+            unsafe { gl::Flush() };
+        }
+        self.platform.make_current();
+        CURRENT_CONTEXT.set(self.id);
+        self.platform.load_functions();
+    }
+
+    pub(crate) fn is_destroyed(&self) -> bool {
+        self.destroyed.get()
+    }
+
+    /// Makes a context current that can delete the objects this one shares, which is this one
+    /// unless it is destroyed. Returns `false` if every context of the share group is destroyed,
+    /// the objects went away with the last one
+    pub(crate) fn make_share_group_current(&self) -> bool {
+        if !self.destroyed.get() {
+            self.make_current();
+            return true;
+        }
+        let context = self
+            .share_group
+            .borrow()
+            .iter()
+            .filter_map(Weak::upgrade)
+            .find(|context| !context.destroyed.get());
+        match context {
+            Some(context) => {
+                context.make_current();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub(crate) fn present(&self) {
+        self.platform.present();
+    }
+
+    /// Destroys the context, objects that are dropped after this are not deleted by it
+    pub(crate) fn destroy(&self) {
+        if self.destroyed.replace(true) {
+            return;
+        }
+        let current = CURRENT_CONTEXT.get() == self.id;
+        if current {
+            CURRENT_CONTEXT.set(0);
+        }
+        self.platform.destroy(current);
+    }
+}
+
+#[cfg(target_os = "windows")]
+struct PlatformContext {
+    opengl_library: HINSTANCE,
+    device_context: HDC,
+    opengl_context: HGLRC,
+}
+
+#[cfg(target_os = "windows")]
+impl PlatformContext {
+    fn new(
+        surface: &Surface,
+        share: Option<&PlatformContext>,
+    ) -> Result<PlatformContext, RendererError> {
+        let dll_name = U16CString::from_str("opengl32.dll").unwrap();
+        let opengl_library = unsafe { LoadLibraryW(PCWSTR(dll_name.as_ptr())) }
+            .map_err(|error| context_error(format!("Failed to load opengl32.dll: {error}")))?;
+
+        let (device_context, opengl_context) =
+            Self::create_context(surface, share).inspect_err(|_| unsafe {
+                let _ = FreeLibrary(opengl_library);
+            })?;
+
+        Ok(PlatformContext {
+            opengl_library,
+            device_context,
+            opengl_context,
+        })
+    }
+
+    /// `wglCreateContextAttribsARB` can only be loaded with a context current, so a temporary one
+    /// is created first
+    fn create_context(
+        surface: &Surface,
+        share: Option<&PlatformContext>,
+    ) -> Result<(HDC, HGLRC), RendererError> {
+        let device_context = unsafe { GetDC(surface.window_handle) };
+        if device_context == HDC::default() {
+            return Err(context_error("Failed to get device context"));
+        }
+
+        let pixel_format_descriptor = PIXELFORMATDESCRIPTOR {
+            nSize: size_of::<PIXELFORMATDESCRIPTOR>() as _,
+            nVersion: 1,
+            dwFlags: PFD_DRAW_TO_WINDOW | PFD_SUPPORT_OPENGL | PFD_DOUBLEBUFFER,
+            iPixelType: PFD_TYPE_RGBA,
+            cColorBits: 32,
+            cDepthBits: 24,
+            cStencilBits: 8,
+            iLayerType: PFD_MAIN_PLANE,
+            ..Default::default()
+        };
+
+        let format = unsafe { ChoosePixelFormat(device_context, &pixel_format_descriptor) };
+        if format == 0 {
+            return Err(context_error("Could not find format"));
+        }
+
+        if unsafe { SetPixelFormat(device_context, format, &pixel_format_descriptor) } == false {
+            return Err(context_error("Failed to set pixel format"));
+        }
+
+        let temp_opengl_context = unsafe { wglCreateContext(device_context) }.map_err(|error| {
+            context_error(format!("Failed to create temp opengl context: {error}"))
+        })?;
+        if temp_opengl_context == HGLRC::default() {
+            return Err(context_error("Failed to create temp opengl context"));
+        }
+
+        let opengl_context =
+            Self::create_context_with_temp(device_context, temp_opengl_context, share);
+
+        // Deleting the temp context also unbinds it if it is still current
+        if unsafe { wglDeleteContext(temp_opengl_context) } == false {
+            if let Ok(opengl_context) = opengl_context {
+                unsafe {
+                    let _ = wglMakeCurrent(device_context, HGLRC::default());
+                    let _ = wglDeleteContext(opengl_context);
+                }
+            }
+            return Err(context_error("Failed to destroy temp opengl context"));
+        }
+
+        Ok((device_context, opengl_context?))
+    }
+
+    fn create_context_with_temp(
+        device_context: HDC,
+        temp_opengl_context: HGLRC,
+        share: Option<&PlatformContext>,
+    ) -> Result<HGLRC, RendererError> {
+        if unsafe { wglMakeCurrent(device_context, temp_opengl_context) } == false {
+            return Err(context_error("Failed to bind temp opengl context"));
+        }
+
+        #[allow(non_snake_case)]
+        let wglCreateContextAttribsARB: extern "C" fn(
+            hDC: HDC,
+            hshareContext: HGLRC,
+            attribList: *const i32,
+        ) -> HGLRC = unsafe {
+            std::mem::transmute(wglGetProcAddress(PCSTR(
+                b"wglCreateContextAttribsARB\0".as_ptr(),
+            )))
+        };
+
+        let attribs = [
+            0x2091, 3, // WGL_CONTEXT_MAJOR_VERSION_ARB
+            0x2092, 3, // WGL_CONTEXT_MINOR_VERSION_ARB
+            0x9126, 1, // WGL_CONTEXT_PROFILE_MASK_ARB WGL_CONTEXT_CORE_PROFILE_BIT_ARB
+            0,
+        ];
+
+        let opengl_context = wglCreateContextAttribsARB(
+            device_context,
+            share.map_or(HGLRC::default(), |share| share.opengl_context),
+            attribs.as_ptr(),
+        );
+        if opengl_context == HGLRC::default() {
+            return Err(context_error("Failed to create opengl context current"));
+        }
+
+        if unsafe { wglMakeCurrent(device_context, opengl_context) } == false {
+            unsafe {
+                let _ = wglDeleteContext(opengl_context);
+            }
+            return Err(context_error("Failed to bind opengl context"));
+        }
+
+        Ok(opengl_context)
+    }
+
+    fn load_functions(&self) {
+        let mut loaded = LOADED_FUNCTIONS.lock().unwrap();
+        if *loaded == Some(FunctionLoader::Wgl) {
+            return;
+        }
+        let opengl_library = self.opengl_library;
+        gl::load_with(|s| unsafe {
+            let cstr = CString::new(s).unwrap();
+            let mut ptr: *const c_void =
+                std::mem::transmute(wglGetProcAddress(PCSTR(cstr.as_ptr() as _)));
+            if ptr.is_null() {
+                ptr =
+                    std::mem::transmute(GetProcAddress(opengl_library, PCSTR(cstr.as_ptr() as _)));
+            }
+            ptr
+        });
+        *loaded = Some(FunctionLoader::Wgl);
+    }
+
+    fn make_current(&self) {
+        if unsafe { wglMakeCurrent(self.device_context, self.opengl_context) } == false {
+            panic!("Failed to bind opengl context");
+        }
+    }
+
+    fn present(&self) {
+        unsafe { SwapBuffers(self.device_context) };
+    }
+
+    fn destroy(&self, current: bool) {
+        if current && unsafe { wglMakeCurrent(self.device_context, HGLRC::default()) } == false {
+            panic!("Failed to unbind opengl context");
+        }
+        if unsafe { wglDeleteContext(self.opengl_context) } == false {
+            panic!("Failed to destroy opengl context");
+        }
+
+        if unsafe { FreeLibrary(self.opengl_library) } == false {
+            panic!("Unable to unload opengl32.dll");
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+type GLXCreateContextAttribsARB = unsafe extern "C" fn(
+    dpy: *mut Display,
+    config: GLXFBConfig,
+    share_context: GLXContext,
+    direct: Bool,
+    attrib_list: *const c_int,
+) -> GLXContext;
+
+#[cfg(target_os = "linux")]
+enum PlatformContext {
+    Glx {
+        display: *mut Display,
+        window: Window,
+        context: GLXContext,
+    },
+    Egl {
+        display: egl::Display,
+        surface: egl::Surface,
+        context: egl::Context,
+    },
+}
+
+#[cfg(target_os = "linux")]
+const EGL_PLATFORM_SURFACELESS_MESA: egl::Enum = 0x31DD;
+
+#[cfg(target_os = "linux")]
+impl PlatformContext {
+    fn new(
+        surface: &Surface,
+        share: Option<&PlatformContext>,
+    ) -> Result<PlatformContext, RendererError> {
+        match (surface.is_headless(), share) {
+            (true, None | Some(PlatformContext::Egl { .. })) => {
+                let share = share.map(|share| match share {
+                    PlatformContext::Egl { context, .. } => *context,
+                    PlatformContext::Glx { .. } => unreachable!(),
+                });
+                Self::create_egl_context(surface.get_size(), share)
+            }
+            (false, None | Some(PlatformContext::Glx { .. })) => {
+                let share = share.map_or(std::ptr::null_mut(), |share| match share {
+                    PlatformContext::Glx { context, .. } => *context,
+                    PlatformContext::Egl { .. } => unreachable!(),
+                });
+                let context = Self::create_glx_context(surface, share)?;
+                Ok(PlatformContext::Glx {
+                    display: surface.display,
+                    window: surface.window,
+                    context,
+                })
+            }
+            _ => Err(context_error(
+                "A headless surface and a window can not share resources",
+            )),
+        }
+    }
+
+    fn create_glx_context(
+        surface: &Surface,
+        share: GLXContext,
+    ) -> Result<GLXContext, RendererError> {
+        let display = surface.display;
+
+        // The framebuffer config has to use the same visual the window was created with
+        let mut window_attributes =
+            unsafe { MaybeUninit::<XWindowAttributes>::zeroed().assume_init() };
+        if unsafe { XGetWindowAttributes(display, surface.window, &mut window_attributes) } == 0 {
+            return Err(context_error("Failed to get window attributes"));
+        }
+        let visual_id = unsafe { XVisualIDFromVisual(window_attributes.visual) };
+
+        let framebuffer_config_attribs = [
+            GLX_X_RENDERABLE,
+            1,
+            GLX_DRAWABLE_TYPE,
+            GLX_WINDOW_BIT,
+            GLX_RENDER_TYPE,
+            GLX_RGBA_BIT,
+            GLX_DOUBLEBUFFER,
+            1,
+            GLX_RED_SIZE,
+            8,
+            GLX_GREEN_SIZE,
+            8,
+            GLX_BLUE_SIZE,
+            8,
+            GLX_DEPTH_SIZE,
+            24,
+            GLX_STENCIL_SIZE,
+            8,
+            0,
+        ];
+
+        let framebuffer_config = unsafe {
+            let mut count = 0;
+            let framebuffer_configs = glXChooseFBConfig(
+                display,
+                XDefaultScreen(display),
+                framebuffer_config_attribs.as_ptr(),
+                &mut count,
+            );
+            if framebuffer_configs.is_null() {
+                return Err(context_error("Could not find framebuffer config"));
+            }
+            let framebuffer_config = std::slice::from_raw_parts(framebuffer_configs, count as _)
+                .iter()
+                .copied()
+                .find(|&framebuffer_config| {
+                    let mut id = 0;
+                    glXGetFBConfigAttrib(display, framebuffer_config, GLX_VISUAL_ID, &mut id);
+                    id as VisualID == visual_id
+                });
+            XFree(framebuffer_configs.cast());
+            framebuffer_config.ok_or_else(|| {
+                context_error("Could not find framebuffer config matching the window")
+            })?
+        };
+
+        #[allow(non_snake_case)]
+        let glXCreateContextAttribsARB = match unsafe {
+            glXGetProcAddress(c"glXCreateContextAttribsARB".as_ptr().cast())
+        } {
+            Some(function) => unsafe {
+                std::mem::transmute::<unsafe extern "C" fn(), GLXCreateContextAttribsARB>(function)
+            },
+            None => return Err(context_error("glXCreateContextAttribsARB is not supported")),
+        };
+
+        let attribs = [
+            GLX_CONTEXT_MAJOR_VERSION_ARB,
+            3,
+            GLX_CONTEXT_MINOR_VERSION_ARB,
+            3,
+            GLX_CONTEXT_PROFILE_MASK_ARB,
+            GLX_CONTEXT_CORE_PROFILE_BIT_ARB,
+            0,
+        ];
+
+        let opengl_context = unsafe {
+            glXCreateContextAttribsARB(
+                display,
+                framebuffer_config,
+                share,
+                true as _,
+                attribs.as_ptr(),
+            )
+        };
+        if opengl_context.is_null() {
+            return Err(context_error("Failed to create opengl context"));
+        }
+
+        if unsafe { glXMakeCurrent(display, surface.window, opengl_context) } == 0 {
+            unsafe { glXDestroyContext(display, opengl_context) };
+            return Err(context_error("Failed to bind opengl context"));
+        }
+
+        Ok(opengl_context)
+    }
+
+    /// Headless surfaces have no window to draw to, so this renders into a pbuffer on mesa's
+    /// surfaceless platform which does not need a display server or a gpu
+    fn create_egl_context(
+        size: Vector2<usize>,
+        share: Option<egl::Context>,
+    ) -> Result<PlatformContext, RendererError> {
+        let egl = egl::Instance::new(egl::Static);
+
+        let display = unsafe {
+            egl.get_platform_display(
+                EGL_PLATFORM_SURFACELESS_MESA,
+                egl::DEFAULT_DISPLAY,
+                &[egl::ATTRIB_NONE],
+            )
+        }
+        .map_err(|error| {
+            context_error(format!("Failed to get surfaceless egl display: {error}"))
+        })?;
+        egl.initialize(display)
+            .map_err(|error| context_error(format!("Failed to initialize egl display: {error}")))?;
+
+        let config = egl
+            .choose_first_config(
+                display,
+                &[
+                    egl::SURFACE_TYPE,
+                    egl::PBUFFER_BIT,
+                    egl::RENDERABLE_TYPE,
+                    egl::OPENGL_BIT,
+                    egl::RED_SIZE,
+                    8,
+                    egl::GREEN_SIZE,
+                    8,
+                    egl::BLUE_SIZE,
+                    8,
+                    egl::ALPHA_SIZE,
+                    8,
+                    egl::DEPTH_SIZE,
+                    24,
+                    egl::STENCIL_SIZE,
+                    8,
+                    egl::NONE,
+                ],
+            )
+            .map_err(|error| context_error(format!("Failed to choose egl config: {error}")))?
+            .ok_or_else(|| context_error("Could not find egl config"))?;
+
+        let surface = egl
+            .create_pbuffer_surface(
+                display,
+                config,
+                &[egl::WIDTH, size.x as _, egl::HEIGHT, size.y as _, egl::NONE],
+            )
+            .map_err(|error| context_error(format!("Failed to create pbuffer surface: {error}")))?;
+
+        if let Err(error) = egl.bind_api(egl::OPENGL_API) {
+            let _ = egl.destroy_surface(display, surface);
+            return Err(context_error(format!(
+                "Failed to bind the opengl api: {error}"
+            )));
+        }
+        let context = match egl.create_context(
+            display,
+            config,
+            share,
+            &[
+                egl::CONTEXT_MAJOR_VERSION,
+                3,
+                egl::CONTEXT_MINOR_VERSION,
+                3,
+                egl::CONTEXT_OPENGL_PROFILE_MASK,
+                egl::CONTEXT_OPENGL_CORE_PROFILE_BIT,
+                egl::NONE,
+            ],
+        ) {
+            Ok(context) => context,
+            Err(error) => {
+                let _ = egl.destroy_surface(display, surface);
+                return Err(context_error(format!(
+                    "Failed to create opengl context: {error}"
+                )));
+            }
+        };
+
+        if let Err(error) = egl.make_current(display, Some(surface), Some(surface), Some(context)) {
+            let _ = egl.destroy_context(display, context);
+            let _ = egl.destroy_surface(display, surface);
+            return Err(context_error(format!(
+                "Failed to bind opengl context: {error}"
+            )));
+        }
+
+        Ok(PlatformContext::Egl {
+            display,
+            surface,
+            context,
+        })
+    }
+
+    fn load_functions(&self) {
+        let loader = match self {
+            PlatformContext::Glx { .. } => FunctionLoader::Glx,
+            PlatformContext::Egl { .. } => FunctionLoader::Egl,
+        };
+        let mut loaded = LOADED_FUNCTIONS.lock().unwrap();
+        if *loaded == Some(loader) {
+            return;
+        }
+        match self {
+            PlatformContext::Glx { .. } => gl::load_with(|s| unsafe {
+                let cstr = CString::new(s).unwrap();
+                match glXGetProcAddress(cstr.as_ptr().cast()) {
+                    Some(function) => function as *const c_void,
+                    None => std::ptr::null(),
+                }
+            }),
+            PlatformContext::Egl { .. } => {
+                let egl = egl::Instance::new(egl::Static);
+                gl::load_with(|s| match egl.get_proc_address(s) {
+                    Some(function) => function as *const c_void,
+                    None => std::ptr::null(),
+                });
+            }
+        }
+        *loaded = Some(loader);
+    }
+
+    fn make_current(&self) {
+        match *self {
+            PlatformContext::Glx {
+                display,
+                window,
+                context,
+            } => {
+                if unsafe { glXMakeCurrent(display, window, context) } == 0 {
+                    panic!("Failed to bind opengl context");
+                }
+            }
+            PlatformContext::Egl {
+                display,
+                surface,
+                context,
+            } => {
+                let egl = egl::Instance::new(egl::Static);
+                egl.make_current(display, Some(surface), Some(surface), Some(context))
+                    .expect("Failed to bind opengl context");
+            }
+        }
+    }
+
+    fn present(&self) {
+        if let PlatformContext::Glx {
+            display, window, ..
+        } = *self
+        {
+            unsafe { glXSwapBuffers(display, window) };
+        }
+    }
+
+    fn destroy(&self, current: bool) {
+        match *self {
+            PlatformContext::Glx {
+                display, context, ..
+            } => {
+                if current && unsafe { glXMakeCurrent(display, 0, std::ptr::null_mut()) } == 0 {
+                    panic!("Failed to unbind opengl context");
+                }
+                unsafe { glXDestroyContext(display, context) };
+            }
+            PlatformContext::Egl {
+                display,
+                surface,
+                context,
+            } => {
+                let egl = egl::Instance::new(egl::Static);
+                if current {
+                    egl.make_current(display, None, None, None)
+                        .expect("Failed to unbind opengl context");
+                }
+                egl.destroy_context(display, context)
+                    .expect("Failed to destroy opengl context");
+                egl.destroy_surface(display, surface)
+                    .expect("Failed to destroy pbuffer surface");
+            }
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+struct PlatformContext;
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+impl PlatformContext {
+    fn new(
+        _surface: &Surface,
+        _share: Option<&PlatformContext>,
+    ) -> Result<PlatformContext, RendererError> {
+        unimplemented!()
+    }
+
+    fn load_functions(&self) {
+        unimplemented!()
+    }
+
+    fn make_current(&self) {
+        unimplemented!()
+    }
+
+    fn present(&self) {
+        unimplemented!()
+    }
+
+    fn destroy(&self, _current: bool) {
+        unimplemented!()
+    }
+}
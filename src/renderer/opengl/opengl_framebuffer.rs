@@ -0,0 +1,170 @@
+use std::{collections::HashMap, marker::PhantomData, rc::Rc, sync::atomic::AtomicUsize};
+
+use gl::types::GLuint;
+
+use crate::{
+    math::Vector2,
+    renderer::{
+        framebuffer::check_attachments,
+        opengl::{OpenGLContext, OpenGLTexture},
+        DepthAttachment, Framebuffer, FramebufferID, RendererError, TextureID,
+    },
+    PhantomUnsend, PhantomUnsync,
+};
+
+pub(crate) struct OpenGLFramebuffer {
+    id: FramebufferID,
+    context: Rc<OpenGLContext>,
+    opengl_id: GLuint,
+    depth_renderbuffer: Option<GLuint>,
+    depth_texture: Option<TextureID>,
+    size: Vector2<usize>,
+    color_attachments: Vec<TextureID>,
+    _send: PhantomUnsend,
+    _sync: PhantomUnsync,
+}
+
+impl OpenGLFramebuffer {
+    pub(crate) fn new(
+        context: &Rc<OpenGLContext>,
+        size: Vector2<usize>,
+        color_attachments: &[TextureID],
+        depth_attachment: DepthAttachment,
+        textures: &HashMap<TextureID, OpenGLTexture>,
+    ) -> Result<OpenGLFramebuffer, RendererError> {
+        check_attachments(size, color_attachments, &depth_attachment, textures)?;
+        unsafe {
+            let mut framebuffer = 0;
+            gl::GenFramebuffers(1, &mut framebuffer);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, framebuffer);
+
+            for (i, id) in color_attachments.iter().enumerate() {
+                let texture = &textures[id];
+                gl::FramebufferTexture2D(
+                    gl::FRAMEBUFFER,
+                    gl::COLOR_ATTACHMENT0 + i as GLuint,
+                    gl::TEXTURE_2D,
+                    texture.get_opengl_id(),
+                    0,
+                );
+            }
+            let draw_buffers: Vec<_> = (0..color_attachments.len() as GLuint)
+                .map(|i| gl::COLOR_ATTACHMENT0 + i)
+                .collect();
+            gl::DrawBuffers(draw_buffers.len() as _, draw_buffers.as_ptr());
+
+            let depth_renderbuffer = match depth_attachment {
+                DepthAttachment::None => None,
+                DepthAttachment::Buffer => {
+                    let mut renderbuffer = 0;
+                    gl::GenRenderbuffers(1, &mut renderbuffer);
+                    gl::BindRenderbuffer(gl::RENDERBUFFER, renderbuffer);
+                    gl::RenderbufferStorage(
+                        gl::RENDERBUFFER,
+                        gl::DEPTH24_STENCIL8,
+                        size.x as _,
+                        size.y as _,
+                    );
+                    gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+                    gl::FramebufferRenderbuffer(
+                        gl::FRAMEBUFFER,
+                        gl::DEPTH_STENCIL_ATTACHMENT,
+                        gl::RENDERBUFFER,
+                        renderbuffer,
+                    );
+                    Some(renderbuffer)
+                }
+                DepthAttachment::Texture(id) => {
+                    let texture = &textures[&id];
+                    gl::FramebufferTexture2D(
+                        gl::FRAMEBUFFER,
+                        gl::DEPTH_ATTACHMENT,
+                        gl::TEXTURE_2D,
+                        texture.get_opengl_id(),
+                        0,
+                    );
+                    None
+                }
+            };
+
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                gl::DeleteFramebuffers(1, &framebuffer);
+                if let Some(renderbuffer) = depth_renderbuffer {
+                    gl::DeleteRenderbuffers(1, &renderbuffer);
+                }
+                return Err(RendererError::FramebufferIncomplete(format!(
+                    "status {status:#x}"
+                )));
+            }
+
+            Ok(OpenGLFramebuffer {
+                id: {
+                    static ID: AtomicUsize = AtomicUsize::new(1);
+                    FramebufferID(
+                        ID.fetch_add(1, std::sync::atomic::Ordering::AcqRel),
+                        PhantomData,
+                        PhantomData,
+                    )
+                },
+                context: context.clone(),
+                opengl_id: framebuffer,
+                depth_renderbuffer,
+                depth_texture: match depth_attachment {
+                    DepthAttachment::Texture(id) => Some(id),
+                    DepthAttachment::None | DepthAttachment::Buffer => None,
+                },
+                size,
+                color_attachments: color_attachments.to_vec(),
+                _send: PhantomData,
+                _sync: PhantomData,
+            })
+        }
+    }
+
+    /// The color attachments and the depth texture
+    pub(crate) fn get_attached_textures(&self) -> impl Iterator<Item = TextureID> + '_ {
+        self.color_attachments
+            .iter()
+            .copied()
+            .chain(self.depth_texture)
+    }
+
+    pub(crate) fn bind(&mut self) {
+        unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, self.opengl_id) }
+    }
+
+    pub(crate) fn unbind(&mut self) {
+        unsafe { gl::BindFramebuffer(gl::FRAMEBUFFER, 0) }
+    }
+}
+
+impl Drop for OpenGLFramebuffer {
+    fn drop(&mut self) {
+        // Framebuffers are not shared, they go away with the context that made them
+        if !self.context.is_destroyed() {
+            self.context.make_current();
+            unsafe { gl::DeleteFramebuffers(1, &self.opengl_id) };
+        }
+        if let Some(renderbuffer) = self.depth_renderbuffer {
+            if self.context.make_share_group_current() {
+                unsafe { gl::DeleteRenderbuffers(1, &renderbuffer) };
+            }
+        }
+    }
+}
+
+impl Framebuffer for OpenGLFramebuffer {
+    fn get_id(&self) -> FramebufferID {
+        self.id
+    }
+
+    fn get_size(&self) -> Vector2<usize> {
+        self.size
+    }
+
+    fn get_color_attachments(&self) -> &[TextureID] {
+        &self.color_attachments
+    }
+}
@@ -1,47 +1,60 @@
-use std::{marker::PhantomData, mem::size_of, sync::atomic::AtomicUsize};
-
-use gl::types::GLuint;
+use std::{marker::PhantomData, rc::Rc, sync::atomic::AtomicUsize};
 
 use crate::{
-    renderer::{IndexBuffer, IndexBufferID},
-    PhantomUnsend, PhantomUnsync,
+    renderer::{
+        opengl::{OpenGLBuffer, OpenGLContext},
+        IndexBuffer, IndexBufferID,
+    },
+    slice_to_bytes, PhantomUnsend, PhantomUnsync,
 };
 
 pub(crate) struct OpenGLIndexBuffer {
     id: IndexBufferID,
-    opengl_id: GLuint,
-    count: usize,
+    context: Rc<OpenGLContext>,
+    buffer: Rc<OpenGLBuffer>,
     _send: PhantomUnsend,
     _sync: PhantomUnsync,
 }
 
 impl OpenGLIndexBuffer {
-    pub(crate) fn new(indices: &[u32]) -> OpenGLIndexBuffer {
-        unsafe {
-            let mut index_buffer = 0;
-            gl::GenBuffers(1, &mut index_buffer);
+    pub(crate) fn new(context: &Rc<OpenGLContext>, indices: &[u32]) -> OpenGLIndexBuffer {
+        let mut index_buffer = OpenGLIndexBuffer::from_shared(
+            {
+                static ID: AtomicUsize = AtomicUsize::new(1);
+                IndexBufferID(
+                    ID.fetch_add(1, std::sync::atomic::Ordering::AcqRel),
+                    PhantomData,
+                    PhantomData,
+                )
+            },
+            context,
+            Rc::new(OpenGLBuffer::new(context)),
+        );
+        index_buffer.set_indices(indices);
+        index_buffer
+    }
 
-            let mut index_buffer = OpenGLIndexBuffer {
-                id: {
-                    static ID: AtomicUsize = AtomicUsize::new(1);
-                    IndexBufferID(
-                        ID.fetch_add(1, std::sync::atomic::Ordering::AcqRel),
-                        PhantomData,
-                        PhantomData,
-                    )
-                },
-                opengl_id: index_buffer,
-                count: 0,
-                _send: PhantomData,
-                _sync: PhantomData,
-            };
-            index_buffer.set_indices(indices);
-            index_buffer
+    /// An index buffer of another renderer in the same share group
+    pub(crate) fn from_shared(
+        id: IndexBufferID,
+        context: &Rc<OpenGLContext>,
+        buffer: Rc<OpenGLBuffer>,
+    ) -> OpenGLIndexBuffer {
+        OpenGLIndexBuffer {
+            id,
+            context: context.clone(),
+            buffer,
+            _send: PhantomData,
+            _sync: PhantomData,
         }
     }
 
+    pub(crate) fn get_buffer(&self) -> &Rc<OpenGLBuffer> {
+        &self.buffer
+    }
+
     pub(crate) fn bind(&mut self) {
-        unsafe { gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.opengl_id) };
+        unsafe { gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.buffer.get_opengl_id()) };
     }
 
     pub(crate) fn unbind(&mut self) {
@@ -49,32 +62,18 @@ impl OpenGLIndexBuffer {
     }
 }
 
-impl Drop for OpenGLIndexBuffer {
-    fn drop(&mut self) {
-        unsafe { gl::DeleteBuffers(1, &self.opengl_id) };
-    }
-}
-
 impl IndexBuffer for OpenGLIndexBuffer {
     fn get_id(&self) -> IndexBufferID {
         self.id
     }
 
     fn get_count(&self) -> usize {
-        self.count
+        self.buffer.get_size() / size_of::<u32>()
     }
 
     fn set_indices(&mut self, indices: &[u32]) {
-        self.bind();
-        self.count = indices.len();
-        unsafe {
-            gl::BufferData(
-                gl::ELEMENT_ARRAY_BUFFER,
-                (indices.len() * size_of::<u32>()) as _,
-                indices.as_ptr().cast(),
-                gl::DYNAMIC_DRAW,
-            )
-        };
-        self.unbind();
+        self.context.make_current();
+        self.buffer
+            .set_data(gl::ELEMENT_ARRAY_BUFFER, slice_to_bytes(indices));
     }
 }
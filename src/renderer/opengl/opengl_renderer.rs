@@ -1,237 +1,202 @@
-#[allow(unused_imports)]
 use std::{
-    collections::HashMap,
-    ffi::{c_void, CStr, CString},
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    ffi::{c_void, CStr},
+    hash::Hash,
     marker::PhantomData,
-    mem::size_of,
     pin::Pin,
-    sync::atomic::AtomicBool,
+    rc::{Rc, Weak},
 };
 
-use lazy_static::lazy_static;
-#[cfg(windows)]
-use widestring::U16CString;
-#[cfg(windows)]
-use windows::{
-    core::{PCSTR, PCWSTR},
-    Win32::{
-        Foundation::HINSTANCE,
-        Graphics::{
-            Gdi::{GetDC, HDC},
-            OpenGL::{
-                wglCreateContext, wglDeleteContext, wglGetProcAddress, wglMakeCurrent,
-                ChoosePixelFormat, SetPixelFormat, SwapBuffers, HGLRC, PFD_DOUBLEBUFFER,
-                PFD_DRAW_TO_WINDOW, PFD_MAIN_PLANE, PFD_SUPPORT_OPENGL, PFD_TYPE_RGBA,
-                PIXELFORMATDESCRIPTOR,
-            },
-        },
-        System::LibraryLoader::{FreeLibrary, GetProcAddress, LoadLibraryW},
-    },
-};
+use gl::types::{GLchar, GLenum};
 
 use crate::{
-    math::{Matrix4x4, Vector2, Vector3},
+    math::{Matrix4x4, Vector2, Vector3, Vector4, Zero},
     platform::Surface,
     renderer::{
-        opengl::{OpenGLShader, OpenGLTexture, OpenGLVertexBuffer},
-        CullFace, IndexBuffer, IndexBufferID, Pixels, PrimitiveType, Renderer, RendererDrawContext,
-        Shader, ShaderID, Texture, TextureID, VertexBuffer, VertexBufferElement, VertexBufferID,
+        framebuffer::check_attachment_sizes,
+        material::StoredMaterial,
+        opengl::{
+            OpenGLBuffer, OpenGLContext, OpenGLFramebuffer, OpenGLIndexBuffer, OpenGLShader,
+            OpenGLTexture, OpenGLTextureData, OpenGLUniformBuffer, OpenGLVertexBuffer,
+            OpenGLVertexData,
+        },
+        renderer::check_vertex_count,
+        shader::check_vertex_layout,
+        shader_files::WatchedShader,
+        uniform_buffer::get_camera_block_data,
+        vertex_buffer::check_instance_buffer,
+        BlendMode, ClearDescription, ColorMask, CompareFunction, CullFace, DepthAttachment,
+        Framebuffer, FramebufferID, GlslShaderFiles, Image, IndexBuffer, IndexBufferID, Material,
+        MaterialID, PipelineState, Pixels, PrimitiveType, Rect, Renderer, RendererDrawContext,
+        RendererError, SamplerDescription, Shader, ShaderDescription, ShaderID, StencilOperation,
+        Texture, TextureFormat, TextureID, UniformBuffer, UniformBufferID, UniformValue,
+        VertexBuffer, VertexBufferElement, VertexBufferID, BUILTIN_UNIFORM_BLOCKS,
     },
-    scene::Camera,
+    scene::{Camera, DepthConvention, DepthRange},
     PhantomUnsend, PhantomUnsync,
 };
 
-use super::OpenGLIndexBuffer;
-
 pub(crate) struct OpenGLRenderer {
     surface: Option<Pin<Box<Surface>>>,
-    #[cfg(target_os = "windows")]
-    opengl_library: HINSTANCE,
-    #[cfg(target_os = "windows")]
-    device_context: HDC,
-    #[cfg(target_os = "windows")]
-    opengl_context: HGLRC,
+    context: Rc<OpenGLContext>,
+    share_group: Rc<OpenGLShareGroup>,
     shaders: HashMap<ShaderID, OpenGLShader>,
+    watched_shaders: HashMap<ShaderID, WatchedShader>,
     vertex_buffers: HashMap<VertexBufferID, OpenGLVertexBuffer>,
     index_buffers: HashMap<IndexBufferID, OpenGLIndexBuffer>,
     textures: HashMap<TextureID, OpenGLTexture>,
-    default_white_pixel: OpenGLTexture,
+    materials: HashMap<MaterialID, StoredMaterial>,
+    framebuffers: HashMap<FramebufferID, OpenGLFramebuffer>,
+    uniform_buffers: HashMap<UniformBufferID, OpenGLUniformBuffer>,
+    /// `None` once the renderer is destroyed, like the camera uniform buffer
+    default_white_pixel: Option<OpenGLTexture>,
+    /// Holds the builtin `Camera` uniform block, it is filled in by every drawing context
+    camera_uniform_buffer: Option<OpenGLUniformBuffer>,
+    depth_convention: DepthConvention,
     _send: PhantomUnsend,
     _sync: PhantomUnsync,
 }
 
-lazy_static! {
-    static ref CONTEXT_CREATED: AtomicBool = AtomicBool::new(false);
+/// The textures and buffers of renderers whose contexts share objects, so that one renderer can
+/// pick up what another one created by its id
+#[derive(Default)]
+struct OpenGLShareGroup {
+    textures: RefCell<HashMap<TextureID, Weak<OpenGLTextureData>>>,
+    vertex_buffers: RefCell<HashMap<VertexBufferID, Weak<OpenGLVertexData>>>,
+    index_buffers: RefCell<HashMap<IndexBufferID, Weak<OpenGLBuffer>>>,
+    uniform_buffers: RefCell<HashMap<UniformBufferID, Weak<OpenGLBuffer>>>,
 }
 
-#[cfg(target_os = "windows")]
-impl OpenGLRenderer {
-    pub(crate) fn new(surface: Pin<Box<Surface>>) -> OpenGLRenderer {
-        if CONTEXT_CREATED.swap(true, std::sync::atomic::Ordering::AcqRel) {
-            panic!(
-                "Can only create 1 opengl context at a time, current limitation of the gl crate"
-            );
-        }
-
-        let dll_name = U16CString::from_str("opengl32.dll").unwrap();
-        let opengl_library = unsafe { LoadLibraryW(PCWSTR(dll_name.as_ptr())) }.unwrap();
-
-        let device_context = unsafe { GetDC(surface.window_handle) };
-        if device_context == HDC::default() {
-            panic!("Failed to get device context");
-        }
-
-        let pixel_format_descriptor = PIXELFORMATDESCRIPTOR {
-            nSize: size_of::<PIXELFORMATDESCRIPTOR>() as _,
-            nVersion: 1,
-            dwFlags: PFD_DRAW_TO_WINDOW | PFD_SUPPORT_OPENGL | PFD_DOUBLEBUFFER,
-            iPixelType: PFD_TYPE_RGBA,
-            cColorBits: 32,
-            cDepthBits: 24,
-            cStencilBits: 8,
-            iLayerType: PFD_MAIN_PLANE,
-            ..Default::default()
-        };
-
-        let format = unsafe { ChoosePixelFormat(device_context, &pixel_format_descriptor) };
-        if format == 0 {
-            panic!("Could not find format");
-        }
-
-        if unsafe { SetPixelFormat(device_context, format, &pixel_format_descriptor) } == false {
-            panic!("Failed to set pixel format");
-        }
-
-        let temp_opengl_context = unsafe { wglCreateContext(device_context) }.unwrap();
-        if temp_opengl_context == HGLRC::default() {
-            panic!("Failed to create temp opengl context current");
-        }
-
-        if unsafe { wglMakeCurrent(device_context, temp_opengl_context) } == false {
-            panic!("Failed to bind temp opengl context");
+impl OpenGLShareGroup {
+    /// Forgets the objects that no renderer has anymore
+    fn forget_dropped(&self) {
+        fn forget<K, T>(objects: &RefCell<HashMap<K, Weak<T>>>) {
+            objects
+                .borrow_mut()
+                .retain(|_, object| object.strong_count() > 0);
         }
+        forget(&self.textures);
+        forget(&self.vertex_buffers);
+        forget(&self.index_buffers);
+        forget(&self.uniform_buffers);
+    }
+}
 
-        #[allow(non_snake_case)]
-        let wglCreateContextAttribsARB: extern "C" fn(
-            hDC: HDC,
-            hshareContext: HGLRC,
-            attribList: *const i32,
-        ) -> HGLRC = unsafe {
-            std::mem::transmute(wglGetProcAddress(PCSTR(
-                b"wglCreateContextAttribsARB\0".as_ptr(),
-            )))
-        };
+/// Finds an object that is still alive in one of the maps of `OpenGLShareGroup`
+fn find_shared<K: Eq + Hash, T>(objects: &RefCell<HashMap<K, Weak<T>>>, id: &K) -> Option<Rc<T>> {
+    objects.borrow().get(id).and_then(Weak::upgrade)
+}
 
-        let attribs = [
-            0x2091, 3, // WGL_CONTEXT_MAJOR_VERSION_ARB
-            0x2092, 3, // WGL_CONTEXT_MINOR_VERSION_ARB
-            0x9126, 1, // WGL_CONTEXT_PROFILE_MASK_ARB WGL_CONTEXT_CORE_PROFILE_BIT_ARB
-            0,
-        ];
+/// Forgets an object once no renderer has it anymore
+fn forget_if_dropped<K: Eq + Hash, T>(objects: &RefCell<HashMap<K, Weak<T>>>, id: &K) {
+    let mut objects = objects.borrow_mut();
+    if objects
+        .get(id)
+        .is_some_and(|object| object.strong_count() == 0)
+    {
+        objects.remove(id);
+    }
+}
 
-        let opengl_context =
-            wglCreateContextAttribsARB(device_context, HGLRC::default(), attribs.as_ptr());
-        if opengl_context == HGLRC::default() {
-            panic!("Failed to create opengl context current");
-        }
+/// Bound for the texture uniforms that are not set, the context has to be current
+fn create_default_white_pixel(context: &Rc<OpenGLContext>) -> OpenGLTexture {
+    OpenGLTexture::new(
+        context,
+        (1, 1).into(),
+        Pixels::RGBA(&[(255, 255, 255, 255).into()]),
+        None,
+        SamplerDescription::default(),
+    )
+    .expect("A single white pixel is a valid texture")
+}
 
-        if unsafe { wglMakeCurrent(device_context, opengl_context) } == false {
-            panic!("Failed to bind opengl context");
+/// Sets up the state every new context starts with, the context has to be current
+unsafe fn initialize_context_state() {
+    extern "system" fn message_callback(
+        source: u32,
+        _typ: u32,
+        _id: u32,
+        _severity: u32,
+        _length: i32,
+        message: *const GLchar,
+        _user_param: *mut c_void,
+    ) {
+        // Compile errors are already returned by `compile_shader`, and a shader that fails to
+        // reload must not take down the program
+        if source == gl::DEBUG_SOURCE_SHADER_COMPILER {
+            return;
         }
-
-        if unsafe { wglDeleteContext(temp_opengl_context) } == false {
-            panic!("Failed to destroy temp opengl context");
+        let str = unsafe { CStr::from_ptr(message) }.to_str().unwrap();
+        if cfg!(debug_assertions) {
+            panic!("{str}");
+        } else {
+            eprintln!("{str}");
         }
+    }
 
-        gl::load_with(|s| unsafe {
-            let cstr = CString::new(s).unwrap();
-            let mut ptr: *const c_void =
-                std::mem::transmute(wglGetProcAddress(PCSTR(cstr.as_ptr() as _)));
-            if ptr.is_null() {
-                ptr =
-                    std::mem::transmute(GetProcAddress(opengl_library, PCSTR(cstr.as_ptr() as _)));
-            }
-            ptr
-        });
-
-        unsafe {
-            extern "system" fn message_callback(
-                _source: u32,
-                _typ: u32,
-                _id: u32,
-                _severity: u32,
-                _length: i32,
-                message: *const i8,
-                _user_param: *mut c_void,
-            ) {
-                let str = unsafe { CStr::from_ptr(message) }.to_str().unwrap();
-                if cfg!(debug_assertions) {
-                    panic!("{str}");
-                } else {
-                    eprintln!("{str}");
-                }
-            }
+    gl::Enable(gl::DEBUG_OUTPUT);
+    gl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
+    gl::DebugMessageCallback(Some(message_callback), std::ptr::null());
+    gl::DebugMessageControl(
+        gl::DONT_CARE,
+        gl::DONT_CARE,
+        gl::DEBUG_SEVERITY_NOTIFICATION,
+        0,
+        std::ptr::null(),
+        false as _,
+    );
+}
 
-            gl::Enable(gl::DEBUG_OUTPUT);
-            gl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
-            gl::DebugMessageCallback(Some(message_callback), std::ptr::null());
-            gl::DebugMessageControl(
-                gl::DONT_CARE,
-                gl::DONT_CARE,
-                gl::DEBUG_SEVERITY_NOTIFICATION,
-                0,
-                std::ptr::null(),
-                false as _,
-            );
-        }
+impl OpenGLRenderer {
+    pub(crate) fn new(surface: Pin<Box<Surface>>) -> Result<OpenGLRenderer, RendererError> {
+        Self::new_in_share_group(surface, None, Rc::default())
+    }
 
-        unsafe {
-            gl::Enable(gl::BLEND);
-            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
-        }
+    fn new_in_share_group(
+        surface: Pin<Box<Surface>>,
+        share: Option<&OpenGLContext>,
+        share_group: Rc<OpenGLShareGroup>,
+    ) -> Result<OpenGLRenderer, RendererError> {
+        let context = OpenGLContext::new(&surface, share)?;
+        unsafe { initialize_context_state() };
 
-        OpenGLRenderer {
+        Ok(OpenGLRenderer {
             surface: Some(surface),
-            opengl_library,
-            device_context,
-            opengl_context,
+            share_group,
             shaders: HashMap::new(),
+            watched_shaders: HashMap::new(),
             vertex_buffers: HashMap::new(),
             index_buffers: HashMap::new(),
             textures: HashMap::new(),
-            default_white_pixel: OpenGLTexture::new(
-                (1, 1).into(),
-                Pixels::RGBA(&[(255, 255, 255, 255).into()]),
-            ),
+            materials: HashMap::new(),
+            framebuffers: HashMap::new(),
+            uniform_buffers: HashMap::new(),
+            default_white_pixel: Some(create_default_white_pixel(&context)),
+            camera_uniform_buffer: Some(OpenGLUniformBuffer::new(&context, &[])),
+            depth_convention: DepthConvention::default(),
+            context,
             _send: PhantomData,
             _sync: PhantomData,
-        }
-    }
-
-    fn destroy(&mut self) {
-        if unsafe { wglMakeCurrent(self.device_context, HGLRC::default()) } == false {
-            panic!("Failed to unbind opengl context");
-        }
-        if unsafe { wglDeleteContext(self.opengl_context) } == false {
-            panic!("Failed to destroy opengl context");
-        }
-
-        if unsafe { FreeLibrary(self.opengl_library) } == false {
-            panic!("Unable to unload opengl32.dll");
-        }
-
-        CONTEXT_CREATED.store(false, std::sync::atomic::Ordering::Release);
-    }
-}
-
-#[cfg(not(target_os = "windows"))]
-impl OpenGLRenderer {
-    pub(crate) fn new(_surface: Pin<Box<Surface>>) -> OpenGLRenderer {
-        unimplemented!()
+        })
     }
 
+    /// Deletes the objects of this renderer before the context goes away, the ones that are
+    /// shared stay alive for the other renderers
     fn destroy(&mut self) {
-        unimplemented!()
+        self.context.make_current();
+        self.shaders.clear();
+        self.watched_shaders.clear();
+        self.framebuffers.clear();
+        self.vertex_buffers.clear();
+        self.index_buffers.clear();
+        self.textures.clear();
+        self.materials.clear();
+        self.uniform_buffers.clear();
+        self.default_white_pixel = None;
+        self.camera_uniform_buffer = None;
+        self.share_group.forget_dropped();
+        self.context.destroy();
     }
 }
 
@@ -257,19 +222,60 @@ impl Renderer for OpenGLRenderer {
         self.surface.take().unwrap()
     }
 
-    fn create_shader(
+    fn create_shared_renderer(
+        &self,
+        surface: Pin<Box<Surface>>,
+    ) -> Result<Box<dyn Renderer>, RendererError> {
+        Ok(Box::new(OpenGLRenderer::new_in_share_group(
+            surface,
+            Some(&self.context),
+            self.share_group.clone(),
+        )?))
+    }
+
+    fn create_shader(&mut self, description: ShaderDescription) -> Result<ShaderID, RendererError> {
+        self.context.make_current();
+        let shader = OpenGLShader::new(&self.context, &description)?;
+        let id = shader.get_id();
+        assert!(self.shaders.insert(id, shader).is_none());
+        Ok(id)
+    }
+
+    fn create_shader_from_files(
         &mut self,
-        vertex_shader_source: &str,
-        fragment_shader_source: &str,
-    ) -> Result<ShaderID, String> {
-        let shader = OpenGLShader::new(vertex_shader_source, fragment_shader_source)?;
+        description: ShaderDescription,
+        files: GlslShaderFiles,
+    ) -> Result<ShaderID, RendererError> {
+        self.context.make_current();
+        let mut watched_shader = WatchedShader::new(&description, files);
+        let shader = watched_shader
+            .with_description(|description| OpenGLShader::new(&self.context, description))?;
         let id = shader.get_id();
         assert!(self.shaders.insert(id, shader).is_none());
+        self.watched_shaders.insert(id, watched_shader);
         Ok(id)
     }
 
+    fn reload_shaders(&mut self) -> Vec<(ShaderID, Result<(), RendererError>)> {
+        self.context.make_current();
+        let mut results = vec![];
+        for (&id, watched_shader) in &mut self.watched_shaders {
+            if !watched_shader.has_changed() {
+                continue;
+            }
+            let shader = self.shaders.get_mut(&id).unwrap();
+            results.push((
+                id,
+                watched_shader.with_description(|description| shader.reload(description)),
+            ));
+        }
+        results
+    }
+
     fn destroy_shader(&mut self, id: ShaderID) {
+        self.context.make_current();
         self.shaders.remove(&id);
+        self.watched_shaders.remove(&id);
     }
 
     fn get_shader(&self, id: ShaderID) -> Option<&dyn Shader> {
@@ -286,15 +292,34 @@ impl Renderer for OpenGLRenderer {
         &mut self,
         layout: &[VertexBufferElement],
         data: &[u8],
-    ) -> VertexBufferID {
-        let vertex_buffer = OpenGLVertexBuffer::new(layout, data);
+    ) -> Result<VertexBufferID, RendererError> {
+        self.context.make_current();
+        let vertex_buffer = OpenGLVertexBuffer::new(&self.context, layout, data)?;
         let id = vertex_buffer.get_id();
+        self.share_group
+            .vertex_buffers
+            .borrow_mut()
+            .insert(id, Rc::downgrade(vertex_buffer.get_data()));
         assert!(self.vertex_buffers.insert(id, vertex_buffer).is_none());
-        id
+        Ok(id)
+    }
+
+    fn share_vertex_buffer(&mut self, id: VertexBufferID) -> Result<(), RendererError> {
+        if self.vertex_buffers.contains_key(&id) {
+            return Ok(());
+        }
+        let data = find_shared(&self.share_group.vertex_buffers, &id)
+            .ok_or(RendererError::InvalidVertexBuffer(id))?;
+        self.context.make_current();
+        self.vertex_buffers
+            .insert(id, OpenGLVertexBuffer::from_shared(id, &self.context, data));
+        Ok(())
     }
 
     fn destroy_vertex_buffer(&mut self, id: VertexBufferID) {
+        self.context.make_current();
         self.vertex_buffers.remove(&id);
+        forget_if_dropped(&self.share_group.vertex_buffers, &id);
     }
 
     fn get_vertex_buffer(&self, id: VertexBufferID) -> Option<&dyn VertexBuffer> {
@@ -310,14 +335,35 @@ impl Renderer for OpenGLRenderer {
     }
 
     fn create_index_buffer(&mut self, indices: &[u32]) -> IndexBufferID {
-        let index_buffer = OpenGLIndexBuffer::new(indices);
+        self.context.make_current();
+        let index_buffer = OpenGLIndexBuffer::new(&self.context, indices);
         let id = index_buffer.get_id();
+        self.share_group
+            .index_buffers
+            .borrow_mut()
+            .insert(id, Rc::downgrade(index_buffer.get_buffer()));
         assert!(self.index_buffers.insert(id, index_buffer).is_none());
         id
     }
 
+    fn share_index_buffer(&mut self, id: IndexBufferID) -> Result<(), RendererError> {
+        if self.index_buffers.contains_key(&id) {
+            return Ok(());
+        }
+        let buffer = find_shared(&self.share_group.index_buffers, &id)
+            .ok_or(RendererError::InvalidIndexBuffer(id))?;
+        self.context.make_current();
+        self.index_buffers.insert(
+            id,
+            OpenGLIndexBuffer::from_shared(id, &self.context, buffer),
+        );
+        Ok(())
+    }
+
     fn destroy_index_buffer(&mut self, id: IndexBufferID) {
+        self.context.make_current();
         self.index_buffers.remove(&id);
+        forget_if_dropped(&self.share_group.index_buffers, &id);
     }
 
     fn get_index_buffer(&self, id: IndexBufferID) -> Option<&dyn IndexBuffer> {
@@ -332,15 +378,107 @@ impl Renderer for OpenGLRenderer {
             .map(|index_buffer| index_buffer as &mut dyn IndexBuffer)
     }
 
-    fn create_texture(&mut self, size: Vector2<usize>, data: Pixels) -> TextureID {
-        let texture = OpenGLTexture::new(size, data);
+    fn create_uniform_buffer(&mut self, data: &[u8]) -> UniformBufferID {
+        self.context.make_current();
+        let uniform_buffer = OpenGLUniformBuffer::new(&self.context, data);
+        let id = uniform_buffer.get_id();
+        self.share_group
+            .uniform_buffers
+            .borrow_mut()
+            .insert(id, Rc::downgrade(uniform_buffer.get_buffer()));
+        assert!(self.uniform_buffers.insert(id, uniform_buffer).is_none());
+        id
+    }
+
+    fn share_uniform_buffer(&mut self, id: UniformBufferID) -> Result<(), RendererError> {
+        if self.uniform_buffers.contains_key(&id) {
+            return Ok(());
+        }
+        let buffer = find_shared(&self.share_group.uniform_buffers, &id)
+            .ok_or(RendererError::InvalidUniformBuffer(id))?;
+        self.context.make_current();
+        self.uniform_buffers.insert(
+            id,
+            OpenGLUniformBuffer::from_shared(id, &self.context, buffer),
+        );
+        Ok(())
+    }
+
+    fn destroy_uniform_buffer(&mut self, id: UniformBufferID) {
+        self.context.make_current();
+        self.uniform_buffers.remove(&id);
+        forget_if_dropped(&self.share_group.uniform_buffers, &id);
+    }
+
+    fn get_uniform_buffer(&self, id: UniformBufferID) -> Option<&dyn UniformBuffer> {
+        self.uniform_buffers
+            .get(&id)
+            .map(|uniform_buffer| uniform_buffer as &dyn UniformBuffer)
+    }
+
+    fn get_uniform_buffer_mut(&mut self, id: UniformBufferID) -> Option<&mut dyn UniformBuffer> {
+        self.uniform_buffers
+            .get_mut(&id)
+            .map(|uniform_buffer| uniform_buffer as &mut dyn UniformBuffer)
+    }
+
+    fn set_uniform_buffer_binding(
+        &mut self,
+        binding: usize,
+        uniform_buffer: Option<UniformBufferID>,
+    ) -> Result<(), RendererError> {
+        if BUILTIN_UNIFORM_BLOCKS
+            .iter()
+            .any(|builtin| builtin.binding == binding)
+        {
+            return Err(RendererError::BuiltinUniformBufferBinding(binding));
+        }
+        self.context.make_current();
+        match uniform_buffer {
+            Some(id) => self
+                .uniform_buffers
+                .get(&id)
+                .ok_or(RendererError::InvalidUniformBuffer(id))?
+                .bind_to(binding),
+            None => unsafe { gl::BindBufferBase(gl::UNIFORM_BUFFER, binding as _, 0) },
+        }
+        Ok(())
+    }
+
+    fn create_texture(
+        &mut self,
+        size: Vector2<usize>,
+        data: Pixels,
+        format: Option<TextureFormat>,
+        sampler: SamplerDescription,
+    ) -> Result<TextureID, RendererError> {
+        self.context.make_current();
+        let texture = OpenGLTexture::new(&self.context, size, data, format, sampler)?;
         let id = texture.get_id();
+        self.share_group
+            .textures
+            .borrow_mut()
+            .insert(id, Rc::downgrade(texture.get_data()));
         assert!(self.textures.insert(id, texture).is_none());
-        id
+        Ok(id)
+    }
+
+    fn share_texture(&mut self, id: TextureID) -> Result<(), RendererError> {
+        if self.textures.contains_key(&id) {
+            return Ok(());
+        }
+        let data = find_shared(&self.share_group.textures, &id)
+            .ok_or(RendererError::InvalidTexture(id))?;
+        self.context.make_current();
+        self.textures
+            .insert(id, OpenGLTexture::from_shared(id, &self.context, data));
+        Ok(())
     }
 
     fn destroy_texture(&mut self, id: TextureID) {
+        self.context.make_current();
         self.textures.remove(&id);
+        forget_if_dropped(&self.share_group.textures, &id);
     }
 
     fn get_texture(&self, id: TextureID) -> Option<&dyn Texture> {
@@ -355,38 +493,233 @@ impl Renderer for OpenGLRenderer {
             .map(|texture| texture as &mut dyn Texture)
     }
 
-    fn resize(&mut self, size: Vector2<usize>) {
+    fn create_material(&mut self, shader: ShaderID) -> Result<MaterialID, RendererError> {
+        let material = StoredMaterial::new(
+            shader,
+            self.shaders
+                .get(&shader)
+                .ok_or(RendererError::InvalidShader(shader))?
+                .get_declared_uniforms(),
+        );
+        let id = material.get_id();
+        assert!(self.materials.insert(id, material).is_none());
+        Ok(id)
+    }
+
+    fn destroy_material(&mut self, id: MaterialID) {
+        self.materials.remove(&id);
+    }
+
+    fn get_material(&self, id: MaterialID) -> Option<&dyn Material> {
+        self.materials
+            .get(&id)
+            .map(|material| material as &dyn Material)
+    }
+
+    fn get_material_mut(&mut self, id: MaterialID) -> Option<&mut dyn Material> {
+        self.materials
+            .get_mut(&id)
+            .map(|material| material as &mut dyn Material)
+    }
+
+    fn create_framebuffer(
+        &mut self,
+        size: Vector2<usize>,
+        color_attachments: &[TextureID],
+        depth_attachment: DepthAttachment,
+    ) -> Result<FramebufferID, RendererError> {
+        self.context.make_current();
+        let framebuffer = OpenGLFramebuffer::new(
+            &self.context,
+            size,
+            color_attachments,
+            depth_attachment,
+            &self.textures,
+        )?;
+        let id = framebuffer.get_id();
+        assert!(self.framebuffers.insert(id, framebuffer).is_none());
+        Ok(id)
+    }
+
+    fn destroy_framebuffer(&mut self, id: FramebufferID) {
+        self.context.make_current();
+        self.framebuffers.remove(&id);
+    }
+
+    fn get_framebuffer(&self, id: FramebufferID) -> Option<&dyn Framebuffer> {
+        self.framebuffers
+            .get(&id)
+            .map(|framebuffer| framebuffer as &dyn Framebuffer)
+    }
+
+    fn get_framebuffer_mut(&mut self, id: FramebufferID) -> Option<&mut dyn Framebuffer> {
+        self.framebuffers
+            .get_mut(&id)
+            .map(|framebuffer| framebuffer as &mut dyn Framebuffer)
+    }
+
+    fn resize(&mut self, size: Vector2<usize>) -> Result<(), RendererError> {
+        // The pbuffer of a headless surface is created once with the size of the surface
+        #[cfg(target_os = "linux")]
+        if self.get_surface().is_headless() && size != self.get_surface().get_size() {
+            return Err(RendererError::HeadlessResizeUnsupported {
+                size,
+                surface_size: self.get_surface().get_size(),
+            });
+        }
+        self.context.make_current();
         unsafe { gl::Viewport(0, 0, size.x as _, size.y as _) }
+        Ok(())
     }
 
     fn present(&mut self) {
-        #[cfg(target_os = "windows")]
+        self.context.make_current();
+        self.context.present();
+    }
+
+    fn read_pixels(&self) -> Image {
+        let size = self.get_surface().get_size();
+        self.context.make_current();
         unsafe {
-            SwapBuffers(self.device_context);
+            let mut pixels = vec![Vector4::zero(); size.x * size.y];
+            gl::ReadPixels(
+                0,
+                0,
+                size.x as _,
+                size.y as _,
+                gl::RGBA,
+                gl::FLOAT,
+                pixels.as_mut_ptr().cast(),
+            );
+            Image { size, pixels }
         }
     }
 
-    fn clear(&mut self, color: Vector3<f32>) {
+    fn set_depth_convention(
+        &mut self,
+        depth_convention: DepthConvention,
+    ) -> Result<(), RendererError> {
+        self.context.make_current();
+        let depth = match depth_convention.range {
+            DepthRange::NegativeOneToOne => gl::NEGATIVE_ONE_TO_ONE,
+            DepthRange::ZeroToOne => gl::ZERO_TO_ONE,
+        };
+        if gl::ClipControl::is_loaded() {
+            unsafe { gl::ClipControl(gl::LOWER_LEFT, depth) };
+        } else if depth_convention.range != DepthRange::NegativeOneToOne {
+            return Err(RendererError::ClipControlUnsupported);
+        }
+        self.depth_convention = depth_convention;
+        Ok(())
+    }
+
+    fn get_depth_convention(&self) -> DepthConvention {
+        self.depth_convention
+    }
+
+    fn clear(
+        &mut self,
+        framebuffer: Option<FramebufferID>,
+        description: ClearDescription,
+    ) -> Result<(), RendererError> {
+        let mut framebuffer = match framebuffer {
+            Some(id) => Some(
+                self.framebuffers
+                    .get_mut(&id)
+                    .ok_or(RendererError::InvalidFramebuffer(id))?,
+            ),
+            None => None,
+        };
+        if let Some(framebuffer) = &framebuffer {
+            check_attachment_sizes(
+                framebuffer.get_size(),
+                framebuffer.get_attached_textures(),
+                &self.textures,
+            )?;
+        }
+        self.context.make_current();
+        if let Some(framebuffer) = &mut framebuffer {
+            framebuffer.bind();
+        }
         unsafe {
-            gl::ClearColor(color.x, color.y, color.z, 1.0);
-            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT | gl::STENCIL_BUFFER_BIT);
+            let mut mask = 0;
+            if let Some(color) = description.color {
+                gl::ClearColor(color.x, color.y, color.z, color.w);
+                mask |= gl::COLOR_BUFFER_BIT;
+            }
+            if let Some(depth) = description.depth {
+                gl::ClearDepth(depth as _);
+                mask |= gl::DEPTH_BUFFER_BIT;
+            }
+            if let Some(stencil) = description.stencil {
+                gl::ClearStencil(stencil as _);
+                mask |= gl::STENCIL_BUFFER_BIT;
+            }
+            if mask != 0 {
+                gl::Clear(mask);
+            }
+        }
+        if let Some(framebuffer) = &mut framebuffer {
+            framebuffer.unbind();
         }
+        Ok(())
     }
 
     fn drawing_context<'a>(
         &'a mut self,
+        framebuffer: Option<FramebufferID>,
+        viewport: Option<Rect>,
+        scissor: Option<Rect>,
         camera: Camera<f32>,
-        depth_testing: bool,
+        pipeline_state: PipelineState,
         cull_face: CullFace,
-    ) -> Box<dyn RendererDrawContext + 'a> {
+    ) -> Result<Box<dyn RendererDrawContext + 'a>, RendererError> {
+        self.context.make_current();
+        let surface_size = self.get_surface().get_size();
+        let framebuffer = match framebuffer {
+            Some(id) => Some(
+                self.framebuffers
+                    .get_mut(&id)
+                    .ok_or(RendererError::InvalidFramebuffer(id))?,
+            ),
+            None => None,
+        };
+        if let Some(framebuffer) = &framebuffer {
+            check_attachment_sizes(
+                framebuffer.get_size(),
+                framebuffer.get_attached_textures(),
+                &self.textures,
+            )?;
+        }
+        let target_size = match &framebuffer {
+            Some(framebuffer) => framebuffer.get_size(),
+            None => surface_size,
+        };
+        let framebuffer = framebuffer.map(|framebuffer| {
+            framebuffer.bind();
+            framebuffer.get_id()
+        });
         unsafe {
-            if depth_testing {
-                gl::Enable(gl::DEPTH_TEST);
-                gl::DepthFunc(gl::GEQUAL);
-                gl::ClearDepth(0.0);
-            } else {
-                gl::Disable(gl::DEPTH_TEST);
+            let viewport = viewport.unwrap_or(Rect::new(Vector2::zero(), target_size));
+            gl::Viewport(
+                viewport.position.x as _,
+                viewport.position.y as _,
+                viewport.size.x as _,
+                viewport.size.y as _,
+            );
+            match scissor {
+                Some(scissor) => {
+                    gl::Enable(gl::SCISSOR_TEST);
+                    gl::Scissor(
+                        scissor.position.x as _,
+                        scissor.position.y as _,
+                        scissor.size.x as _,
+                        scissor.size.y as _,
+                    );
+                }
+                None => gl::Disable(gl::SCISSOR_TEST),
             }
+            apply_pipeline_state(&pipeline_state);
             match cull_face {
                 CullFace::None => {
                     gl::Disable(gl::CULL_FACE);
@@ -403,76 +736,379 @@ impl Renderer for OpenGLRenderer {
                 }
             }
         }
-        Box::new(OpenGLRendererDrawContext {
+        let view_matrix = camera.transform.into();
+        let projection_matrix = camera.projection_type.to_matrix(self.depth_convention);
+        let camera_uniform_buffer = self.camera_uniform_buffer.as_mut().unwrap();
+        camera_uniform_buffer.set_data(&get_camera_block_data(projection_matrix, view_matrix));
+        camera_uniform_buffer.bind_to(0);
+        Ok(Box::new(OpenGLRendererDrawContext {
             renderer: self,
-            view_matrix: camera.transform.into(),
-            projection_matrix: camera.projection_type.into(),
+            framebuffer,
+            view_matrix,
+            projection_matrix,
+            shaders_with_camera: HashSet::new(),
+            pipeline_state,
+            point_size: 1.0,
+            line_width: 1.0,
             _send: PhantomData,
             _sync: PhantomData,
-        })
+        }))
     }
 }
 
 pub struct OpenGLRendererDrawContext<'a> {
     renderer: &'a mut OpenGLRenderer,
+    framebuffer: Option<FramebufferID>,
     view_matrix: Matrix4x4<f32>,
     projection_matrix: Matrix4x4<f32>,
+    /// The shaders that already got `u_ProjectionMatrix` and `u_ViewMatrix`, programs keep their
+    /// uniforms so the camera only has to be uploaded once per drawing context
+    shaders_with_camera: HashSet<ShaderID>,
+    pipeline_state: PipelineState,
+    point_size: f32,
+    line_width: f32,
     _send: PhantomUnsend,
     _sync: PhantomUnsync,
 }
 
-impl<'a> RendererDrawContext for OpenGLRendererDrawContext<'a> {
-    fn draw(
+impl<'a> Drop for OpenGLRendererDrawContext<'a> {
+    fn drop(&mut self) {
+        self.renderer.context.make_current();
+        if let Some(framebuffer) = self
+            .framebuffer
+            .and_then(|id| self.renderer.framebuffers.get_mut(&id))
+        {
+            framebuffer.unbind();
+        }
+        let surface_size = self.renderer.get_surface().get_size();
+        unsafe {
+            gl::Viewport(0, 0, surface_size.x as _, surface_size.y as _);
+            gl::Disable(gl::SCISSOR_TEST);
+            // The masks also apply to `Renderer::clear`
+            apply_pipeline_state(&PipelineState::default());
+        }
+    }
+}
+
+/// Sets the blend, depth, stencil and color mask state of the current context
+unsafe fn apply_pipeline_state(pipeline_state: &PipelineState) {
+    let blend_factors = match pipeline_state.blend {
+        BlendMode::None => None,
+        BlendMode::Alpha => Some((gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA)),
+        BlendMode::Additive => Some((gl::SRC_ALPHA, gl::ONE)),
+        BlendMode::Multiply => Some((gl::DST_COLOR, gl::ZERO)),
+        BlendMode::PremultipliedAlpha => Some((gl::ONE, gl::ONE_MINUS_SRC_ALPHA)),
+    };
+    match blend_factors {
+        Some((source, destination)) => {
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(source, destination);
+        }
+        None => gl::Disable(gl::BLEND),
+    }
+
+    match &pipeline_state.depth {
+        Some(depth) => {
+            gl::Enable(gl::DEPTH_TEST);
+            gl::DepthFunc(get_compare_function(depth.compare));
+            gl::DepthMask(depth.write as _);
+        }
+        None => {
+            gl::Disable(gl::DEPTH_TEST);
+            gl::DepthMask(gl::TRUE);
+        }
+    }
+
+    match &pipeline_state.stencil {
+        Some(stencil) => {
+            gl::Enable(gl::STENCIL_TEST);
+            gl::StencilFunc(
+                get_compare_function(stencil.compare),
+                stencil.reference as _,
+                stencil.read_mask as _,
+            );
+            gl::StencilMask(stencil.write_mask as _);
+            gl::StencilOp(
+                get_stencil_operation(stencil.fail),
+                get_stencil_operation(stencil.depth_fail),
+                get_stencil_operation(stencil.pass),
+            );
+        }
+        None => {
+            gl::Disable(gl::STENCIL_TEST);
+            gl::StencilMask(0xFF);
+        }
+    }
+
+    let ColorMask {
+        red,
+        green,
+        blue,
+        alpha,
+    } = pipeline_state.color_mask;
+    gl::ColorMask(red as _, green as _, blue as _, alpha as _);
+}
+
+fn get_compare_function(compare: CompareFunction) -> GLenum {
+    match compare {
+        CompareFunction::Never => gl::NEVER,
+        CompareFunction::Less => gl::LESS,
+        CompareFunction::LessEqual => gl::LEQUAL,
+        CompareFunction::Equal => gl::EQUAL,
+        CompareFunction::NotEqual => gl::NOTEQUAL,
+        CompareFunction::GreaterEqual => gl::GEQUAL,
+        CompareFunction::Greater => gl::GREATER,
+        CompareFunction::Always => gl::ALWAYS,
+    }
+}
+
+fn get_stencil_operation(operation: StencilOperation) -> GLenum {
+    match operation {
+        StencilOperation::Keep => gl::KEEP,
+        StencilOperation::Zero => gl::ZERO,
+        StencilOperation::Replace => gl::REPLACE,
+        StencilOperation::Increment => gl::INCR,
+        StencilOperation::Decrement => gl::DECR,
+        StencilOperation::IncrementWrap => gl::INCR_WRAP,
+        StencilOperation::DecrementWrap => gl::DECR_WRAP,
+        StencilOperation::Invert => gl::INVERT,
+    }
+}
+
+impl<'a> OpenGLRendererDrawContext<'a> {
+    /// Draws `instances` as a vertex buffer and the number of instances, or once without them
+    #[allow(clippy::too_many_arguments)]
+    fn draw_vertices(
         &mut self,
         typ: PrimitiveType,
         shader: ShaderID,
         vertex_buffer: VertexBufferID,
+        index_buffer: Option<IndexBufferID>,
+        instances: Option<(VertexBufferID, usize)>,
         texture: Option<TextureID>,
         model_matrix: Matrix4x4<f32>,
         color: Vector3<f32>,
-    ) {
-        // TODO: maybe some proper error handling
-        let Some(shader) = self.renderer.shaders.get_mut(&shader) else { return; };
-        let Some(vertex_buffer) = self.renderer.vertex_buffers.get_mut(&vertex_buffer) else { return; };
+    ) -> Result<(), RendererError> {
+        let shader = self
+            .renderer
+            .shaders
+            .get_mut(&shader)
+            .ok_or(RendererError::InvalidShader(shader))?;
+        let vertex_buffer = self
+            .renderer
+            .vertex_buffers
+            .get(&vertex_buffer)
+            .ok_or(RendererError::InvalidVertexBuffer(vertex_buffer))?;
+        let mut index_buffer = match index_buffer {
+            Some(id) => Some(
+                self.renderer
+                    .index_buffers
+                    .get_mut(&id)
+                    .ok_or(RendererError::InvalidIndexBuffer(id))?,
+            ),
+            None => None,
+        };
+        let count = match &index_buffer {
+            Some(index_buffer) => index_buffer.get_count(),
+            None => vertex_buffer.get_count(),
+        };
+        check_vertex_count(typ, count)?;
+        let instances = match instances {
+            Some((id, instance_count)) => {
+                let instance_buffer = self
+                    .renderer
+                    .vertex_buffers
+                    .get(&id)
+                    .ok_or(RendererError::InvalidVertexBuffer(id))?;
+                check_instance_buffer(
+                    vertex_buffer.get_layout(),
+                    instance_buffer.get_layout(),
+                    instance_buffer.get_count(),
+                    instance_count,
+                )?;
+                Some((instance_buffer, instance_count))
+            }
+            None => None,
+        };
+        check_vertex_layout(
+            shader.get_inputs(),
+            vertex_buffer.get_layout(),
+            instances.map(|(instance_buffer, _)| instance_buffer.get_layout()),
+        )?;
+        if let Some(id) = texture.filter(|id| !self.renderer.textures.contains_key(id)) {
+            return Err(RendererError::InvalidTexture(id));
+        }
 
+        self.renderer.context.make_current();
         shader.bind();
         vertex_buffer.bind();
+        if let Some((instance_buffer, _)) = &instances {
+            instance_buffer.bind_instances();
+        }
+        if let Some(index_buffer) = &mut index_buffer {
+            index_buffer.bind();
+        }
+        // Binding a texture also binds it to the active unit, so `u_Texture` goes last
+        bind_texture_uniforms(
+            shader,
+            &mut self.renderer.textures,
+            self.renderer.default_white_pixel.as_mut().unwrap(),
+        );
         let texture_index = 0;
-        if let Some(texture) = texture
-            .map(|id| self.renderer.textures.get_mut(&id))
-            .flatten()
-        {
+        if let Some(texture) = texture.and_then(|id| self.renderer.textures.get_mut(&id)) {
             texture.bind(texture_index);
         } else {
-            self.renderer.default_white_pixel.bind(texture_index);
+            self.renderer
+                .default_white_pixel
+                .as_mut()
+                .unwrap()
+                .bind(texture_index);
+        }
+        if self.shaders_with_camera.insert(shader.get_id()) {
+            shader.set_uniform_unchecked(
+                "u_ProjectionMatrix",
+                UniformValue::Matrix4x4(self.projection_matrix),
+            );
+            shader.set_uniform_unchecked("u_ViewMatrix", UniformValue::Matrix4x4(self.view_matrix));
         }
         unsafe {
-            shader.set_uniform_matrix("u_ProjectionMatrix", &self.projection_matrix);
-            shader.set_uniform_matrix("u_ViewMatrix", &self.view_matrix);
-            shader.set_uniform_matrix("u_ModelMatrix", &model_matrix);
-            shader.set_uniform_vector3("u_Color", color);
-            shader.set_uniform_int("u_Texture", texture_index as i32);
+            shader.set_uniform_unchecked("u_ModelMatrix", UniformValue::Matrix4x4(model_matrix));
+            shader.set_uniform_unchecked("u_Color", UniformValue::Float3(color));
+            shader.set_uniform_unchecked("u_Texture", UniformValue::Int(texture_index as i32));
+            gl::PointSize(self.point_size);
+            gl::LineWidth(self.line_width);
             let typ = match typ {
-                PrimitiveType::Triangle => {
-                    assert_eq!(vertex_buffer.get_count() % 3, 0);
-                    gl::TRIANGLES
+                PrimitiveType::Points => gl::POINTS,
+                PrimitiveType::Lines => gl::LINES,
+                PrimitiveType::LineStrip => gl::LINE_STRIP,
+                PrimitiveType::LineLoop => gl::LINE_LOOP,
+                PrimitiveType::Triangle => gl::TRIANGLES,
+                PrimitiveType::TriangleStrip => gl::TRIANGLE_STRIP,
+                PrimitiveType::TriangleFan => gl::TRIANGLE_FAN,
+            };
+            match (&index_buffer, &instances) {
+                (None, None) => gl::DrawArrays(typ, 0, count as _),
+                (None, Some((_, instance_count))) => {
+                    gl::DrawArraysInstanced(typ, 0, count as _, *instance_count as _)
                 }
-                PrimitiveType::TriangleStrip => {
-                    let count = vertex_buffer.get_count();
-                    assert!(count == 0 || count >= 3);
-                    gl::TRIANGLE_STRIP
+                (Some(_), None) => {
+                    gl::DrawElements(typ, count as _, gl::UNSIGNED_INT, std::ptr::null())
                 }
-            };
-            gl::DrawArrays(typ, 0, vertex_buffer.get_count() as _);
+                (Some(_), Some((_, instance_count))) => gl::DrawElementsInstanced(
+                    typ,
+                    count as _,
+                    gl::UNSIGNED_INT,
+                    std::ptr::null(),
+                    *instance_count as _,
+                ),
+            }
         }
-        if let Some(texture) = texture
-            .map(|id| self.renderer.textures.get_mut(&id))
-            .flatten()
-        {
+        if let Some(texture) = texture.and_then(|id| self.renderer.textures.get_mut(&id)) {
             texture.unbind();
         }
+        if let Some(index_buffer) = &mut index_buffer {
+            index_buffer.unbind();
+        }
+        if let Some((instance_buffer, _)) = &instances {
+            instance_buffer.unbind_instances();
+        }
         vertex_buffer.unbind();
         shader.unbind();
+        Ok(())
+    }
+}
+
+/// Binds the textures that were set with `Shader::set_uniform` to the units after the one
+/// `u_Texture` uses
+fn bind_texture_uniforms(
+    shader: &mut OpenGLShader,
+    textures: &mut HashMap<TextureID, OpenGLTexture>,
+    default_white_pixel: &mut OpenGLTexture,
+) {
+    for (i, (location, id)) in shader
+        .get_texture_uniforms()
+        .to_vec()
+        .into_iter()
+        .enumerate()
+    {
+        let unit = i as u32 + 1;
+        match textures.get_mut(&id) {
+            Some(texture) => texture.bind(unit),
+            None => default_white_pixel.bind(unit),
+        }
+        shader.set_texture_unit(location, unit);
+    }
+}
+
+impl<'a> RendererDrawContext for OpenGLRendererDrawContext<'a> {
+    fn set_uniform(
+        &mut self,
+        shader: ShaderID,
+        name: &str,
+        value: UniformValue,
+    ) -> Result<(), RendererError> {
+        self.renderer
+            .shaders
+            .get_mut(&shader)
+            .ok_or(RendererError::InvalidShader(shader))?
+            .set_uniform(name, value)
+    }
+
+    fn bind_material(&mut self, material: MaterialID) -> Result<ShaderID, RendererError> {
+        let material = self
+            .renderer
+            .materials
+            .get(&material)
+            .ok_or(RendererError::InvalidMaterial(material))?;
+        let shader = self
+            .renderer
+            .shaders
+            .get_mut(&material.get_shader())
+            .ok_or(RendererError::InvalidShader(material.get_shader()))?;
+        material.apply(
+            shader,
+            self.renderer.default_white_pixel.as_ref().unwrap().get_id(),
+        )?;
+        Ok(material.get_shader())
+    }
+
+    fn set_pipeline_state(&mut self, pipeline_state: PipelineState) {
+        self.pipeline_state = pipeline_state;
+        self.renderer.context.make_current();
+        unsafe { apply_pipeline_state(&pipeline_state) };
+    }
+
+    fn get_pipeline_state(&self) -> PipelineState {
+        self.pipeline_state
+    }
+
+    fn set_point_size(&mut self, size: f32) {
+        self.point_size = size.max(1.0);
+    }
+
+    fn set_line_width(&mut self, width: f32) {
+        self.line_width = width.max(1.0);
+    }
+
+    fn draw(
+        &mut self,
+        typ: PrimitiveType,
+        shader: ShaderID,
+        vertex_buffer: VertexBufferID,
+        texture: Option<TextureID>,
+        model_matrix: Matrix4x4<f32>,
+        color: Vector3<f32>,
+    ) -> Result<(), RendererError> {
+        self.draw_vertices(
+            typ,
+            shader,
+            vertex_buffer,
+            None,
+            None,
+            texture,
+            model_matrix,
+            color,
+        )
     }
 
     fn draw_indexed(
@@ -484,56 +1120,63 @@ impl<'a> RendererDrawContext for OpenGLRendererDrawContext<'a> {
         texture: Option<TextureID>,
         model_matrix: Matrix4x4<f32>,
         color: Vector3<f32>,
-    ) {
-        // TODO: maybe some proper error handling
-        let Some(shader) = self.renderer.shaders.get_mut(&shader) else { return; };
-        let Some(vertex_buffer) = self.renderer.vertex_buffers.get_mut(&vertex_buffer) else { return; };
-        let Some(index_buffer) = self.renderer.index_buffers.get_mut(&index_buffer) else { return; };
+    ) -> Result<(), RendererError> {
+        self.draw_vertices(
+            typ,
+            shader,
+            vertex_buffer,
+            Some(index_buffer),
+            None,
+            texture,
+            model_matrix,
+            color,
+        )
+    }
 
-        shader.bind();
-        vertex_buffer.bind();
-        index_buffer.bind();
-        let texture_index = 0;
-        if let Some(texture) = texture
-            .map(|id| self.renderer.textures.get_mut(&id))
-            .flatten()
-        {
-            texture.bind(texture_index);
-        } else {
-            self.renderer.default_white_pixel.bind(texture_index);
-        }
-        unsafe {
-            shader.set_uniform_matrix("u_ProjectionMatrix", &self.projection_matrix);
-            shader.set_uniform_matrix("u_ViewMatrix", &self.view_matrix);
-            shader.set_uniform_matrix("u_ModelMatrix", &model_matrix);
-            shader.set_uniform_vector3("u_Color", color);
-            shader.set_uniform_int("u_Texture", texture_index as i32);
-            let typ = match typ {
-                PrimitiveType::Triangle => {
-                    assert_eq!(index_buffer.get_count() % 3, 0);
-                    gl::TRIANGLES
-                }
-                PrimitiveType::TriangleStrip => {
-                    let count = index_buffer.get_count();
-                    assert!(count == 0 || count >= 3);
-                    gl::TRIANGLE_STRIP
-                }
-            };
-            gl::DrawElements(
-                typ,
-                index_buffer.get_count() as _,
-                gl::UNSIGNED_INT,
-                std::ptr::null(),
-            );
-        }
-        if let Some(texture) = texture
-            .map(|id| self.renderer.textures.get_mut(&id))
-            .flatten()
-        {
-            texture.unbind();
-        }
-        index_buffer.unbind();
-        vertex_buffer.unbind();
-        shader.unbind();
+    fn draw_instanced(
+        &mut self,
+        typ: PrimitiveType,
+        shader: ShaderID,
+        vertex_buffer: VertexBufferID,
+        instance_buffer: VertexBufferID,
+        instance_count: usize,
+        texture: Option<TextureID>,
+        model_matrix: Matrix4x4<f32>,
+        color: Vector3<f32>,
+    ) -> Result<(), RendererError> {
+        self.draw_vertices(
+            typ,
+            shader,
+            vertex_buffer,
+            None,
+            Some((instance_buffer, instance_count)),
+            texture,
+            model_matrix,
+            color,
+        )
+    }
+
+    fn draw_indexed_instanced(
+        &mut self,
+        typ: PrimitiveType,
+        shader: ShaderID,
+        vertex_buffer: VertexBufferID,
+        index_buffer: IndexBufferID,
+        instance_buffer: VertexBufferID,
+        instance_count: usize,
+        texture: Option<TextureID>,
+        model_matrix: Matrix4x4<f32>,
+        color: Vector3<f32>,
+    ) -> Result<(), RendererError> {
+        self.draw_vertices(
+            typ,
+            shader,
+            vertex_buffer,
+            Some(index_buffer),
+            Some((instance_buffer, instance_count)),
+            texture,
+            model_matrix,
+            color,
+        )
     }
 }
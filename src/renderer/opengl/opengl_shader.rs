@@ -1,29 +1,64 @@
-use std::{ffi::CString, marker::PhantomData, sync::atomic::AtomicUsize};
+use std::{
+    collections::HashMap, ffi::CString, marker::PhantomData, rc::Rc, sync::atomic::AtomicUsize,
+};
 
-use gl::types::{GLenum, GLuint};
+use gl::types::{GLchar, GLenum, GLint, GLsizei, GLuint};
 
 use crate::{
-    math::{Matrix4x4, Vector3},
-    renderer::{Shader, ShaderID},
+    renderer::{
+        glsl_preprocessor::{preprocess, PreprocessedGlsl},
+        opengl::OpenGLContext,
+        shader::{check_uniform_array_size, check_uniform_value, get_declared_variables},
+        RendererError, Shader, ShaderDataType, ShaderDescription, ShaderID, ShaderStage,
+        ShaderVariable, TextureID, UniformValue,
+    },
     PhantomUnsend, PhantomUnsync,
 };
 
 pub(crate) struct OpenGLShader {
     id: ShaderID,
+    context: Rc<OpenGLContext>,
     opengl_id: GLuint,
+    declared_uniforms: Vec<(String, ShaderDataType)>,
+    /// The declared uniforms with the array size they were declared with, which array values are
+    /// checked against
+    declared_variables: Vec<ShaderVariable>,
+    inputs: Vec<ShaderVariable>,
+    uniforms: Vec<ShaderVariable>,
+    /// The location of every declared uniform with the number of elements opengl has room for,
+    /// the location is `-1` if the glsl does not use it
+    uniform_locations: HashMap<String, (GLint, usize)>,
+    /// The texture uniforms that were set with `set_uniform`, the renderer binds them every draw
+    textures: Vec<(GLint, TextureID)>,
     _send: PhantomUnsend,
     _sync: PhantomUnsync,
 }
 
 impl OpenGLShader {
     pub(crate) fn new(
-        vertex_shader_source: &str,
-        fragment_shader_source: &str,
-    ) -> Result<OpenGLShader, String> {
-        unsafe fn compile_shader(typ: GLenum, source: &str) -> Result<GLuint, String> {
-            let shader = gl::CreateShader(typ);
-            let ptr = source.as_ptr();
-            let length = source.len() as i32;
+        context: &Rc<OpenGLContext>,
+        description: &ShaderDescription,
+    ) -> Result<OpenGLShader, RendererError> {
+        description.validate()?;
+        let Some(source) = description.glsl else {
+            return Err(RendererError::MissingShaderSource);
+        };
+
+        let vertex_source = preprocess("vertex", source.vertex, &source.preprocessor)
+            .map_err(RendererError::ShaderPreprocessingFailed)?;
+        let fragment_source = preprocess("fragment", source.fragment, &source.preprocessor)
+            .map_err(RendererError::ShaderPreprocessingFailed)?;
+
+        unsafe fn compile_shader(
+            stage: ShaderStage,
+            source: &PreprocessedGlsl,
+        ) -> Result<GLuint, RendererError> {
+            let shader = gl::CreateShader(match stage {
+                ShaderStage::Vertex => gl::VERTEX_SHADER,
+                ShaderStage::Fragment => gl::FRAGMENT_SHADER,
+            });
+            let ptr = source.source.as_ptr();
+            let length = source.source.len() as i32;
             gl::ShaderSource(shader, 1, &ptr as *const _ as _, &length);
             gl::CompileShader(shader);
 
@@ -42,26 +77,32 @@ impl OpenGLShader {
 
                 gl::DeleteShader(shader);
 
-                Err(info_log)
+                Err(RendererError::ShaderCompilationFailed {
+                    stage,
+                    log: source.map_log(&info_log),
+                })
             } else {
                 Ok(shader)
             }
         }
 
         unsafe {
-            let vertex_shader = compile_shader(gl::VERTEX_SHADER, vertex_shader_source)?;
-            let fragment_shader = match compile_shader(gl::FRAGMENT_SHADER, fragment_shader_source)
-            {
+            let vertex_shader = compile_shader(ShaderStage::Vertex, &vertex_source)?;
+            let fragment_shader = match compile_shader(ShaderStage::Fragment, &fragment_source) {
                 Ok(fragment_shader) => fragment_shader,
-                Err(message) => {
+                Err(error) => {
                     gl::DeleteShader(vertex_shader);
-                    return Err(message);
+                    return Err(error);
                 }
             };
 
             let shader = gl::CreateProgram();
             gl::AttachShader(shader, vertex_shader);
             gl::AttachShader(shader, fragment_shader);
+            for (location, input) in description.inputs.iter().enumerate() {
+                let name = CString::new(input.name).unwrap();
+                gl::BindAttribLocation(shader, location as _, name.as_ptr());
+            }
             gl::LinkProgram(shader);
 
             let mut linked = 0;
@@ -81,7 +122,7 @@ impl OpenGLShader {
                 gl::DeleteShader(fragment_shader);
                 gl::DeleteProgram(shader);
 
-                return Err(info_log);
+                return Err(RendererError::ShaderLinkingFailed(info_log));
             }
 
             gl::DetachShader(shader, vertex_shader);
@@ -89,6 +130,16 @@ impl OpenGLShader {
             gl::DeleteShader(vertex_shader);
             gl::DeleteShader(fragment_shader);
 
+            let (attributes, uniforms) = get_active_variables(shader);
+            if let Err(message) = check_interface(&attributes, &uniforms, description)
+                .and_then(|()| bind_uniform_blocks(shader, description))
+            {
+                gl::DeleteProgram(shader);
+                return Err(RendererError::ShaderInterfaceMismatch(message));
+            }
+            let inputs = to_shader_variables(attributes);
+            let uniforms = to_shader_variables(uniforms);
+
             Ok(OpenGLShader {
                 id: {
                     static ID: AtomicUsize = AtomicUsize::new(1);
@@ -98,7 +149,30 @@ impl OpenGLShader {
                         PhantomData,
                     )
                 },
+                context: context.clone(),
                 opengl_id: shader,
+                declared_uniforms: description
+                    .uniforms
+                    .iter()
+                    .map(|uniform| (uniform.name.to_string(), uniform.typ))
+                    .collect(),
+                declared_variables: get_declared_variables(description.uniforms),
+                uniform_locations: description
+                    .uniforms
+                    .iter()
+                    .map(|declared| {
+                        let location = uniforms
+                            .iter()
+                            .find(|uniform| uniform.name == declared.name)
+                            .map_or((-1, 0), |uniform| {
+                                (uniform.location as GLint, uniform.array_size)
+                            });
+                        (declared.name.to_string(), location)
+                    })
+                    .collect(),
+                inputs,
+                uniforms,
+                textures: vec![],
                 _send: PhantomData,
                 _sync: PhantomData,
             })
@@ -113,52 +187,331 @@ impl OpenGLShader {
         unsafe { gl::UseProgram(0) }
     }
 
-    pub(crate) fn set_uniform_matrix(&mut self, name: &str, matrix: &Matrix4x4<f32>) -> bool {
-        unsafe {
-            let name = CString::new(name).unwrap();
-            let location = gl::GetUniformLocation(self.opengl_id, name.as_ptr());
-            if location == -1 {
-                return false;
-            }
-            gl::UniformMatrix4fv(
-                location,
-                1,
-                false as _,
-                matrix as *const Matrix4x4<f32> as _,
-            );
-            true
+    /// Sets a uniform without checking its type, this is for the builtin uniforms and the texture
+    /// units, the shader has to be bound
+    pub(crate) fn set_uniform_unchecked(&mut self, name: &str, value: UniformValue) {
+        if let Some(&(location, array_size)) = self.uniform_locations.get(name) {
+            unsafe { upload_uniform(location, array_size, value) };
         }
     }
 
-    pub(crate) fn set_uniform_vector3(&mut self, name: &str, vector: Vector3<f32>) -> bool {
-        unsafe {
-            let name = CString::new(name).unwrap();
-            let location = gl::GetUniformLocation(self.opengl_id, name.as_ptr());
-            if location == -1 {
-                return false;
-            }
-            gl::Uniform3f(location, vector.x, vector.y, vector.z);
-            true
+    /// Replaces the program with one made from `description` but keeps the id, the current program
+    /// is kept if the new one fails to compile
+    pub(crate) fn reload(&mut self, description: &ShaderDescription) -> Result<(), RendererError> {
+        let mut shader = OpenGLShader::new(&self.context, description)?;
+        shader.id = self.id;
+        *self = shader;
+        Ok(())
+    }
+
+    pub(crate) fn get_declared_uniforms(&self) -> &[(String, ShaderDataType)] {
+        &self.declared_uniforms
+    }
+
+    pub(crate) fn get_texture_uniforms(&self) -> &[(GLint, TextureID)] {
+        &self.textures
+    }
+
+    /// Sets the texture unit of the sampler at `location`, the shader has to be bound
+    pub(crate) fn set_texture_unit(&mut self, location: GLint, unit: u32) {
+        unsafe { gl::Uniform1i(location, unit as _) };
+    }
+}
+
+/// Array values are cut off at `array_size`, because the compiler can leave out the last elements
+/// of an array when the glsl does not use them
+unsafe fn upload_uniform(location: GLint, array_size: usize, value: UniformValue) {
+    if location == -1 {
+        return;
+    }
+    let count = |length: usize| length.min(array_size) as GLsizei;
+    match value {
+        UniformValue::Float(value) => gl::Uniform1f(location, value),
+        UniformValue::Float2(value) => gl::Uniform2f(location, value.x, value.y),
+        UniformValue::Float3(value) => gl::Uniform3f(location, value.x, value.y, value.z),
+        UniformValue::Float4(value) => gl::Uniform4f(location, value.x, value.y, value.z, value.w),
+        UniformValue::Int(value) => gl::Uniform1i(location, value),
+        UniformValue::Int2(value) => gl::Uniform2i(location, value.x, value.y),
+        UniformValue::Int3(value) => gl::Uniform3i(location, value.x, value.y, value.z),
+        UniformValue::Int4(value) => gl::Uniform4i(location, value.x, value.y, value.z, value.w),
+        UniformValue::UInt(value) => gl::Uniform1ui(location, value),
+        UniformValue::UInt2(value) => gl::Uniform2ui(location, value.x, value.y),
+        UniformValue::UInt3(value) => gl::Uniform3ui(location, value.x, value.y, value.z),
+        UniformValue::UInt4(value) => gl::Uniform4ui(location, value.x, value.y, value.z, value.w),
+        UniformValue::Matrix3x3(value) => {
+            gl::UniformMatrix3fv(location, 1, false as _, &value as *const _ as _)
+        }
+        UniformValue::Matrix4x4(value) => {
+            gl::UniformMatrix4fv(location, 1, false as _, &value as *const _ as _)
+        }
+        UniformValue::FloatArray(values) => {
+            gl::Uniform1fv(location, count(values.len()), values.as_ptr())
+        }
+        UniformValue::Float2Array(values) => {
+            gl::Uniform2fv(location, count(values.len()), values.as_ptr().cast())
+        }
+        UniformValue::Float3Array(values) => {
+            gl::Uniform3fv(location, count(values.len()), values.as_ptr().cast())
+        }
+        UniformValue::Float4Array(values) => {
+            gl::Uniform4fv(location, count(values.len()), values.as_ptr().cast())
+        }
+        UniformValue::IntArray(values) => {
+            gl::Uniform1iv(location, count(values.len()), values.as_ptr())
+        }
+        UniformValue::Int2Array(values) => {
+            gl::Uniform2iv(location, count(values.len()), values.as_ptr().cast())
         }
+        UniformValue::Int3Array(values) => {
+            gl::Uniform3iv(location, count(values.len()), values.as_ptr().cast())
+        }
+        UniformValue::Int4Array(values) => {
+            gl::Uniform4iv(location, count(values.len()), values.as_ptr().cast())
+        }
+        UniformValue::UIntArray(values) => {
+            gl::Uniform1uiv(location, count(values.len()), values.as_ptr())
+        }
+        UniformValue::UInt2Array(values) => {
+            gl::Uniform2uiv(location, count(values.len()), values.as_ptr().cast())
+        }
+        UniformValue::UInt3Array(values) => {
+            gl::Uniform3uiv(location, count(values.len()), values.as_ptr().cast())
+        }
+        UniformValue::UInt4Array(values) => {
+            gl::Uniform4uiv(location, count(values.len()), values.as_ptr().cast())
+        }
+        UniformValue::Matrix3x3Array(values) => gl::UniformMatrix3fv(
+            location,
+            count(values.len()),
+            false as _,
+            values.as_ptr().cast(),
+        ),
+        UniformValue::Matrix4x4Array(values) => gl::UniformMatrix4fv(
+            location,
+            count(values.len()),
+            false as _,
+            values.as_ptr().cast(),
+        ),
+        // Textures are bound by the renderer for every draw
+        UniformValue::Texture(_) => {}
     }
+}
 
-    pub(crate) fn set_uniform_int(&mut self, name: &str, value: i32) -> bool {
-        unsafe {
-            let name = CString::new(name).unwrap();
-            let location = gl::GetUniformLocation(self.opengl_id, name.as_ptr());
-            if location == -1 {
-                return false;
-            }
-            gl::Uniform1i(location, value);
-            true
+fn get_data_type(typ: GLenum) -> Option<ShaderDataType> {
+    match typ {
+        gl::FLOAT => Some(ShaderDataType::Float),
+        gl::FLOAT_VEC2 => Some(ShaderDataType::Float2),
+        gl::FLOAT_VEC3 => Some(ShaderDataType::Float3),
+        gl::FLOAT_VEC4 => Some(ShaderDataType::Float4),
+        gl::INT => Some(ShaderDataType::Int),
+        gl::INT_VEC2 => Some(ShaderDataType::Int2),
+        gl::INT_VEC3 => Some(ShaderDataType::Int3),
+        gl::INT_VEC4 => Some(ShaderDataType::Int4),
+        gl::UNSIGNED_INT => Some(ShaderDataType::UInt),
+        gl::UNSIGNED_INT_VEC2 => Some(ShaderDataType::UInt2),
+        gl::UNSIGNED_INT_VEC3 => Some(ShaderDataType::UInt3),
+        gl::UNSIGNED_INT_VEC4 => Some(ShaderDataType::UInt4),
+        gl::FLOAT_MAT3 => Some(ShaderDataType::Matrix3x3),
+        gl::FLOAT_MAT4 => Some(ShaderDataType::Matrix4x4),
+        gl::SAMPLER_2D => Some(ShaderDataType::Texture),
+        _ => None,
+    }
+}
+
+/// An active input or uniform of a linked program, arrays are named without the `[0]`
+struct ActiveVariable {
+    name: String,
+    typ: GLenum,
+    array_size: usize,
+    location: GLint,
+}
+
+/// Gets the active inputs and uniforms of a linked program, the ones opengl provides are skipped
+unsafe fn get_active_variables(program: GLuint) -> (Vec<ActiveVariable>, Vec<ActiveVariable>) {
+    type GetActive =
+        unsafe fn(GLuint, GLuint, GLsizei, *mut GLsizei, *mut GLint, *mut GLenum, *mut GLchar);
+    type GetLocation = unsafe fn(GLuint, *const GLchar) -> GLint;
+
+    unsafe fn get_active(
+        program: GLuint,
+        count: GLenum,
+        max_length: GLenum,
+        get_active: GetActive,
+        get_location: GetLocation,
+    ) -> Vec<ActiveVariable> {
+        let mut count_value = 0;
+        gl::GetProgramiv(program, count, &mut count_value);
+        let mut max_length_value = 0;
+        gl::GetProgramiv(program, max_length, &mut max_length_value);
+
+        (0..count_value as GLuint)
+            .map(|index| {
+                let mut vec = vec![0u8; max_length_value as usize];
+                let mut length = 0;
+                let mut size = 0;
+                let mut typ = 0;
+                get_active(
+                    program,
+                    index,
+                    max_length_value,
+                    &mut length,
+                    &mut size,
+                    &mut typ,
+                    vec.as_mut_ptr().cast(),
+                );
+                let name = std::str::from_utf8(&vec[..length as usize]).unwrap();
+                let c_name = CString::new(name).unwrap();
+                ActiveVariable {
+                    name: name.strip_suffix("[0]").unwrap_or(name).to_string(),
+                    typ,
+                    array_size: size as usize,
+                    location: get_location(program, c_name.as_ptr()),
+                }
+            })
+            // The members of uniform blocks have no location
+            .filter(|variable| !variable.name.starts_with("gl_") && variable.location != -1)
+            .collect()
+    }
+
+    let attributes = get_active(
+        program,
+        gl::ACTIVE_ATTRIBUTES,
+        gl::ACTIVE_ATTRIBUTE_MAX_LENGTH,
+        gl::GetActiveAttrib,
+        gl::GetAttribLocation,
+    );
+    let uniforms = get_active(
+        program,
+        gl::ACTIVE_UNIFORMS,
+        gl::ACTIVE_UNIFORM_MAX_LENGTH,
+        gl::GetActiveUniform,
+        gl::GetUniformLocation,
+    );
+    (attributes, uniforms)
+}
+
+/// Checks that every active input and uniform of the linked program is declared with the same type,
+/// declared ones that the glsl does not use are allowed because the compiler may have removed them
+fn check_interface(
+    attributes: &[ActiveVariable],
+    uniforms: &[ActiveVariable],
+    description: &ShaderDescription,
+) -> Result<(), String> {
+    for attribute in attributes {
+        let name = &attribute.name;
+        let Some(location) = description
+            .inputs
+            .iter()
+            .position(|input| input.name == name)
+        else {
+            return Err(format!("Input `{name}` is not declared"));
+        };
+        let input = &description.inputs[location];
+        if get_data_type(attribute.typ) != Some(input.typ) {
+            return Err(format!(
+                "Input `{name}` is declared as a {:?} but the glsl type does not match",
+                input.typ
+            ));
+        }
+        if attribute.location != location as GLint {
+            return Err(format!(
+                "Input `{name}` is declared as input {location} but the glsl puts it at location {}",
+                attribute.location
+            ));
         }
     }
+
+    for active_uniform in uniforms {
+        let name = &active_uniform.name;
+        // Arrays are declared with the type of their elements
+        let Some(uniform) = description
+            .uniforms
+            .iter()
+            .find(|uniform| uniform.name == name)
+        else {
+            return Err(format!("Uniform `{name}` is not declared"));
+        };
+        if get_data_type(active_uniform.typ) != Some(uniform.typ) {
+            return Err(format!(
+                "Uniform `{name}` is declared as a {:?} but the glsl type does not match",
+                uniform.typ
+            ));
+        }
+        if active_uniform.array_size > uniform.array_size {
+            return Err(format!(
+                "Uniform `{name}` is declared with {} elements but the glsl has {}",
+                uniform.array_size, active_uniform.array_size
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that every active uniform block is declared, and points it at its declared binding
+unsafe fn bind_uniform_blocks(
+    program: GLuint,
+    description: &ShaderDescription,
+) -> Result<(), String> {
+    let mut max_bindings = 0;
+    gl::GetIntegerv(gl::MAX_UNIFORM_BUFFER_BINDINGS, &mut max_bindings);
+    let mut count = 0;
+    gl::GetProgramiv(program, gl::ACTIVE_UNIFORM_BLOCKS, &mut count);
+    let mut max_length = 0;
+    gl::GetProgramiv(
+        program,
+        gl::ACTIVE_UNIFORM_BLOCK_MAX_NAME_LENGTH,
+        &mut max_length,
+    );
+
+    for index in 0..count as GLuint {
+        let mut vec = vec![0u8; max_length as usize];
+        let mut length = 0;
+        gl::GetActiveUniformBlockName(
+            program,
+            index,
+            max_length,
+            &mut length,
+            vec.as_mut_ptr().cast(),
+        );
+        let name = std::str::from_utf8(&vec[..length as usize]).unwrap();
+        let Some(block) = description
+            .uniform_blocks
+            .iter()
+            .find(|block| block.name == name)
+        else {
+            return Err(format!("Uniform block `{name}` is not declared"));
+        };
+        if block.binding >= max_bindings as usize {
+            return Err(format!(
+                "Uniform block `{name}` is declared at binding {} but there are only {max_bindings} bindings",
+                block.binding
+            ));
+        }
+        gl::UniformBlockBinding(program, index, block.binding as _);
+    }
+    Ok(())
+}
+
+/// Only valid after `check_interface` has succeeded, so every type is known
+fn to_shader_variables(variables: Vec<ActiveVariable>) -> Vec<ShaderVariable> {
+    variables
+        .into_iter()
+        .map(|variable| ShaderVariable {
+            name: variable.name,
+            typ: get_data_type(variable.typ).unwrap(),
+            array_size: variable.array_size,
+            location: variable.location as usize,
+        })
+        .collect()
 }
 
 impl Drop for OpenGLShader {
     fn drop(&mut self) {
-        unsafe {
-            gl::DeleteProgram(self.opengl_id);
+        if self.context.make_share_group_current() {
+            unsafe {
+                gl::DeleteProgram(self.opengl_id);
+            }
         }
     }
 }
@@ -167,4 +520,32 @@ impl Shader for OpenGLShader {
     fn get_id(&self) -> ShaderID {
         self.id
     }
+
+    fn get_inputs(&self) -> &[ShaderVariable] {
+        &self.inputs
+    }
+
+    fn get_uniforms(&self) -> &[ShaderVariable] {
+        &self.uniforms
+    }
+
+    fn set_uniform(&mut self, name: &str, value: UniformValue) -> Result<(), RendererError> {
+        check_uniform_value(&self.declared_uniforms, name, &value)?;
+        check_uniform_array_size(&self.declared_variables, name, &value)?;
+        let (location, array_size) = self.uniform_locations[name];
+        if let UniformValue::Texture(id) = value {
+            if location != -1 {
+                self.textures.retain(|&(other, _)| other != location);
+                self.textures.push((location, id));
+            }
+            return Ok(());
+        }
+        self.context.make_current();
+        unsafe {
+            gl::UseProgram(self.opengl_id);
+            upload_uniform(location, array_size, value);
+            gl::UseProgram(0);
+        }
+        Ok(())
+    }
 }
@@ -1,58 +1,108 @@
-use std::{marker::PhantomData, sync::atomic::AtomicUsize};
+use std::{
+    cell::Cell,
+    ffi::{c_void, CStr},
+    marker::PhantomData,
+    rc::Rc,
+    sync::atomic::AtomicUsize,
+};
 
-use gl::types::GLuint;
+use gl::types::{GLenum, GLuint};
 
 use crate::{
     math::{Vector2, Vector4, Zero},
-    renderer::{Pixels, Texture, TextureID},
+    renderer::{
+        opengl::OpenGLContext,
+        texture::{check_pixel_count, check_region_in_bounds},
+        Pixels, RendererError, SamplerDescription, Texture, TextureFilter, TextureFormat,
+        TextureID, TextureWrap,
+    },
     PhantomUnsend, PhantomUnsync,
 };
 
+// These are the same for GL_EXT_texture_filter_anisotropic and opengl 4.6
+const TEXTURE_MAX_ANISOTROPY: GLenum = 0x84FE;
+const MAX_TEXTURE_MAX_ANISOTROPY: GLenum = 0x84FF;
+
+/// What renderers that share a texture have in common
+pub(crate) struct OpenGLTextureData {
+    /// The context that created the texture
+    context: Rc<OpenGLContext>,
+    opengl_id: GLuint,
+    size: Cell<Vector2<usize>>,
+    format: TextureFormat,
+    sampler: Cell<SamplerDescription>,
+}
+
 pub(crate) struct OpenGLTexture {
     id: TextureID,
-    opengl_id: GLuint,
-    size: Vector2<usize>,
+    context: Rc<OpenGLContext>,
+    data: Rc<OpenGLTextureData>,
     _send: PhantomUnsend,
     _sync: PhantomUnsync,
 }
 
 impl OpenGLTexture {
-    pub(crate) fn new(size: Vector2<usize>, pixels: Pixels) -> OpenGLTexture {
-        unsafe {
-            let mut texture = 0;
-            gl::GenTextures(1, &mut texture);
-
-            let mut texture = OpenGLTexture {
-                id: {
-                    static ID: AtomicUsize = AtomicUsize::new(1);
-                    TextureID(
-                        ID.fetch_add(1, std::sync::atomic::Ordering::AcqRel),
-                        PhantomData,
-                        PhantomData,
-                    )
-                },
+    pub(crate) fn new(
+        context: &Rc<OpenGLContext>,
+        size: Vector2<usize>,
+        pixels: Pixels,
+        format: Option<TextureFormat>,
+        sampler: SamplerDescription,
+    ) -> Result<OpenGLTexture, RendererError> {
+        let mut texture = 0;
+        unsafe { gl::GenTextures(1, &mut texture) };
+
+        let mut texture = OpenGLTexture::from_shared(
+            {
+                static ID: AtomicUsize = AtomicUsize::new(1);
+                TextureID(
+                    ID.fetch_add(1, std::sync::atomic::Ordering::AcqRel),
+                    PhantomData,
+                    PhantomData,
+                )
+            },
+            context,
+            Rc::new(OpenGLTextureData {
+                context: context.clone(),
                 opengl_id: texture,
-                size: 0.into(),
-                _send: PhantomData,
-                _sync: PhantomData,
-            };
+                size: Cell::new(0.into()),
+                format: format.unwrap_or_else(|| TextureFormat::from_pixels(&pixels)),
+                sampler: Cell::new(sampler),
+            }),
+        );
 
-            texture.bind(0);
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as _);
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as _);
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as _);
-            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as _);
-            texture.unbind();
+        texture.set_pixels(size, pixels)?;
+        texture.set_sampler(sampler);
+        Ok(texture)
+    }
 
-            texture.set_pixels(size, pixels);
-            texture
+    /// A texture of another renderer in the same share group
+    pub(crate) fn from_shared(
+        id: TextureID,
+        context: &Rc<OpenGLContext>,
+        data: Rc<OpenGLTextureData>,
+    ) -> OpenGLTexture {
+        OpenGLTexture {
+            id,
+            context: context.clone(),
+            data,
+            _send: PhantomData,
+            _sync: PhantomData,
         }
     }
 
+    pub(crate) fn get_data(&self) -> &Rc<OpenGLTextureData> {
+        &self.data
+    }
+
+    pub(crate) fn get_opengl_id(&self) -> GLuint {
+        self.data.opengl_id
+    }
+
     pub(crate) fn bind(&mut self, unit: u32) {
         unsafe {
-            gl::BindTexture(gl::TEXTURE_2D, self.opengl_id);
-            gl::BindTextureUnit(unit, self.opengl_id);
+            gl::BindTexture(gl::TEXTURE_2D, self.data.opengl_id);
+            gl::BindTextureUnit(unit, self.data.opengl_id);
         }
     }
 
@@ -61,9 +111,88 @@ impl OpenGLTexture {
     }
 }
 
-impl Drop for OpenGLTexture {
+fn supports_anisotropy() -> bool {
+    unsafe {
+        let mut count = 0;
+        gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count);
+        (0..count as GLuint).any(|i| {
+            let extension = CStr::from_ptr(gl::GetStringi(gl::EXTENSIONS, i).cast());
+            matches!(
+                extension.to_bytes(),
+                b"GL_EXT_texture_filter_anisotropic" | b"GL_ARB_texture_filter_anisotropic"
+            )
+        })
+    }
+}
+
+fn get_internal_format(format: TextureFormat) -> GLenum {
+    match format {
+        TextureFormat::R8 => gl::R8,
+        TextureFormat::RG8 => gl::RG8,
+        TextureFormat::RGB8 => gl::RGB8,
+        TextureFormat::RGBA8 => gl::RGBA8,
+        TextureFormat::SRGB8Alpha8 => gl::SRGB8_ALPHA8,
+        TextureFormat::R16 => gl::R16,
+        TextureFormat::RG16 => gl::RG16,
+        TextureFormat::RGB16 => gl::RGB16,
+        TextureFormat::RGBA16 => gl::RGBA16,
+        TextureFormat::R16F => gl::R16F,
+        TextureFormat::RG16F => gl::RG16F,
+        TextureFormat::RGB16F => gl::RGB16F,
+        TextureFormat::RGBA16F => gl::RGBA16F,
+        TextureFormat::R32F => gl::R32F,
+        TextureFormat::RG32F => gl::RG32F,
+        TextureFormat::RGB32F => gl::RGB32F,
+        TextureFormat::RGBA32F => gl::RGBA32F,
+        TextureFormat::Depth16 => gl::DEPTH_COMPONENT16,
+        TextureFormat::Depth24 => gl::DEPTH_COMPONENT24,
+        TextureFormat::Depth32F => gl::DEPTH_COMPONENT32F,
+    }
+}
+
+/// The format, type and data to upload `pixels` to a texture with `format`
+fn get_pixel_transfer(
+    pixels: &Pixels,
+    format: TextureFormat,
+) -> Result<(GLenum, GLenum, *const c_void), RendererError> {
+    let (channels, typ, data) = match pixels {
+        Pixels::R(pixels) => (gl::RED, gl::UNSIGNED_BYTE, pixels.as_ptr().cast()),
+        Pixels::RG(pixels) => (gl::RG, gl::UNSIGNED_BYTE, pixels.as_ptr().cast()),
+        Pixels::RGB(pixels) => (gl::RGB, gl::UNSIGNED_BYTE, pixels.as_ptr().cast()),
+        Pixels::RGBA(pixels) => (gl::RGBA, gl::UNSIGNED_BYTE, pixels.as_ptr().cast()),
+        Pixels::R16(pixels) => (gl::RED, gl::UNSIGNED_SHORT, pixels.as_ptr().cast()),
+        Pixels::RG16(pixels) => (gl::RG, gl::UNSIGNED_SHORT, pixels.as_ptr().cast()),
+        Pixels::RGB16(pixels) => (gl::RGB, gl::UNSIGNED_SHORT, pixels.as_ptr().cast()),
+        Pixels::RGBA16(pixels) => (gl::RGBA, gl::UNSIGNED_SHORT, pixels.as_ptr().cast()),
+        Pixels::RF(pixels) => (gl::RED, gl::FLOAT, pixels.as_ptr().cast()),
+        Pixels::RGF(pixels) => (gl::RG, gl::FLOAT, pixels.as_ptr().cast()),
+        Pixels::RGBF(pixels) => (gl::RGB, gl::FLOAT, pixels.as_ptr().cast()),
+        Pixels::RGBAF(pixels) => (gl::RGBA, gl::FLOAT, pixels.as_ptr().cast()),
+    };
+    if format.is_depth() {
+        // Depth textures can only be set from single channel pixels
+        if channels != gl::RED {
+            return Err(RendererError::PixelFormatMismatch(format));
+        }
+        Ok((gl::DEPTH_COMPONENT, typ, data))
+    } else {
+        Ok((channels, typ, data))
+    }
+}
+
+fn get_wrap(wrap: TextureWrap) -> GLenum {
+    match wrap {
+        TextureWrap::Repeat => gl::REPEAT,
+        TextureWrap::MirroredRepeat => gl::MIRRORED_REPEAT,
+        TextureWrap::ClampToEdge => gl::CLAMP_TO_EDGE,
+    }
+}
+
+impl Drop for OpenGLTextureData {
     fn drop(&mut self) {
-        unsafe { gl::DeleteTextures(1, &self.opengl_id) };
+        if self.context.make_share_group_current() {
+            unsafe { gl::DeleteTextures(1, &self.opengl_id) };
+        }
     }
 }
 
@@ -73,59 +202,215 @@ impl Texture for OpenGLTexture {
     }
 
     fn get_size(&self) -> Vector2<usize> {
-        self.size
+        self.data.size.get()
+    }
+
+    fn get_format(&self) -> TextureFormat {
+        self.data.format
+    }
+
+    fn get_sampler(&self) -> SamplerDescription {
+        self.data.sampler.get()
     }
 
-    fn set_pixels(&mut self, size: Vector2<usize>, pixels: Pixels) {
+    fn set_sampler(&mut self, sampler: SamplerDescription) {
+        let generate_mipmaps = sampler.mipmaps && !self.get_sampler().mipmaps;
+        self.data.sampler.set(sampler);
+        self.context.make_current();
         unsafe {
             self.bind(0);
-            self.size = size;
-            let (format, typ, pixels) = match pixels {
-                Pixels::RGB(pixels) => {
-                    assert_eq!(size.x * size.y, pixels.len());
-                    (gl::RGB, gl::UNSIGNED_BYTE, pixels.as_ptr().cast())
-                }
-                Pixels::RGBA(pixels) => {
-                    assert_eq!(size.x * size.y, pixels.len());
-                    (gl::RGBA, gl::UNSIGNED_BYTE, pixels.as_ptr().cast())
-                }
-                Pixels::RGBF(pixels) => {
-                    assert_eq!(size.x * size.y, pixels.len());
-                    (gl::RGB, gl::FLOAT, pixels.as_ptr().cast())
-                }
-                Pixels::RGBAF(pixels) => {
-                    assert_eq!(size.x * size.y, pixels.len());
-                    (gl::RGBA, gl::FLOAT, pixels.as_ptr().cast())
-                }
+            let min_filter = match (sampler.min_filter, sampler.mipmaps) {
+                (TextureFilter::Nearest, false) => gl::NEAREST,
+                (TextureFilter::Linear | TextureFilter::Trilinear, false) => gl::LINEAR,
+                (TextureFilter::Nearest, true) => gl::NEAREST_MIPMAP_NEAREST,
+                (TextureFilter::Linear, true) => gl::LINEAR_MIPMAP_NEAREST,
+                (TextureFilter::Trilinear, true) => gl::LINEAR_MIPMAP_LINEAR,
             };
+            let mag_filter = match sampler.mag_filter {
+                TextureFilter::Nearest => gl::NEAREST,
+                TextureFilter::Linear | TextureFilter::Trilinear => gl::LINEAR,
+            };
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, min_filter as _);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, mag_filter as _);
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_WRAP_S,
+                get_wrap(sampler.wrap_u) as _,
+            );
+            gl::TexParameteri(
+                gl::TEXTURE_2D,
+                gl::TEXTURE_WRAP_T,
+                get_wrap(sampler.wrap_v) as _,
+            );
+            if supports_anisotropy() {
+                let mut max_anisotropy = 1.0;
+                gl::GetFloatv(MAX_TEXTURE_MAX_ANISOTROPY, &mut max_anisotropy);
+                gl::TexParameterf(
+                    gl::TEXTURE_2D,
+                    TEXTURE_MAX_ANISOTROPY,
+                    sampler.max_anisotropy.clamp(1.0, max_anisotropy),
+                );
+            }
+            if generate_mipmaps {
+                gl::GenerateMipmap(gl::TEXTURE_2D);
+            }
+            self.unbind();
+        }
+    }
+
+    fn set_pixels(&mut self, size: Vector2<usize>, pixels: Pixels) -> Result<(), RendererError> {
+        check_pixel_count(size, &pixels)?;
+        let (format, typ, data) = get_pixel_transfer(&pixels, self.get_format())?;
+        self.context.make_current();
+        unsafe {
+            self.bind(0);
+            self.data.size.set(size);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
             gl::TexImage2D(
                 gl::TEXTURE_2D,
                 0,
-                gl::RGBA32F as _,
+                get_internal_format(self.get_format()) as _,
                 size.x as _,
                 size.y as _,
                 0,
                 format,
                 typ,
-                pixels,
+                data,
             );
+            if self.get_sampler().mipmaps {
+                gl::GenerateMipmap(gl::TEXTURE_2D);
+            }
             self.unbind();
         }
+        Ok(())
     }
 
-    fn get_pixels(&self) -> Vec<Vector4<f32>> {
+    fn set_sub_pixels(
+        &mut self,
+        offset: Vector2<usize>,
+        size: Vector2<usize>,
+        pixels: Pixels,
+    ) -> Result<(), RendererError> {
+        check_region_in_bounds(self.get_size(), offset, size)?;
+        check_pixel_count(size, &pixels)?;
+        let (format, typ, data) = get_pixel_transfer(&pixels, self.get_format())?;
+        self.context.make_current();
         unsafe {
-            let mut pixels = vec![Vector4::zero(); self.size.x * self.size.y];
-            gl::BindTexture(gl::TEXTURE_2D, self.opengl_id);
-            gl::GetTexImage(
+            self.bind(0);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexSubImage2D(
                 gl::TEXTURE_2D,
                 0,
-                gl::RGBA,
-                gl::FLOAT,
-                pixels.as_mut_ptr().cast(),
+                offset.x as _,
+                offset.y as _,
+                size.x as _,
+                size.y as _,
+                format,
+                typ,
+                data,
             );
+            if self.get_sampler().mipmaps {
+                gl::GenerateMipmap(gl::TEXTURE_2D);
+            }
+            self.unbind();
+        }
+        Ok(())
+    }
+
+    fn get_pixels(&self) -> Vec<Vector4<f32>> {
+        let size = self.get_size();
+        self.context.make_current();
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, self.data.opengl_id);
+            let pixels = if self.get_format().is_depth() {
+                let mut depths = vec![0.0f32; size.x * size.y];
+                gl::GetTexImage(
+                    gl::TEXTURE_2D,
+                    0,
+                    gl::DEPTH_COMPONENT,
+                    gl::FLOAT,
+                    depths.as_mut_ptr().cast(),
+                );
+                depths
+                    .into_iter()
+                    .map(|depth| (depth, 0.0, 0.0, 1.0).into())
+                    .collect()
+            } else {
+                let mut pixels = vec![Vector4::zero(); size.x * size.y];
+                gl::GetTexImage(
+                    gl::TEXTURE_2D,
+                    0,
+                    gl::RGBA,
+                    gl::FLOAT,
+                    pixels.as_mut_ptr().cast(),
+                );
+                pixels
+            };
             gl::BindTexture(gl::TEXTURE_2D, 0);
             pixels
         }
     }
+
+    fn get_sub_pixels(
+        &self,
+        offset: Vector2<usize>,
+        size: Vector2<usize>,
+    ) -> Result<Vec<Vector4<f32>>, RendererError> {
+        check_region_in_bounds(self.get_size(), offset, size)?;
+        self.context.make_current();
+        // There is no way to read part of a texture before opengl 4.5, so it gets attached to a
+        // temporary framebuffer that is read from instead
+        unsafe {
+            let mut previous_read_framebuffer = 0;
+            gl::GetIntegerv(gl::READ_FRAMEBUFFER_BINDING, &mut previous_read_framebuffer);
+            let mut framebuffer = 0;
+            gl::GenFramebuffers(1, &mut framebuffer);
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, framebuffer);
+
+            let attachment = if self.get_format().is_depth() {
+                gl::DEPTH_ATTACHMENT
+            } else {
+                gl::ReadBuffer(gl::COLOR_ATTACHMENT0);
+                gl::COLOR_ATTACHMENT0
+            };
+            gl::FramebufferTexture2D(
+                gl::READ_FRAMEBUFFER,
+                attachment,
+                gl::TEXTURE_2D,
+                self.data.opengl_id,
+                0,
+            );
+
+            let mut pixels = vec![Vector4::zero(); size.x * size.y];
+            if self.get_format().is_depth() {
+                let mut depths = vec![0.0f32; size.x * size.y];
+                gl::ReadPixels(
+                    offset.x as _,
+                    offset.y as _,
+                    size.x as _,
+                    size.y as _,
+                    gl::DEPTH_COMPONENT,
+                    gl::FLOAT,
+                    depths.as_mut_ptr().cast(),
+                );
+                for (pixel, depth) in pixels.iter_mut().zip(depths) {
+                    *pixel = (depth, 0.0, 0.0, 1.0).into();
+                }
+            } else {
+                gl::ReadPixels(
+                    offset.x as _,
+                    offset.y as _,
+                    size.x as _,
+                    size.y as _,
+                    gl::RGBA,
+                    gl::FLOAT,
+                    pixels.as_mut_ptr().cast(),
+                );
+            }
+
+            gl::BindFramebuffer(gl::READ_FRAMEBUFFER, previous_read_framebuffer as _);
+            gl::DeleteFramebuffers(1, &framebuffer);
+            Ok(pixels)
+        }
+    }
 }
@@ -0,0 +1,88 @@
+use std::{marker::PhantomData, rc::Rc, sync::atomic::AtomicUsize};
+
+use crate::{
+    renderer::{
+        opengl::{OpenGLBuffer, OpenGLContext},
+        uniform_buffer::check_range_in_bounds,
+        RendererError, UniformBuffer, UniformBufferID,
+    },
+    PhantomUnsend, PhantomUnsync,
+};
+
+pub(crate) struct OpenGLUniformBuffer {
+    id: UniformBufferID,
+    context: Rc<OpenGLContext>,
+    buffer: Rc<OpenGLBuffer>,
+    _send: PhantomUnsend,
+    _sync: PhantomUnsync,
+}
+
+impl OpenGLUniformBuffer {
+    pub(crate) fn new(context: &Rc<OpenGLContext>, data: &[u8]) -> OpenGLUniformBuffer {
+        let mut uniform_buffer = OpenGLUniformBuffer::from_shared(
+            {
+                static ID: AtomicUsize = AtomicUsize::new(1);
+                UniformBufferID(
+                    ID.fetch_add(1, std::sync::atomic::Ordering::AcqRel),
+                    PhantomData,
+                    PhantomData,
+                )
+            },
+            context,
+            Rc::new(OpenGLBuffer::new(context)),
+        );
+        uniform_buffer.set_data(data);
+        uniform_buffer
+    }
+
+    /// A uniform buffer of another renderer in the same share group
+    pub(crate) fn from_shared(
+        id: UniformBufferID,
+        context: &Rc<OpenGLContext>,
+        buffer: Rc<OpenGLBuffer>,
+    ) -> OpenGLUniformBuffer {
+        OpenGLUniformBuffer {
+            id,
+            context: context.clone(),
+            buffer,
+            _send: PhantomData,
+            _sync: PhantomData,
+        }
+    }
+
+    pub(crate) fn get_buffer(&self) -> &Rc<OpenGLBuffer> {
+        &self.buffer
+    }
+
+    pub(crate) fn bind_to(&self, binding: usize) {
+        unsafe {
+            gl::BindBufferBase(
+                gl::UNIFORM_BUFFER,
+                binding as _,
+                self.buffer.get_opengl_id(),
+            )
+        };
+    }
+}
+
+impl UniformBuffer for OpenGLUniformBuffer {
+    fn get_id(&self) -> UniformBufferID {
+        self.id
+    }
+
+    fn get_size(&self) -> usize {
+        self.buffer.get_size()
+    }
+
+    fn set_data(&mut self, data: &[u8]) {
+        self.context.make_current();
+        self.buffer.set_data(gl::UNIFORM_BUFFER, data);
+    }
+
+    fn set_sub_data(&mut self, offset: usize, data: &[u8]) -> Result<(), RendererError> {
+        check_range_in_bounds(self.get_size(), offset, data.len())?;
+        self.context.make_current();
+        self.buffer.set_sub_data(gl::UNIFORM_BUFFER, offset, data);
+        Ok(())
+    }
+}
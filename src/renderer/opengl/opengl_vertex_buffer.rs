@@ -1,71 +1,190 @@
-use std::{marker::PhantomData, mem::size_of, sync::atomic::AtomicUsize};
+use std::{cell::RefCell, marker::PhantomData, rc::Rc, sync::atomic::AtomicUsize};
 
-use gl::types::GLuint;
+use gl::types::{GLenum, GLuint};
 
 use crate::{
-    renderer::{VertexBuffer, VertexBufferElement, VertexBufferID},
+    renderer::{
+        opengl::{OpenGLBuffer, OpenGLContext},
+        vertex_buffer::{
+            get_vertex_attributes, get_vertex_count, VertexAttribute, VertexComponentType,
+        },
+        RendererError, VertexBuffer, VertexBufferElement, VertexBufferID,
+    },
     PhantomUnsend, PhantomUnsync,
 };
 
+/// What renderers that share a vertex buffer have in common. Like vertex arrays in opengl the
+/// layout is not shared, each renderer starts with the one that was last set by any of them
+pub(crate) struct OpenGLVertexData {
+    buffer: OpenGLBuffer,
+    layout: RefCell<Vec<VertexBufferElement>>,
+}
+
 pub(crate) struct OpenGLVertexBuffer {
     id: VertexBufferID,
+    context: Rc<OpenGLContext>,
     opengl_vertex_array_id: GLuint,
-    opengl_id: GLuint,
+    data: Rc<OpenGLVertexData>,
+    layout: Vec<VertexBufferElement>,
+    attributes: Vec<VertexAttribute>,
     stride: usize,
-    count: usize,
     _send: PhantomUnsend,
     _sync: PhantomUnsync,
 }
 
 impl OpenGLVertexBuffer {
-    pub(crate) fn new(layout: &[VertexBufferElement], data: &[u8]) -> OpenGLVertexBuffer {
-        let mut vertex_buffer = unsafe {
-            let mut vertex_array = 0;
-            gl::GenVertexArrays(1, &mut vertex_array);
-            let mut vertex_buffer = 0;
-            gl::GenBuffers(1, &mut vertex_buffer);
-
-            OpenGLVertexBuffer {
-                id: {
-                    static ID: AtomicUsize = AtomicUsize::new(1);
-                    VertexBufferID(
-                        ID.fetch_add(1, std::sync::atomic::Ordering::AcqRel),
-                        PhantomData,
-                        PhantomData,
-                    )
-                },
-                opengl_vertex_array_id: vertex_array,
-                opengl_id: vertex_buffer,
-                stride: 0,
-                count: 0,
-                _send: PhantomData,
-                _sync: PhantomData,
-            }
+    pub(crate) fn new(
+        context: &Rc<OpenGLContext>,
+        layout: &[VertexBufferElement],
+        data: &[u8],
+    ) -> Result<OpenGLVertexBuffer, RendererError> {
+        let mut vertex_buffer = OpenGLVertexBuffer::from_shared(
+            {
+                static ID: AtomicUsize = AtomicUsize::new(1);
+                VertexBufferID(
+                    ID.fetch_add(1, std::sync::atomic::Ordering::AcqRel),
+                    PhantomData,
+                    PhantomData,
+                )
+            },
+            context,
+            Rc::new(OpenGLVertexData {
+                buffer: OpenGLBuffer::new(context),
+                layout: RefCell::new(vec![]),
+            }),
+        );
+        vertex_buffer.set_layout(layout, data)?;
+        Ok(vertex_buffer)
+    }
+
+    /// A vertex buffer of another renderer in the same share group, it gets its own vertex array
+    pub(crate) fn from_shared(
+        id: VertexBufferID,
+        context: &Rc<OpenGLContext>,
+        data: Rc<OpenGLVertexData>,
+    ) -> OpenGLVertexBuffer {
+        let mut vertex_array = 0;
+        unsafe { gl::GenVertexArrays(1, &mut vertex_array) };
+        let mut vertex_buffer = OpenGLVertexBuffer {
+            id,
+            context: context.clone(),
+            opengl_vertex_array_id: vertex_array,
+            data,
+            layout: vec![],
+            attributes: vec![],
+            stride: 0,
+            _send: PhantomData,
+            _sync: PhantomData,
         };
-        vertex_buffer.set_layout(layout, data);
+        let layout = vertex_buffer.data.layout.borrow().clone();
+        vertex_buffer.apply_layout(&layout);
         vertex_buffer
     }
 
-    pub(crate) fn bind(&mut self) {
+    pub(crate) fn get_data(&self) -> &Rc<OpenGLVertexData> {
+        &self.data
+    }
+
+    pub(crate) fn bind(&self) {
         unsafe {
             gl::BindVertexArray(self.opengl_vertex_array_id);
-            gl::BindBuffer(gl::ARRAY_BUFFER, self.opengl_id);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.data.buffer.get_opengl_id());
         }
     }
 
-    pub(crate) fn unbind(&mut self) {
+    pub(crate) fn unbind(&self) {
         unsafe {
             gl::BindVertexArray(0);
             gl::BindBuffer(gl::ARRAY_BUFFER, 0);
         }
     }
+
+    /// Adds the attributes of this buffer to the bound vertex array of another vertex buffer, one
+    /// vertex per instance
+    pub(crate) fn bind_instances(&self) {
+        unsafe { gl::BindBuffer(gl::ARRAY_BUFFER, self.data.buffer.get_opengl_id()) };
+        set_attribute_pointers(&self.attributes, self.stride, 1);
+    }
+
+    /// Takes the attributes of this buffer out of the bound vertex array again
+    pub(crate) fn unbind_instances(&self) {
+        for attribute in &self.attributes {
+            unsafe {
+                gl::VertexAttribDivisor(attribute.location as _, 0);
+                gl::DisableVertexAttribArray(attribute.location as _);
+            }
+        }
+    }
+
+    /// Points the vertex array at the data, the layout has to be checked against the data first
+    fn apply_layout(&mut self, layout: &[VertexBufferElement]) {
+        let (attributes, stride) = get_vertex_attributes(layout).unwrap();
+
+        self.bind();
+        for attribute in &self.attributes {
+            unsafe { gl::DisableVertexAttribArray(attribute.location as _) };
+        }
+        set_attribute_pointers(&attributes, stride, 0);
+        self.unbind();
+        self.layout = layout.to_vec();
+        self.attributes = attributes;
+        self.stride = stride;
+    }
+}
+
+/// Reads the attributes from the bound array buffer into the bound vertex array, advancing to the
+/// next vertex once every `divisor` instances or for every vertex if it is `0`
+fn set_attribute_pointers(attributes: &[VertexAttribute], stride: usize, divisor: u32) {
+    for attribute in attributes {
+        let (typ, count) = attribute.element.get_components().unwrap();
+        let location = attribute.location as _;
+        let offset = attribute.offset as _;
+        unsafe {
+            gl::EnableVertexAttribArray(location);
+            if typ.is_integer() {
+                gl::VertexAttribIPointer(
+                    location,
+                    count as _,
+                    get_opengl_type(typ),
+                    stride as _,
+                    offset,
+                );
+            } else {
+                let normalized =
+                    !matches!(typ, VertexComponentType::Float | VertexComponentType::Half);
+                gl::VertexAttribPointer(
+                    location,
+                    count as _,
+                    get_opengl_type(typ),
+                    normalized as _,
+                    stride as _,
+                    offset,
+                );
+            }
+            gl::VertexAttribDivisor(location, divisor);
+        }
+    }
+}
+
+fn get_opengl_type(typ: VertexComponentType) -> GLenum {
+    match typ {
+        VertexComponentType::Float => gl::FLOAT,
+        VertexComponentType::Half => gl::HALF_FLOAT,
+        VertexComponentType::Int => gl::INT,
+        VertexComponentType::UInt => gl::UNSIGNED_INT,
+        VertexComponentType::UByteNormalized => gl::UNSIGNED_BYTE,
+        VertexComponentType::ByteNormalized => gl::BYTE,
+        VertexComponentType::UShortNormalized => gl::UNSIGNED_SHORT,
+        VertexComponentType::ShortNormalized => gl::SHORT,
+    }
 }
 
 impl Drop for OpenGLVertexBuffer {
     fn drop(&mut self) {
-        unsafe {
-            gl::DeleteBuffers(1, &self.opengl_id);
-            gl::DeleteVertexArrays(1, &self.opengl_vertex_array_id);
+        // Vertex arrays are not shared, they go away with the context that made them
+        if !self.context.is_destroyed() {
+            self.context.make_current();
+            unsafe { gl::DeleteVertexArrays(1, &self.opengl_vertex_array_id) };
         }
     }
 }
@@ -75,70 +194,38 @@ impl VertexBuffer for OpenGLVertexBuffer {
         self.id
     }
 
+    /// Another renderer that shares the buffer can change the data to something that is not a
+    /// whole number of vertices in this layout, the rest is not drawn
     fn get_count(&self) -> usize {
-        self.count
+        match self.stride {
+            0 => 0,
+            stride => self.data.buffer.get_size() / stride,
+        }
     }
 
-    fn set_layout(&mut self, layout: &[VertexBufferElement], data: &[u8]) {
-        fn get_element_size(element: &VertexBufferElement) -> usize {
-            match element {
-                VertexBufferElement::Float => 1 * size_of::<f32>(),
-                VertexBufferElement::Float2 => 2 * size_of::<f32>(),
-                VertexBufferElement::Float3 => 3 * size_of::<f32>(),
-                VertexBufferElement::Float4 => 4 * size_of::<f32>(),
-            }
-        }
+    fn get_layout(&self) -> &[VertexBufferElement] {
+        &self.layout
+    }
 
-        self.stride = layout.iter().map(get_element_size).sum();
+    fn set_layout(
+        &mut self,
+        layout: &[VertexBufferElement],
+        data: &[u8],
+    ) -> Result<(), RendererError> {
+        // Checked before anything changes so the buffer is left as it was
+        let (_, stride) = get_vertex_attributes(layout)?;
+        get_vertex_count(stride, data)?;
 
-        self.bind();
-        let mut offset = 0;
-        for (i, element) in layout.iter().enumerate() {
-            unsafe {
-                gl::EnableVertexAttribArray(i as _);
-                gl::VertexAttribPointer(
-                    i as _,
-                    match element {
-                        VertexBufferElement::Float => 1,
-                        VertexBufferElement::Float2 => 2,
-                        VertexBufferElement::Float3 => 3,
-                        VertexBufferElement::Float4 => 4,
-                    },
-                    match element {
-                        VertexBufferElement::Float
-                        | VertexBufferElement::Float2
-                        | VertexBufferElement::Float3
-                        | VertexBufferElement::Float4 => gl::FLOAT,
-                    },
-                    false as _,
-                    self.stride as _,
-                    offset as _,
-                );
-            }
-            offset += get_element_size(&element);
-        }
-        self.unbind();
-
-        self.set_data(data);
+        self.context.make_current();
+        self.apply_layout(layout);
+        *self.data.layout.borrow_mut() = layout.to_vec();
+        self.set_data(data)
     }
 
-    fn set_data(&mut self, data: &[u8]) {
-        if self.stride > 0 {
-            assert_eq!(data.len() % self.stride, 0);
-            self.count = data.len() / self.stride;
-        } else {
-            assert_eq!(data.len(), 0);
-            self.count = 0;
-        }
-        self.bind();
-        unsafe {
-            gl::BufferData(
-                gl::ARRAY_BUFFER,
-                data.len() as _,
-                data.as_ptr().cast(),
-                gl::DYNAMIC_DRAW,
-            )
-        };
-        self.unbind();
+    fn set_data(&mut self, data: &[u8]) -> Result<(), RendererError> {
+        get_vertex_count(self.stride, data)?;
+        self.context.make_current();
+        self.data.buffer.set_data(gl::ARRAY_BUFFER, data);
+        Ok(())
     }
 }
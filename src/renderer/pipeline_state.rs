@@ -0,0 +1,190 @@
+use crate::scene::DepthConvention;
+
+/// How the color a fragment outputs is combined with the color that is already there, the same
+/// factors are used for the alpha channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlendMode {
+    /// The output replaces what is there
+    None,
+    /// `source * source.alpha + destination * (1 - source.alpha)`
+    Alpha,
+    /// `source * source.alpha + destination`
+    Additive,
+    /// `source * destination`
+    Multiply,
+    /// `source + destination * (1 - source.alpha)`, for colors that are already multiplied by
+    /// their alpha
+    PremultipliedAlpha,
+}
+
+/// Compares a new value to the one that is stored, the test passes if `new <op> stored` is true
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareFunction {
+    Never,
+    Less,
+    LessEqual,
+    Equal,
+    NotEqual,
+    GreaterEqual,
+    Greater,
+    Always,
+}
+
+impl CompareFunction {
+    pub(crate) fn compare<T: PartialOrd>(self, new: T, stored: T) -> bool {
+        match self {
+            CompareFunction::Never => false,
+            CompareFunction::Less => new < stored,
+            CompareFunction::LessEqual => new <= stored,
+            CompareFunction::Equal => new == stored,
+            CompareFunction::NotEqual => new != stored,
+            CompareFunction::GreaterEqual => new >= stored,
+            CompareFunction::Greater => new > stored,
+            CompareFunction::Always => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DepthState {
+    pub compare: CompareFunction,
+    /// Whether fragments that pass write their depth
+    pub write: bool,
+}
+
+impl DepthState {
+    /// Keeps the nearest fragment and writes its depth
+    pub fn keep_nearest(depth_convention: DepthConvention) -> DepthState {
+        DepthState {
+            compare: if depth_convention.reversed {
+                CompareFunction::GreaterEqual
+            } else {
+                CompareFunction::LessEqual
+            },
+            write: true,
+        }
+    }
+}
+
+impl Default for DepthState {
+    /// Keeps the nearest fragment with `DepthConvention::default()`
+    fn default() -> Self {
+        DepthState::keep_nearest(DepthConvention::default())
+    }
+}
+
+/// What happens to the stored stencil value, only the bits in `StencilState::write_mask` change
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StencilOperation {
+    Keep,
+    Zero,
+    /// Stores `StencilState::reference`
+    Replace,
+    /// Stops at `255`
+    Increment,
+    /// Stops at `0`
+    Decrement,
+    /// Goes from `255` back to `0`
+    IncrementWrap,
+    /// Goes from `0` back to `255`
+    DecrementWrap,
+    Invert,
+}
+
+impl StencilOperation {
+    pub(crate) fn apply(self, stored: u8, reference: u8) -> u8 {
+        match self {
+            StencilOperation::Keep => stored,
+            StencilOperation::Zero => 0,
+            StencilOperation::Replace => reference,
+            StencilOperation::Increment => stored.saturating_add(1),
+            StencilOperation::Decrement => stored.saturating_sub(1),
+            StencilOperation::IncrementWrap => stored.wrapping_add(1),
+            StencilOperation::DecrementWrap => stored.wrapping_sub(1),
+            StencilOperation::Invert => !stored,
+        }
+    }
+}
+
+/// The stencil test compares `reference & read_mask` to `stored & read_mask`, it only does
+/// anything when the target has a stencil buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StencilState {
+    pub compare: CompareFunction,
+    pub reference: u8,
+    pub read_mask: u8,
+    pub write_mask: u8,
+    /// When the stencil test fails
+    pub fail: StencilOperation,
+    /// When the stencil test passes but the depth test fails
+    pub depth_fail: StencilOperation,
+    /// When both tests pass, or the stencil test passes and there is no depth test
+    pub pass: StencilOperation,
+}
+
+impl Default for StencilState {
+    fn default() -> Self {
+        Self {
+            compare: CompareFunction::Always,
+            reference: 0,
+            read_mask: 0xFF,
+            write_mask: 0xFF,
+            fail: StencilOperation::Keep,
+            depth_fail: StencilOperation::Keep,
+            pass: StencilOperation::Keep,
+        }
+    }
+}
+
+/// Which channels of the color attachments get written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColorMask {
+    pub red: bool,
+    pub green: bool,
+    pub blue: bool,
+    pub alpha: bool,
+}
+
+impl ColorMask {
+    pub const ALL: ColorMask = ColorMask {
+        red: true,
+        green: true,
+        blue: true,
+        alpha: true,
+    };
+    pub const NONE: ColorMask = ColorMask {
+        red: false,
+        green: false,
+        blue: false,
+        alpha: false,
+    };
+}
+
+impl Default for ColorMask {
+    fn default() -> Self {
+        ColorMask::ALL
+    }
+}
+
+/// The fixed function state draws are done with, the default blends with alpha and has no depth
+/// or stencil test
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PipelineState {
+    pub blend: BlendMode,
+    /// `None` turns off the depth test, which also means no depth is written
+    pub depth: Option<DepthState>,
+    /// `None` turns off the stencil test, which also means the stencil is never written
+    pub stencil: Option<StencilState>,
+    pub color_mask: ColorMask,
+}
+
+impl Default for PipelineState {
+    fn default() -> Self {
+        Self {
+            blend: BlendMode::Alpha,
+            depth: None,
+            stencil: None,
+            color_mask: ColorMask::ALL,
+        }
+    }
+}
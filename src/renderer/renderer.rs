@@ -1,23 +1,32 @@
 use std::pin::Pin;
 
 use crate::{
-    math::{Matrix4x4, Vector2, Vector3},
+    math::{Matrix4x4, Vector2, Vector3, Vector4},
     platform::Surface,
     renderer::{
-        opengl::OpenGLRenderer, IndexBuffer, IndexBufferID, Pixels, Shader, ShaderID, Texture,
-        TextureID, VertexBuffer, VertexBufferElement, VertexBufferID,
+        opengl::OpenGLRenderer, software::SoftwareRenderer, DepthAttachment, Framebuffer,
+        FramebufferID, GlslShaderFiles, Image, IndexBuffer, IndexBufferID, Material, MaterialID,
+        PipelineState, Pixels, RendererError, SamplerDescription, Shader, ShaderDescription,
+        ShaderID, Texture, TextureFormat, TextureID, UniformBuffer, UniformBufferID, UniformValue,
+        VertexBuffer, VertexBufferElement, VertexBufferID,
     },
-    scene::Camera,
+    scene::{Camera, DepthConvention},
 };
 
 pub enum RendererAPI {
     OpenGL,
+    /// Rasterizes on the cpu, shaders are created from `ShaderDescription::software`
+    Software,
 }
 
-pub(crate) fn new_renderer(surface: Pin<Box<Surface>>, api: RendererAPI) -> Box<dyn Renderer> {
-    match api {
-        RendererAPI::OpenGL => Box::new(OpenGLRenderer::new(surface)),
-    }
+pub(crate) fn new_renderer(
+    surface: Pin<Box<Surface>>,
+    api: RendererAPI,
+) -> Result<Box<dyn Renderer>, RendererError> {
+    Ok(match api {
+        RendererAPI::OpenGL => Box::new(OpenGLRenderer::new(surface)?),
+        RendererAPI::Software => Box::new(SoftwareRenderer::new(surface)),
+    })
 }
 
 pub enum CullFace {
@@ -26,16 +35,74 @@ pub enum CullFace {
     CounterClockwise,
 }
 
+/// A rectangle of pixels, `position` is the bottom left corner like it is in opengl
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub position: Vector2<usize>,
+    pub size: Vector2<usize>,
+}
+
+impl Rect {
+    pub fn new(position: Vector2<usize>, size: Vector2<usize>) -> Rect {
+        Rect { position, size }
+    }
+
+    /// The aspect ratio for a camera that draws to this rectangle, `0.0` if it has no height
+    pub fn aspect_ratio(&self) -> f32 {
+        if self.size.y == 0 {
+            0.0
+        } else {
+            self.size.x as f32 / self.size.y as f32
+        }
+    }
+}
+
+/// What `Renderer::clear` clears, the buffers that are `None` keep their contents. The default
+/// clears everything, the color to opaque black and the depth to the far plane of
+/// `DepthConvention::default()`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClearDescription {
+    pub color: Option<Vector4<f32>>,
+    /// `DepthConvention::get_far_depth` is the depth nothing is behind
+    pub depth: Option<f32>,
+    pub stencil: Option<u8>,
+}
+
+impl Default for ClearDescription {
+    fn default() -> Self {
+        Self {
+            color: Some((0.0, 0.0, 0.0, 1.0).into()),
+            depth: Some(DepthConvention::default().get_far_depth()),
+            stencil: Some(0),
+        }
+    }
+}
+
 pub trait Renderer {
     fn get_surface(&self) -> &Surface;
     fn get_surface_mut(&mut self) -> &mut Surface;
     fn take_surface(self) -> Pin<Box<Surface>>;
+    /// Creates a renderer for another surface that can share textures and buffers with this one,
+    /// and with every other renderer that was created from either of them
+    fn create_shared_renderer(
+        &self,
+        surface: Pin<Box<Surface>>,
+    ) -> Result<Box<dyn Renderer>, RendererError>;
 
-    fn create_shader(
+    /// Fails if the description has no source for this api or the source does not match the
+    /// declared inputs and uniforms
+    fn create_shader(&mut self, description: ShaderDescription) -> Result<ShaderID, RendererError>;
+    /// Like `create_shader` but the glsl source is read from `files` instead of `description.glsl`,
+    /// the files are watched by `reload_shaders`
+    fn create_shader_from_files(
         &mut self,
-        vertex_shader_source: &str,
-        fragment_shader_source: &str,
-    ) -> Result<ShaderID, String>;
+        description: ShaderDescription,
+        files: GlslShaderFiles,
+    ) -> Result<ShaderID, RendererError>;
+    /// Compiles every shader made with `create_shader_from_files` again if its files changed, and
+    /// returns the result for each of them. A shader that fails keeps its previous program, the
+    /// ones that succeed keep their `ShaderID` but lose the values set with `set_uniform`
+    fn reload_shaders(&mut self) -> Vec<(ShaderID, Result<(), RendererError>)>;
     fn destroy_shader(&mut self, id: ShaderID);
     fn get_shader(&self, id: ShaderID) -> Option<&dyn Shader>;
     fn get_shader_mut(&mut self, id: ShaderID) -> Option<&mut dyn Shader>;
@@ -44,39 +111,166 @@ pub trait Renderer {
         &mut self,
         layout: &[VertexBufferElement],
         data: &[u8],
-    ) -> VertexBufferID;
+    ) -> Result<VertexBufferID, RendererError>;
+    /// Makes a vertex buffer of a renderer that shares resources with this one usable here under
+    /// the same id. Only the data is shared, the layout starts as the last one that was set and
+    /// changing it only affects this renderer
+    fn share_vertex_buffer(&mut self, id: VertexBufferID) -> Result<(), RendererError>;
     fn destroy_vertex_buffer(&mut self, id: VertexBufferID);
     fn get_vertex_buffer(&self, id: VertexBufferID) -> Option<&dyn VertexBuffer>;
     fn get_vertex_buffer_mut(&mut self, id: VertexBufferID) -> Option<&mut dyn VertexBuffer>;
 
     fn create_index_buffer(&mut self, indices: &[u32]) -> IndexBufferID;
+    /// Makes an index buffer of a renderer that shares resources with this one usable here under
+    /// the same id
+    fn share_index_buffer(&mut self, id: IndexBufferID) -> Result<(), RendererError>;
     fn destroy_index_buffer(&mut self, id: IndexBufferID);
     fn get_index_buffer(&self, id: IndexBufferID) -> Option<&dyn IndexBuffer>;
     fn get_index_buffer_mut(&mut self, id: IndexBufferID) -> Option<&mut dyn IndexBuffer>;
 
-    fn create_texture(&mut self, size: Vector2<usize>, pixels: Pixels) -> TextureID;
+    fn create_uniform_buffer(&mut self, data: &[u8]) -> UniformBufferID;
+    /// Makes a uniform buffer of a renderer that shares resources with this one usable here under
+    /// the same id
+    fn share_uniform_buffer(&mut self, id: UniformBufferID) -> Result<(), RendererError>;
+    fn destroy_uniform_buffer(&mut self, id: UniformBufferID);
+    fn get_uniform_buffer(&self, id: UniformBufferID) -> Option<&dyn UniformBuffer>;
+    fn get_uniform_buffer_mut(&mut self, id: UniformBufferID) -> Option<&mut dyn UniformBuffer>;
+    /// Every shader that declares a uniform block at `binding` reads from `uniform_buffer` until
+    /// the binding is changed, the bindings of `BUILTIN_UNIFORM_BLOCKS` can not be set
+    fn set_uniform_buffer_binding(
+        &mut self,
+        binding: usize,
+        uniform_buffer: Option<UniformBufferID>,
+    ) -> Result<(), RendererError>;
+
+    /// If `None` is passed as `format` then `TextureFormat::from_pixels` is used
+    fn create_texture(
+        &mut self,
+        size: Vector2<usize>,
+        pixels: Pixels,
+        format: Option<TextureFormat>,
+        sampler: SamplerDescription,
+    ) -> Result<TextureID, RendererError>;
+    /// Makes a texture of a renderer that shares resources with this one usable here under the
+    /// same id, it is deleted once every renderer that has it destroys it
+    fn share_texture(&mut self, id: TextureID) -> Result<(), RendererError>;
     fn destroy_texture(&mut self, id: TextureID);
     fn get_texture(&self, id: TextureID) -> Option<&dyn Texture>;
     fn get_texture_mut(&mut self, id: TextureID) -> Option<&mut dyn Texture>;
 
-    fn resize(&mut self, size: Vector2<usize>);
+    /// The material starts with every texture slot empty and no uniform values, it keeps the
+    /// uniforms the shader declares now even if the shader gets reloaded
+    fn create_material(&mut self, shader: ShaderID) -> Result<MaterialID, RendererError>;
+    fn destroy_material(&mut self, id: MaterialID);
+    fn get_material(&self, id: MaterialID) -> Option<&dyn Material>;
+    fn get_material_mut(&mut self, id: MaterialID) -> Option<&mut dyn Material>;
+
+    /// Every color attachment must be `size` big, and stay that size while it is attached
+    fn create_framebuffer(
+        &mut self,
+        size: Vector2<usize>,
+        color_attachments: &[TextureID],
+        depth_attachment: DepthAttachment,
+    ) -> Result<FramebufferID, RendererError>;
+    fn destroy_framebuffer(&mut self, id: FramebufferID);
+    fn get_framebuffer(&self, id: FramebufferID) -> Option<&dyn Framebuffer>;
+    fn get_framebuffer_mut(&mut self, id: FramebufferID) -> Option<&mut dyn Framebuffer>;
+
+    /// Fails if the surface is headless and the api can not change the size of its offscreen
+    /// framebuffer
+    fn resize(&mut self, size: Vector2<usize>) -> Result<(), RendererError>;
     fn present(&mut self);
+    /// Reads back what has been drawn to the surface since the last `present`
+    fn read_pixels(&self) -> Image;
 
-    fn clear(&mut self, color: Vector3<f32>);
+    /// The depth convention the projection of the camera is built with in the drawing contexts
+    /// made after this, it starts as `DepthConvention::default()`. Fails if the api can not do
+    /// the depth range
+    fn set_depth_convention(
+        &mut self,
+        depth_convention: DepthConvention,
+    ) -> Result<(), RendererError>;
+    fn get_depth_convention(&self) -> DepthConvention;
+
+    /// If `None` is passed as `framebuffer` then the surface is cleared, buffers the target does not
+    /// have are skipped
+    fn clear(
+        &mut self,
+        framebuffer: Option<FramebufferID>,
+        description: ClearDescription,
+    ) -> Result<(), RendererError>;
+    /// If `None` is passed as `framebuffer` then everything is drawn to the surface, the draws use
+    /// `pipeline_state` until `RendererDrawContext::set_pipeline_state` changes it.
+    /// Clip space is mapped to `viewport`, or to all of the target if it is `None`, and nothing
+    /// outside of `scissor` is drawn. Both are reset once the drawing context is dropped
     fn drawing_context<'a>(
         &'a mut self,
+        framebuffer: Option<FramebufferID>,
+        viewport: Option<Rect>,
+        scissor: Option<Rect>,
         camera: Camera<f32>,
-        depth_testing: bool,
+        pipeline_state: PipelineState,
         cull_face: CullFace,
-    ) -> Box<dyn RendererDrawContext + 'a>;
+    ) -> Result<Box<dyn RendererDrawContext + 'a>, RendererError>;
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PrimitiveType {
+    /// Every vertex is a square of `RendererDrawContext::set_point_size` pixels
+    Points,
+    /// Lines are `RendererDrawContext::set_line_width` pixels wide
+    Lines,
+    LineStrip,
+    /// A line strip that goes back to the first vertex at the end
+    LineLoop,
     Triangle,
     TriangleStrip,
+    /// Every triangle has the first vertex and two vertices after it in common
+    TriangleFan,
+}
+
+/// Checks that `count` vertices make up whole primitives of `typ`
+pub(crate) fn check_vertex_count(typ: PrimitiveType, count: usize) -> Result<(), RendererError> {
+    let valid = match typ {
+        PrimitiveType::Points => true,
+        PrimitiveType::Lines => count.is_multiple_of(2),
+        PrimitiveType::LineStrip | PrimitiveType::LineLoop => count != 1,
+        PrimitiveType::Triangle => count.is_multiple_of(3),
+        PrimitiveType::TriangleStrip | PrimitiveType::TriangleFan => count == 0 || count >= 3,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(RendererError::InvalidVertexCount { typ, count })
+    }
 }
 
 pub trait RendererDrawContext {
+    /// The same as `Shader::set_uniform`, so uniforms can be changed between draws
+    fn set_uniform(
+        &mut self,
+        shader: ShaderID,
+        name: &str,
+        value: UniformValue,
+    ) -> Result<(), RendererError>;
+
+    /// Sets the textures and uniform values of the material on its shader, they stay set like the
+    /// ones from `set_uniform`. The shader to draw with is returned
+    fn bind_material(&mut self, material: MaterialID) -> Result<ShaderID, RendererError>;
+
+    /// The blending, depth, stencil and color mask for the draws after this one, so it can change
+    /// from draw to draw
+    fn set_pipeline_state(&mut self, pipeline_state: PipelineState);
+    fn get_pipeline_state(&self) -> PipelineState;
+
+    /// The size in pixels of `PrimitiveType::Points` for the rest of the drawing context, it
+    /// starts at `1` and anything below that is drawn as `1`
+    fn set_point_size(&mut self, size: f32);
+
+    /// The width in pixels of lines for the rest of the drawing context, it starts at `1` and
+    /// anything below that is drawn as `1`
+    fn set_line_width(&mut self, width: f32);
+
     /// If `None` is passed as `texture` then a default texture of a single white pixel is used
     fn draw(
         &mut self,
@@ -86,9 +280,10 @@ pub trait RendererDrawContext {
         texture: Option<TextureID>,
         model_matrix: Matrix4x4<f32>,
         color: Vector3<f32>,
-    );
+    ) -> Result<(), RendererError>;
 
     /// If `None` is passed as `texture` then a default texture of a single white pixel is used
+    #[allow(clippy::too_many_arguments)]
     fn draw_indexed(
         &mut self,
         typ: PrimitiveType,
@@ -98,5 +293,36 @@ pub trait RendererDrawContext {
         texture: Option<TextureID>,
         model_matrix: Matrix4x4<f32>,
         color: Vector3<f32>,
-    );
+    ) -> Result<(), RendererError>;
+
+    /// Draws the vertices `instance_count` times, every instance reads the next vertex of
+    /// `instance_buffer`. Its layout has to use other locations than the vertex buffer, like with
+    /// `VertexBufferElement::Location`
+    #[allow(clippy::too_many_arguments)]
+    fn draw_instanced(
+        &mut self,
+        typ: PrimitiveType,
+        shader: ShaderID,
+        vertex_buffer: VertexBufferID,
+        instance_buffer: VertexBufferID,
+        instance_count: usize,
+        texture: Option<TextureID>,
+        model_matrix: Matrix4x4<f32>,
+        color: Vector3<f32>,
+    ) -> Result<(), RendererError>;
+
+    /// The indexed version of `draw_instanced`
+    #[allow(clippy::too_many_arguments)]
+    fn draw_indexed_instanced(
+        &mut self,
+        typ: PrimitiveType,
+        shader: ShaderID,
+        vertex_buffer: VertexBufferID,
+        index_buffer: IndexBufferID,
+        instance_buffer: VertexBufferID,
+        instance_count: usize,
+        texture: Option<TextureID>,
+        model_matrix: Matrix4x4<f32>,
+        color: Vector3<f32>,
+    ) -> Result<(), RendererError>;
 }
@@ -1,12 +1,574 @@
-use crate::{PhantomUnsend, PhantomUnsync};
+use crate::{
+    math::{Matrix3x3, Matrix4x4, Vector2, Vector3, Vector4},
+    renderer::{
+        vertex_buffer::{get_vertex_attributes, VertexComponentType},
+        GlslPreprocessor, RendererError, SoftwareShaderUniforms, TextureID, VertexBufferElement,
+    },
+    PhantomUnsend, PhantomUnsync,
+};
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct ShaderID(
     pub(crate) usize,
     pub(crate) PhantomUnsend,
     pub(crate) PhantomUnsync,
 );
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ShaderDataType {
+    Float,
+    Float2,
+    Float3,
+    Float4,
+    Int,
+    Int2,
+    Int3,
+    Int4,
+    UInt,
+    UInt2,
+    UInt3,
+    UInt4,
+    Matrix3x3,
+    Matrix4x4,
+    Texture,
+}
+
+/// A value for `Shader::set_uniform`, the array variants set a uniform array declared with the
+/// type of its elements starting at the first element
+#[derive(Clone, Copy)]
+pub enum UniformValue<'a> {
+    Float(f32),
+    Float2(Vector2<f32>),
+    Float3(Vector3<f32>),
+    Float4(Vector4<f32>),
+    Int(i32),
+    Int2(Vector2<i32>),
+    Int3(Vector3<i32>),
+    Int4(Vector4<i32>),
+    UInt(u32),
+    UInt2(Vector2<u32>),
+    UInt3(Vector3<u32>),
+    UInt4(Vector4<u32>),
+    Matrix3x3(Matrix3x3<f32>),
+    Matrix4x4(Matrix4x4<f32>),
+    FloatArray(&'a [f32]),
+    Float2Array(&'a [Vector2<f32>]),
+    Float3Array(&'a [Vector3<f32>]),
+    Float4Array(&'a [Vector4<f32>]),
+    IntArray(&'a [i32]),
+    Int2Array(&'a [Vector2<i32>]),
+    Int3Array(&'a [Vector3<i32>]),
+    Int4Array(&'a [Vector4<i32>]),
+    UIntArray(&'a [u32]),
+    UInt2Array(&'a [Vector2<u32>]),
+    UInt3Array(&'a [Vector3<u32>]),
+    UInt4Array(&'a [Vector4<u32>]),
+    Matrix3x3Array(&'a [Matrix3x3<f32>]),
+    Matrix4x4Array(&'a [Matrix4x4<f32>]),
+    /// The texture is bound to a free texture unit for every draw, if it gets destroyed a default
+    /// texture of a single white pixel is used
+    Texture(TextureID),
+}
+
+impl<'a> UniformValue<'a> {
+    pub fn get_type(&self) -> ShaderDataType {
+        match self {
+            UniformValue::Float(_) | UniformValue::FloatArray(_) => ShaderDataType::Float,
+            UniformValue::Float2(_) | UniformValue::Float2Array(_) => ShaderDataType::Float2,
+            UniformValue::Float3(_) | UniformValue::Float3Array(_) => ShaderDataType::Float3,
+            UniformValue::Float4(_) | UniformValue::Float4Array(_) => ShaderDataType::Float4,
+            UniformValue::Int(_) | UniformValue::IntArray(_) => ShaderDataType::Int,
+            UniformValue::Int2(_) | UniformValue::Int2Array(_) => ShaderDataType::Int2,
+            UniformValue::Int3(_) | UniformValue::Int3Array(_) => ShaderDataType::Int3,
+            UniformValue::Int4(_) | UniformValue::Int4Array(_) => ShaderDataType::Int4,
+            UniformValue::UInt(_) | UniformValue::UIntArray(_) => ShaderDataType::UInt,
+            UniformValue::UInt2(_) | UniformValue::UInt2Array(_) => ShaderDataType::UInt2,
+            UniformValue::UInt3(_) | UniformValue::UInt3Array(_) => ShaderDataType::UInt3,
+            UniformValue::UInt4(_) | UniformValue::UInt4Array(_) => ShaderDataType::UInt4,
+            UniformValue::Matrix3x3(_) | UniformValue::Matrix3x3Array(_) => {
+                ShaderDataType::Matrix3x3
+            }
+            UniformValue::Matrix4x4(_) | UniformValue::Matrix4x4Array(_) => {
+                ShaderDataType::Matrix4x4
+            }
+            UniformValue::Texture(_) => ShaderDataType::Texture,
+        }
+    }
+
+    /// The number of elements of an array value, `None` for anything else
+    pub fn get_array_length(&self) -> Option<usize> {
+        match self {
+            UniformValue::FloatArray(values) => Some(values.len()),
+            UniformValue::Float2Array(values) => Some(values.len()),
+            UniformValue::Float3Array(values) => Some(values.len()),
+            UniformValue::Float4Array(values) => Some(values.len()),
+            UniformValue::IntArray(values) => Some(values.len()),
+            UniformValue::Int2Array(values) => Some(values.len()),
+            UniformValue::Int3Array(values) => Some(values.len()),
+            UniformValue::Int4Array(values) => Some(values.len()),
+            UniformValue::UIntArray(values) => Some(values.len()),
+            UniformValue::UInt2Array(values) => Some(values.len()),
+            UniformValue::UInt3Array(values) => Some(values.len()),
+            UniformValue::UInt4Array(values) => Some(values.len()),
+            UniformValue::Matrix3x3Array(values) => Some(values.len()),
+            UniformValue::Matrix4x4Array(values) => Some(values.len()),
+            _ => None,
+        }
+    }
+}
+
+/// An owned copy of a `UniformValue`, so a shader can keep it between draws
+#[derive(Clone)]
+pub(crate) enum StoredUniformValue {
+    Float(f32),
+    Float2(Vector2<f32>),
+    Float3(Vector3<f32>),
+    Float4(Vector4<f32>),
+    Int(i32),
+    Int2(Vector2<i32>),
+    Int3(Vector3<i32>),
+    Int4(Vector4<i32>),
+    UInt(u32),
+    UInt2(Vector2<u32>),
+    UInt3(Vector3<u32>),
+    UInt4(Vector4<u32>),
+    Matrix3x3(Matrix3x3<f32>),
+    Matrix4x4(Matrix4x4<f32>),
+    FloatArray(Vec<f32>),
+    Float2Array(Vec<Vector2<f32>>),
+    Float3Array(Vec<Vector3<f32>>),
+    Float4Array(Vec<Vector4<f32>>),
+    IntArray(Vec<i32>),
+    Int2Array(Vec<Vector2<i32>>),
+    Int3Array(Vec<Vector3<i32>>),
+    Int4Array(Vec<Vector4<i32>>),
+    UIntArray(Vec<u32>),
+    UInt2Array(Vec<Vector2<u32>>),
+    UInt3Array(Vec<Vector3<u32>>),
+    UInt4Array(Vec<Vector4<u32>>),
+    Matrix3x3Array(Vec<Matrix3x3<f32>>),
+    Matrix4x4Array(Vec<Matrix4x4<f32>>),
+    Texture(TextureID),
+}
+
+impl StoredUniformValue {
+    pub(crate) fn as_value(&self) -> UniformValue<'_> {
+        match self {
+            StoredUniformValue::Float(value) => UniformValue::Float(*value),
+            StoredUniformValue::Float2(value) => UniformValue::Float2(*value),
+            StoredUniformValue::Float3(value) => UniformValue::Float3(*value),
+            StoredUniformValue::Float4(value) => UniformValue::Float4(*value),
+            StoredUniformValue::Int(value) => UniformValue::Int(*value),
+            StoredUniformValue::Int2(value) => UniformValue::Int2(*value),
+            StoredUniformValue::Int3(value) => UniformValue::Int3(*value),
+            StoredUniformValue::Int4(value) => UniformValue::Int4(*value),
+            StoredUniformValue::UInt(value) => UniformValue::UInt(*value),
+            StoredUniformValue::UInt2(value) => UniformValue::UInt2(*value),
+            StoredUniformValue::UInt3(value) => UniformValue::UInt3(*value),
+            StoredUniformValue::UInt4(value) => UniformValue::UInt4(*value),
+            StoredUniformValue::Matrix3x3(value) => UniformValue::Matrix3x3(*value),
+            StoredUniformValue::Matrix4x4(value) => UniformValue::Matrix4x4(*value),
+            StoredUniformValue::FloatArray(values) => UniformValue::FloatArray(values),
+            StoredUniformValue::Float2Array(values) => UniformValue::Float2Array(values),
+            StoredUniformValue::Float3Array(values) => UniformValue::Float3Array(values),
+            StoredUniformValue::Float4Array(values) => UniformValue::Float4Array(values),
+            StoredUniformValue::IntArray(values) => UniformValue::IntArray(values),
+            StoredUniformValue::Int2Array(values) => UniformValue::Int2Array(values),
+            StoredUniformValue::Int3Array(values) => UniformValue::Int3Array(values),
+            StoredUniformValue::Int4Array(values) => UniformValue::Int4Array(values),
+            StoredUniformValue::UIntArray(values) => UniformValue::UIntArray(values),
+            StoredUniformValue::UInt2Array(values) => UniformValue::UInt2Array(values),
+            StoredUniformValue::UInt3Array(values) => UniformValue::UInt3Array(values),
+            StoredUniformValue::UInt4Array(values) => UniformValue::UInt4Array(values),
+            StoredUniformValue::Matrix3x3Array(values) => UniformValue::Matrix3x3Array(values),
+            StoredUniformValue::Matrix4x4Array(values) => UniformValue::Matrix4x4Array(values),
+            StoredUniformValue::Texture(id) => UniformValue::Texture(*id),
+        }
+    }
+}
+
+impl<'a> From<UniformValue<'a>> for StoredUniformValue {
+    fn from(value: UniformValue<'a>) -> Self {
+        match value {
+            UniformValue::Float(value) => StoredUniformValue::Float(value),
+            UniformValue::Float2(value) => StoredUniformValue::Float2(value),
+            UniformValue::Float3(value) => StoredUniformValue::Float3(value),
+            UniformValue::Float4(value) => StoredUniformValue::Float4(value),
+            UniformValue::Int(value) => StoredUniformValue::Int(value),
+            UniformValue::Int2(value) => StoredUniformValue::Int2(value),
+            UniformValue::Int3(value) => StoredUniformValue::Int3(value),
+            UniformValue::Int4(value) => StoredUniformValue::Int4(value),
+            UniformValue::UInt(value) => StoredUniformValue::UInt(value),
+            UniformValue::UInt2(value) => StoredUniformValue::UInt2(value),
+            UniformValue::UInt3(value) => StoredUniformValue::UInt3(value),
+            UniformValue::UInt4(value) => StoredUniformValue::UInt4(value),
+            UniformValue::Matrix3x3(value) => StoredUniformValue::Matrix3x3(value),
+            UniformValue::Matrix4x4(value) => StoredUniformValue::Matrix4x4(value),
+            UniformValue::FloatArray(values) => StoredUniformValue::FloatArray(values.to_vec()),
+            UniformValue::Float2Array(values) => StoredUniformValue::Float2Array(values.to_vec()),
+            UniformValue::Float3Array(values) => StoredUniformValue::Float3Array(values.to_vec()),
+            UniformValue::Float4Array(values) => StoredUniformValue::Float4Array(values.to_vec()),
+            UniformValue::IntArray(values) => StoredUniformValue::IntArray(values.to_vec()),
+            UniformValue::Int2Array(values) => StoredUniformValue::Int2Array(values.to_vec()),
+            UniformValue::Int3Array(values) => StoredUniformValue::Int3Array(values.to_vec()),
+            UniformValue::Int4Array(values) => StoredUniformValue::Int4Array(values.to_vec()),
+            UniformValue::UIntArray(values) => StoredUniformValue::UIntArray(values.to_vec()),
+            UniformValue::UInt2Array(values) => StoredUniformValue::UInt2Array(values.to_vec()),
+            UniformValue::UInt3Array(values) => StoredUniformValue::UInt3Array(values.to_vec()),
+            UniformValue::UInt4Array(values) => StoredUniformValue::UInt4Array(values.to_vec()),
+            UniformValue::Matrix3x3Array(values) => {
+                StoredUniformValue::Matrix3x3Array(values.to_vec())
+            }
+            UniformValue::Matrix4x4Array(values) => {
+                StoredUniformValue::Matrix4x4Array(values.to_vec())
+            }
+            UniformValue::Texture(id) => StoredUniformValue::Texture(id),
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct ShaderInput<'a> {
+    pub name: &'a str,
+    pub typ: ShaderDataType,
+}
+
+#[derive(Clone, Copy)]
+pub struct ShaderUniform<'a> {
+    pub name: &'a str,
+    /// Arrays are declared with the type of their elements
+    pub typ: ShaderDataType,
+    /// `1` for anything that is not an array, an array value for `Shader::set_uniform` can not
+    /// have more elements than this
+    pub array_size: usize,
+}
+
+/// The uniforms that `RendererDrawContext::draw` and `RendererDrawContext::draw_indexed` fill in
+/// for every shader that declares them. `u_ProjectionMatrix` and `u_ViewMatrix` are also in the
+/// builtin `Camera` uniform block, which is uploaded once per drawing context instead of per shader
+pub const BUILTIN_UNIFORMS: &[ShaderUniform<'static>] = &[
+    ShaderUniform {
+        name: "u_ProjectionMatrix",
+        typ: ShaderDataType::Matrix4x4,
+        array_size: 1,
+    },
+    ShaderUniform {
+        name: "u_ViewMatrix",
+        typ: ShaderDataType::Matrix4x4,
+        array_size: 1,
+    },
+    ShaderUniform {
+        name: "u_ModelMatrix",
+        typ: ShaderDataType::Matrix4x4,
+        array_size: 1,
+    },
+    ShaderUniform {
+        name: "u_Color",
+        typ: ShaderDataType::Float3,
+        array_size: 1,
+    },
+    ShaderUniform {
+        name: "u_Texture",
+        typ: ShaderDataType::Texture,
+        array_size: 1,
+    },
+];
+
+/// A `layout(std140)` uniform block that reads from the uniform buffer bound at `binding` with
+/// `Renderer::set_uniform_buffer_binding`
+#[derive(Clone, Copy)]
+pub struct ShaderUniformBlock<'a> {
+    pub name: &'a str,
+    pub binding: usize,
+}
+
+/// The uniform blocks that every drawing context fills in, `Camera` has the members
+/// `mat4 u_ProjectionMatrix` and `mat4 u_ViewMatrix`
+pub const BUILTIN_UNIFORM_BLOCKS: &[ShaderUniformBlock<'static>] = &[ShaderUniformBlock {
+    name: "Camera",
+    binding: 0,
+}];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment,
+}
+
+#[derive(Clone, Copy)]
+pub struct GlslShaderSource<'a> {
+    pub vertex: &'a str,
+    pub fragment: &'a str,
+    pub preprocessor: GlslPreprocessor<'a>,
+}
+
+/// Gets the vertex inputs in the order they are declared. Integer elements are converted to floats,
+/// so they are only exact up to `2^24`. Anything pushed to `varyings` is interpolated for the
+/// fragment shader
+pub type SoftwareVertexShader = fn(
+    inputs: &[Vector4<f32>],
+    uniforms: &SoftwareShaderUniforms,
+    varyings: &mut Vec<f32>,
+) -> Vector4<f32>;
+/// Output `i` is drawn to color attachment `i` of the framebuffer, when drawing to the surface
+/// there is only output `0`. Every output starts as `(0, 0, 0, 0)`
+pub type SoftwareFragmentShader =
+    fn(varyings: &[f32], uniforms: &SoftwareShaderUniforms, outputs: &mut [Vector4<f32>]);
+
+#[derive(Clone, Copy)]
+pub struct SoftwareShaderSource {
+    pub vertex: SoftwareVertexShader,
+    pub fragment: SoftwareFragmentShader,
+}
+
+/// Describes a shader for every `RendererAPI` at once, each api uses the source written for it
+/// and checks it against `inputs` and `uniforms` when the shader is created
+#[derive(Clone, Copy)]
+pub struct ShaderDescription<'a> {
+    /// Input `i` is read from the element at location `i` of the vertex buffer layout
+    pub inputs: &'a [ShaderInput<'a>],
+    pub uniforms: &'a [ShaderUniform<'a>],
+    pub uniform_blocks: &'a [ShaderUniformBlock<'a>],
+    pub glsl: Option<GlslShaderSource<'a>>,
+    pub software: Option<SoftwareShaderSource>,
+}
+
+impl<'a> ShaderDescription<'a> {
+    /// Checks the parts of the interface that do not depend on the api
+    pub(crate) fn validate(&self) -> Result<(), RendererError> {
+        for (i, input) in self.inputs.iter().enumerate() {
+            if self.inputs[..i]
+                .iter()
+                .any(|other| other.name == input.name)
+            {
+                return Err(RendererError::InvalidShaderDescription(format!(
+                    "Input `{}` is declared more than once",
+                    input.name
+                )));
+            }
+            if matches!(
+                input.typ,
+                ShaderDataType::Matrix3x3 | ShaderDataType::Matrix4x4 | ShaderDataType::Texture
+            ) {
+                return Err(RendererError::InvalidShaderDescription(format!(
+                    "Input `{}` can not be a {:?}",
+                    input.name, input.typ
+                )));
+            }
+        }
+        for (i, uniform) in self.uniforms.iter().enumerate() {
+            if self.uniforms[..i]
+                .iter()
+                .any(|other| other.name == uniform.name)
+            {
+                return Err(RendererError::InvalidShaderDescription(format!(
+                    "Uniform `{}` is declared more than once",
+                    uniform.name
+                )));
+            }
+            if let Some(builtin) = BUILTIN_UNIFORMS
+                .iter()
+                .find(|builtin| builtin.name == uniform.name)
+            {
+                if builtin.typ != uniform.typ {
+                    return Err(RendererError::InvalidShaderDescription(format!(
+                        "Uniform `{}` is declared as a {:?} but it is always a {:?}",
+                        uniform.name, uniform.typ, builtin.typ
+                    )));
+                }
+                if uniform.array_size != 1 {
+                    return Err(RendererError::InvalidShaderDescription(format!(
+                        "Uniform `{}` is declared as an array but it never is one",
+                        uniform.name
+                    )));
+                }
+            }
+            if uniform.array_size == 0 {
+                return Err(RendererError::InvalidShaderDescription(format!(
+                    "Uniform `{}` is declared as an array without elements",
+                    uniform.name
+                )));
+            }
+            // There is no array value for textures
+            if uniform.typ == ShaderDataType::Texture && uniform.array_size != 1 {
+                return Err(RendererError::InvalidShaderDescription(format!(
+                    "Uniform `{}` can not be an array of textures",
+                    uniform.name
+                )));
+            }
+        }
+        for (i, block) in self.uniform_blocks.iter().enumerate() {
+            if self.uniform_blocks[..i]
+                .iter()
+                .any(|other| other.name == block.name)
+            {
+                return Err(RendererError::InvalidShaderDescription(format!(
+                    "Uniform block `{}` is declared more than once",
+                    block.name
+                )));
+            }
+            let builtin = BUILTIN_UNIFORM_BLOCKS
+                .iter()
+                .find(|builtin| builtin.name == block.name);
+            match builtin {
+                Some(builtin) if builtin.binding != block.binding => {
+                    return Err(RendererError::InvalidShaderDescription(format!(
+                        "Uniform block `{}` is declared at binding {} but it is always at binding {}",
+                        block.name, block.binding, builtin.binding
+                    )));
+                }
+                None if BUILTIN_UNIFORM_BLOCKS
+                    .iter()
+                    .any(|builtin| builtin.binding == block.binding) =>
+                {
+                    return Err(RendererError::InvalidShaderDescription(format!(
+                        "Uniform block `{}` is declared at binding {} which is used by a builtin uniform block",
+                        block.name, block.binding
+                    )));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// An input or uniform of a shader, as reported by `Shader::get_inputs` and `Shader::get_uniforms`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShaderVariable {
+    /// Arrays are named without the `[0]` that opengl adds
+    pub name: String,
+    pub typ: ShaderDataType,
+    /// `1` for anything that is not an array
+    pub array_size: usize,
+    /// For inputs this is the location of the vertex buffer element they read from
+    pub location: usize,
+}
+
+/// Checks that every input reads an element of the vertex buffer or the instance buffer with the
+/// same number of components, except for 4 component inputs which can read fewer components
+/// because the rest is `(0, 0, 1)`. Int and uint inputs need elements of the same integer type,
+/// float inputs read everything else
+pub(crate) fn check_vertex_layout(
+    inputs: &[ShaderVariable],
+    layout: &[VertexBufferElement],
+    instance_layout: Option<&[VertexBufferElement]>,
+) -> Result<(), RendererError> {
+    let (mut attributes, _) = get_vertex_attributes(layout)?;
+    if let Some(instance_layout) = instance_layout {
+        attributes.extend(get_vertex_attributes(instance_layout)?.0);
+    }
+    for input in inputs {
+        let Some(attribute) = attributes
+            .iter()
+            .find(|attribute| attribute.location == input.location)
+        else {
+            return Err(RendererError::VertexLayoutMismatch {
+                input: input.name.clone(),
+                input_type: input.typ,
+                location: input.location,
+                element_type: None,
+            });
+        };
+        let input_components = match input.typ {
+            ShaderDataType::Float => Some((None, 1)),
+            ShaderDataType::Float2 => Some((None, 2)),
+            ShaderDataType::Float3 => Some((None, 3)),
+            ShaderDataType::Float4 => Some((None, 4)),
+            ShaderDataType::Int => Some((Some(VertexComponentType::Int), 1)),
+            ShaderDataType::Int2 => Some((Some(VertexComponentType::Int), 2)),
+            ShaderDataType::Int3 => Some((Some(VertexComponentType::Int), 3)),
+            ShaderDataType::Int4 => Some((Some(VertexComponentType::Int), 4)),
+            ShaderDataType::UInt => Some((Some(VertexComponentType::UInt), 1)),
+            ShaderDataType::UInt2 => Some((Some(VertexComponentType::UInt), 2)),
+            ShaderDataType::UInt3 => Some((Some(VertexComponentType::UInt), 3)),
+            ShaderDataType::UInt4 => Some((Some(VertexComponentType::UInt), 4)),
+            ShaderDataType::Matrix3x3 | ShaderDataType::Matrix4x4 | ShaderDataType::Texture => None,
+        };
+        let matches = match (input_components, attribute.element.get_components()) {
+            (Some((integer_type, input_count)), Some((typ, count))) => {
+                integer_type == Some(typ).filter(VertexComponentType::is_integer)
+                    && (input_count == count || input_count == 4)
+            }
+            _ => false,
+        };
+        if !matches {
+            return Err(RendererError::VertexLayoutMismatch {
+                input: input.name.clone(),
+                input_type: input.typ,
+                location: input.location,
+                element_type: Some(attribute.element),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Checks that `name` is declared in `uniforms` with the same type as `value`
+pub(crate) fn check_uniform_value(
+    uniforms: &[(String, ShaderDataType)],
+    name: &str,
+    value: &UniformValue,
+) -> Result<(), RendererError> {
+    let Some((_, typ)) = uniforms.iter().find(|(uniform, _)| uniform == name) else {
+        return Err(RendererError::UndeclaredUniform(name.to_string()));
+    };
+    if *typ != value.get_type() {
+        return Err(RendererError::UniformTypeMismatch {
+            name: name.to_string(),
+            declared: *typ,
+            given: value.get_type(),
+        });
+    }
+    Ok(())
+}
+
+/// The declared uniforms with the array size they were declared with, located at the index of
+/// their declaration
+pub(crate) fn get_declared_variables(uniforms: &[ShaderUniform]) -> Vec<ShaderVariable> {
+    uniforms
+        .iter()
+        .enumerate()
+        .map(|(location, uniform)| ShaderVariable {
+            name: uniform.name.to_string(),
+            typ: uniform.typ,
+            array_size: uniform.array_size,
+            location,
+        })
+        .collect()
+}
+
+/// Checks that an array value fits in the declared uniform, something that is not an array only
+/// has room for one element. Every api checks against the declared array size, even if opengl
+/// reports fewer elements because the glsl does not use the last ones
+pub(crate) fn check_uniform_array_size(
+    uniforms: &[ShaderVariable],
+    name: &str,
+    value: &UniformValue,
+) -> Result<(), RendererError> {
+    let (Some(length), Some(uniform)) = (
+        value.get_array_length(),
+        uniforms.iter().find(|uniform| uniform.name == name),
+    ) else {
+        return Ok(());
+    };
+    if length > uniform.array_size {
+        return Err(RendererError::UniformArraySizeMismatch {
+            name: name.to_string(),
+            length,
+            array_size: uniform.array_size,
+        });
+    }
+    Ok(())
+}
+
 pub trait Shader {
     fn get_id(&self) -> ShaderID;
+    /// The inputs the shader uses, opengl only reports the ones the glsl actually uses
+    fn get_inputs(&self) -> &[ShaderVariable];
+    /// The uniforms the shader uses, opengl only reports the ones the glsl actually uses
+    fn get_uniforms(&self) -> &[ShaderVariable];
+    /// The value stays set for every draw with this shader until it is set again, except for the
+    /// builtin uniforms which every draw overwrites
+    fn set_uniform(&mut self, name: &str, value: UniformValue) -> Result<(), RendererError>;
 }
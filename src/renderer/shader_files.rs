@@ -0,0 +1,182 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::renderer::{
+    glsl_preprocessor::preprocess, GlslDefine, GlslInclude, GlslPreprocessor, GlslShaderSource,
+    RendererError, ShaderDataType, ShaderDescription, ShaderInput, ShaderUniform,
+    ShaderUniformBlock, SoftwareShaderSource,
+};
+
+/// The files that the glsl source of a shader is read from, includes are also looked up next to
+/// them
+#[derive(Clone, Copy)]
+pub struct GlslShaderFiles<'a> {
+    pub vertex: &'a Path,
+    pub fragment: &'a Path,
+    pub preprocessor: GlslPreprocessor<'a>,
+}
+
+/// An owned copy of a `ShaderDescription` whose glsl source is read from files, so the shader can
+/// be compiled again when they change
+pub(crate) struct WatchedShader {
+    inputs: Vec<(String, ShaderDataType)>,
+    uniforms: Vec<(String, ShaderDataType, usize)>,
+    uniform_blocks: Vec<(String, usize)>,
+    software: Option<SoftwareShaderSource>,
+    vertex: PathBuf,
+    fragment: PathBuf,
+    defines: Vec<(String, String)>,
+    includes: Vec<(String, String)>,
+    include_paths: Vec<PathBuf>,
+    /// Every file that was read, including the included ones, with its modification time when it
+    /// was last read
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+}
+
+impl WatchedShader {
+    pub(crate) fn new(description: &ShaderDescription, files: GlslShaderFiles) -> WatchedShader {
+        WatchedShader {
+            inputs: description
+                .inputs
+                .iter()
+                .map(|input| (input.name.to_string(), input.typ))
+                .collect(),
+            uniforms: description
+                .uniforms
+                .iter()
+                .map(|uniform| (uniform.name.to_string(), uniform.typ, uniform.array_size))
+                .collect(),
+            uniform_blocks: description
+                .uniform_blocks
+                .iter()
+                .map(|block| (block.name.to_string(), block.binding))
+                .collect(),
+            software: description.software,
+            vertex: files.vertex.to_path_buf(),
+            fragment: files.fragment.to_path_buf(),
+            defines: files
+                .preprocessor
+                .defines
+                .iter()
+                .map(|define| (define.name.to_string(), define.value.to_string()))
+                .collect(),
+            includes: files
+                .preprocessor
+                .includes
+                .iter()
+                .map(|include| (include.name.to_string(), include.source.to_string()))
+                .collect(),
+            include_paths: [files.vertex, files.fragment]
+                .into_iter()
+                .filter_map(Path::parent)
+                .chain(files.preprocessor.include_paths.iter().copied())
+                .map(Path::to_path_buf)
+                .collect(),
+            files: vec![],
+        }
+    }
+
+    /// Whether any file was modified since it was last read, files that can not be read right now
+    /// count as unchanged because editors sometimes replace a file instead of writing to it
+    pub(crate) fn has_changed(&self) -> bool {
+        self.files
+            .iter()
+            .any(|(path, modified)| match get_modified(path) {
+                Some(now) => Some(now) != *modified,
+                None => false,
+            })
+    }
+
+    /// Reads the files and gives `f` the description they make up, the files are not read again
+    /// until they change even if `f` fails
+    pub(crate) fn with_description<R>(
+        &mut self,
+        f: impl FnOnce(&ShaderDescription) -> Result<R, RendererError>,
+    ) -> Result<R, RendererError> {
+        self.files = [&self.vertex, &self.fragment]
+            .into_iter()
+            .map(|path| (path.clone(), get_modified(path)))
+            .collect();
+        let vertex = read_file(&self.vertex)?;
+        let fragment = read_file(&self.fragment)?;
+
+        let defines: Vec<GlslDefine> = self
+            .defines
+            .iter()
+            .map(|(name, value)| GlslDefine { name, value })
+            .collect();
+        let includes: Vec<GlslInclude> = self
+            .includes
+            .iter()
+            .map(|(name, source)| GlslInclude { name, source })
+            .collect();
+        let include_paths: Vec<&Path> = self.include_paths.iter().map(PathBuf::as_path).collect();
+        let preprocessor = GlslPreprocessor {
+            defines: &defines,
+            includes: &includes,
+            include_paths: &include_paths,
+        };
+        // Only to find the included files, the errors are reported when the shader is compiled
+        for source in [&vertex, &fragment] {
+            let Ok(preprocessed) = preprocess("", source, &preprocessor) else {
+                continue;
+            };
+            for path in preprocessed.included_files {
+                if self.files.iter().all(|(file, _)| *file != path) {
+                    let modified = get_modified(&path);
+                    self.files.push((path, modified));
+                }
+            }
+        }
+
+        let inputs: Vec<ShaderInput> = self
+            .inputs
+            .iter()
+            .map(|(name, typ)| ShaderInput { name, typ: *typ })
+            .collect();
+        let uniforms: Vec<ShaderUniform> = self
+            .uniforms
+            .iter()
+            .map(|(name, typ, array_size)| ShaderUniform {
+                name,
+                typ: *typ,
+                array_size: *array_size,
+            })
+            .collect();
+        let uniform_blocks: Vec<ShaderUniformBlock> = self
+            .uniform_blocks
+            .iter()
+            .map(|(name, binding)| ShaderUniformBlock {
+                name,
+                binding: *binding,
+            })
+            .collect();
+        f(&ShaderDescription {
+            inputs: &inputs,
+            uniforms: &uniforms,
+            uniform_blocks: &uniform_blocks,
+            glsl: Some(GlslShaderSource {
+                vertex: &vertex,
+                fragment: &fragment,
+                preprocessor,
+            }),
+            software: self.software,
+        })
+    }
+}
+
+fn get_modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
+fn read_file(path: &Path) -> Result<String, RendererError> {
+    fs::read_to_string(path).map_err(|error| RendererError::ShaderFileUnreadable {
+        path: path.to_path_buf(),
+        message: error.to_string(),
+    })
+}
@@ -0,0 +1,19 @@
+mod software_framebuffer;
+mod software_index_buffer;
+mod software_rasterizer;
+mod software_renderer;
+mod software_shader;
+mod software_texture;
+mod software_uniform_buffer;
+mod software_vertex_buffer;
+
+pub(crate) use software_framebuffer::*;
+pub(crate) use software_index_buffer::*;
+pub(crate) use software_rasterizer::*;
+pub(crate) use software_renderer::*;
+pub(crate) use software_shader::*;
+pub(crate) use software_texture::*;
+pub(crate) use software_uniform_buffer::*;
+pub(crate) use software_vertex_buffer::*;
+
+pub use software_shader::SoftwareShaderUniforms;
@@ -0,0 +1,87 @@
+use std::{collections::HashMap, marker::PhantomData, sync::atomic::AtomicUsize};
+
+use crate::{
+    math::Vector2,
+    renderer::{
+        framebuffer::check_attachments, software::SoftwareTexture, DepthAttachment, Framebuffer,
+        FramebufferID, RendererError, TextureID,
+    },
+    PhantomUnsend, PhantomUnsync,
+};
+
+pub(crate) enum SoftwareDepth {
+    None,
+    Buffer { depth: Vec<f32>, stencil: Vec<u8> },
+    Texture(TextureID),
+}
+
+/// The attachments that are textures live in those textures, only a depth and stencil buffer
+/// belongs to the framebuffer
+pub(crate) struct SoftwareFramebuffer {
+    id: FramebufferID,
+    size: Vector2<usize>,
+    color_attachments: Vec<TextureID>,
+    depth: SoftwareDepth,
+    _send: PhantomUnsend,
+    _sync: PhantomUnsync,
+}
+
+impl SoftwareFramebuffer {
+    pub(crate) fn new(
+        size: Vector2<usize>,
+        color_attachments: &[TextureID],
+        depth_attachment: DepthAttachment,
+        textures: &HashMap<TextureID, SoftwareTexture>,
+    ) -> Result<SoftwareFramebuffer, RendererError> {
+        check_attachments(size, color_attachments, &depth_attachment, textures)?;
+        Ok(SoftwareFramebuffer {
+            id: {
+                static ID: AtomicUsize = AtomicUsize::new(1);
+                FramebufferID(
+                    ID.fetch_add(1, std::sync::atomic::Ordering::AcqRel),
+                    PhantomData,
+                    PhantomData,
+                )
+            },
+            size,
+            color_attachments: color_attachments.to_vec(),
+            depth: match depth_attachment {
+                DepthAttachment::None => SoftwareDepth::None,
+                DepthAttachment::Buffer => SoftwareDepth::Buffer {
+                    depth: vec![0.0; size.x * size.y],
+                    stencil: vec![0; size.x * size.y],
+                },
+                DepthAttachment::Texture(id) => SoftwareDepth::Texture(id),
+            },
+            _send: PhantomData,
+            _sync: PhantomData,
+        })
+    }
+
+    /// The color attachments and the depth texture
+    pub(crate) fn get_attached_textures(&self) -> impl Iterator<Item = TextureID> + '_ {
+        let depth_texture = match self.depth {
+            SoftwareDepth::Texture(id) => Some(id),
+            SoftwareDepth::None | SoftwareDepth::Buffer { .. } => None,
+        };
+        self.color_attachments.iter().copied().chain(depth_texture)
+    }
+
+    pub(crate) fn get_depth_mut(&mut self) -> &mut SoftwareDepth {
+        &mut self.depth
+    }
+}
+
+impl Framebuffer for SoftwareFramebuffer {
+    fn get_id(&self) -> FramebufferID {
+        self.id
+    }
+
+    fn get_size(&self) -> Vector2<usize> {
+        self.size
+    }
+
+    fn get_color_attachments(&self) -> &[TextureID] {
+        &self.color_attachments
+    }
+}
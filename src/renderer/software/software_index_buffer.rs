@@ -0,0 +1,50 @@
+use std::{marker::PhantomData, sync::atomic::AtomicUsize};
+
+use crate::{
+    renderer::{IndexBuffer, IndexBufferID},
+    PhantomUnsend, PhantomUnsync,
+};
+
+pub(crate) struct SoftwareIndexBuffer {
+    id: IndexBufferID,
+    indices: Vec<u32>,
+    _send: PhantomUnsend,
+    _sync: PhantomUnsync,
+}
+
+impl SoftwareIndexBuffer {
+    pub(crate) fn new(indices: &[u32]) -> SoftwareIndexBuffer {
+        SoftwareIndexBuffer {
+            id: {
+                static ID: AtomicUsize = AtomicUsize::new(1);
+                IndexBufferID(
+                    ID.fetch_add(1, std::sync::atomic::Ordering::AcqRel),
+                    PhantomData,
+                    PhantomData,
+                )
+            },
+            indices: indices.to_vec(),
+            _send: PhantomData,
+            _sync: PhantomData,
+        }
+    }
+
+    pub(crate) fn get_indices(&self) -> &[u32] {
+        &self.indices
+    }
+}
+
+impl IndexBuffer for SoftwareIndexBuffer {
+    fn get_id(&self) -> IndexBufferID {
+        self.id
+    }
+
+    fn get_count(&self) -> usize {
+        self.indices.len()
+    }
+
+    fn set_indices(&mut self, indices: &[u32]) {
+        self.indices.clear();
+        self.indices.extend_from_slice(indices);
+    }
+}
@@ -0,0 +1,598 @@
+use crate::{
+    math::{Vector2, Vector4, Zero},
+    renderer::{
+        BlendMode, ClearDescription, CullFace, PipelineState, Rect, StencilOperation, StencilState,
+    },
+    scene::DepthRange,
+};
+
+/// A vertex after the vertex stage, `position` is in clip space and `varyings` get interpolated
+/// across the primitive before being handed to the fragment stage
+#[derive(Clone)]
+pub(crate) struct ShadedVertex {
+    pub(crate) position: Vector4<f32>,
+    pub(crate) varyings: Vec<f32>,
+}
+
+impl ShadedVertex {
+    fn lerp(&self, other: &ShadedVertex, t: f32) -> ShadedVertex {
+        ShadedVertex {
+            position: self.position + (other.position - self.position) * Vector4::from(t),
+            varyings: self
+                .varyings
+                .iter()
+                .zip(&other.varyings)
+                .map(|(a, b)| a + (b - a) * t)
+                .collect(),
+        }
+    }
+}
+
+/// Gets the interpolated varyings, and the varyings of the pixels to the right and above if
+/// derivatives were asked for, then writes one color per color buffer
+pub(crate) type FragmentStage<'a> =
+    dyn FnMut(&[f32], Option<(&[f32], &[f32])>, &mut [Vector4<f32>]) + 'a;
+
+struct WindowVertex<'a> {
+    position: Vector2<f32>,
+    depth: f32,
+    inverse_w: f32,
+    varyings: &'a [f32],
+}
+
+/// The color, depth and stencil buffers that primitives get rasterized into, the rows start at the
+/// bottom like they do in opengl
+pub(crate) struct SoftwareRenderTarget {
+    size: Vector2<usize>,
+    colors: Vec<Vec<Vector4<f32>>>,
+    depth: Option<Vec<f32>>,
+    stencil: Option<Vec<u8>>,
+    viewport: Rect,
+    depth_range: DepthRange,
+    /// The pixels `start..end` that can be drawn to, the scissor rectangle inside of the buffers
+    start: Vector2<usize>,
+    end: Vector2<usize>,
+}
+
+impl SoftwareRenderTarget {
+    pub(crate) fn new(
+        size: Vector2<usize>,
+        colors: Vec<Vec<Vector4<f32>>>,
+        depth: Option<Vec<f32>>,
+        stencil: Option<Vec<u8>>,
+    ) -> SoftwareRenderTarget {
+        for color in &colors {
+            assert_eq!(color.len(), size.x * size.y);
+        }
+        if let Some(depth) = &depth {
+            assert_eq!(depth.len(), size.x * size.y);
+        }
+        if let Some(stencil) = &stencil {
+            assert_eq!(stencil.len(), size.x * size.y);
+        }
+        SoftwareRenderTarget {
+            size,
+            colors,
+            depth,
+            stencil,
+            viewport: Rect::new(Vector2::zero(), size),
+            depth_range: DepthRange::NegativeOneToOne,
+            start: Vector2::zero(),
+            end: size,
+        }
+    }
+
+    /// A single color buffer with a depth and stencil buffer, like the ones a surface has
+    pub(crate) fn with_depth_stencil(size: Vector2<usize>) -> SoftwareRenderTarget {
+        SoftwareRenderTarget::new(
+            size,
+            vec![vec![(0.0, 0.0, 0.0, 1.0).into(); size.x * size.y]],
+            Some(vec![0.0; size.x * size.y]),
+            Some(vec![0; size.x * size.y]),
+        )
+    }
+
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn into_parts(self) -> (Vec<Vec<Vector4<f32>>>, Option<Vec<f32>>, Option<Vec<u8>>) {
+        (self.colors, self.depth, self.stencil)
+    }
+
+    pub(crate) fn get_size(&self) -> Vector2<usize> {
+        self.size
+    }
+
+    pub(crate) fn set_depth_range(&mut self, depth_range: DepthRange) {
+        self.depth_range = depth_range;
+    }
+
+    /// Clip space gets mapped to `viewport`, and only pixels inside of `scissor` are drawn
+    pub(crate) fn set_viewport(&mut self, viewport: Rect, scissor: Option<Rect>) {
+        let scissor = scissor.unwrap_or(Rect::new(Vector2::zero(), self.size));
+        self.viewport = viewport;
+        self.end = (
+            (scissor.position.x + scissor.size.x).min(self.size.x),
+            (scissor.position.y + scissor.size.y).min(self.size.y),
+        )
+            .into();
+        self.start = (
+            scissor.position.x.min(self.end.x),
+            scissor.position.y.min(self.end.y),
+        )
+            .into();
+    }
+
+    pub(crate) fn get_color(&self, attachment: usize) -> &[Vector4<f32>] {
+        &self.colors[attachment]
+    }
+
+    pub(crate) fn clear(&mut self, description: &ClearDescription) {
+        if let Some(color) = description.color {
+            for colors in &mut self.colors {
+                colors.fill(color);
+            }
+        }
+        if let (Some(depth), Some(depths)) = (description.depth, &mut self.depth) {
+            depths.fill(depth);
+        }
+        if let (Some(stencil), Some(stencils)) = (description.stencil, &mut self.stencil) {
+            stencils.fill(stencil);
+        }
+    }
+
+    /// Draws a square of `size` pixels around the vertex, every pixel gets the same varyings
+    pub(crate) fn draw_point(
+        &mut self,
+        vertex: &ShadedVertex,
+        size: f32,
+        pipeline_state: &PipelineState,
+        derivatives: bool,
+        fragment: &mut FragmentStage,
+    ) {
+        if get_clip_planes(self.depth_range)
+            .iter()
+            .chain(&SIDE_PLANES)
+            .any(|plane| plane(&vertex.position) < 0.0)
+        {
+            return;
+        }
+        let vertex = self.to_window(vertex);
+
+        // Covers the pixels with their center inside the square, counting the left and bottom
+        // edges as inside
+        let size = size.round().max(1.0);
+        let start = vertex.position - Vector2::from(size * 0.5 + 0.5);
+        let (start_x, start_y) = (start.x.ceil(), start.y.ceil());
+        let end_x = ((start_x + size).max(0.0) as usize).min(self.end.x);
+        let end_y = ((start_y + size).max(0.0) as usize).min(self.end.y);
+        let start_x = (start_x.max(0.0) as usize).max(self.start.x);
+        let start_y = (start_y.max(0.0) as usize).max(self.start.y);
+
+        let neighbours = derivatives.then_some((vertex.varyings, vertex.varyings));
+        let mut outputs = vec![Vector4::zero(); self.colors.len()];
+        for y in start_y..end_y {
+            for x in start_x..end_x {
+                let index = y * self.size.x + x;
+                if !self.test(index, vertex.depth, pipeline_state) {
+                    continue;
+                }
+                outputs.fill(Vector4::zero());
+                fragment(vertex.varyings, neighbours, &mut outputs);
+                self.blend(index, &outputs, pipeline_state);
+            }
+        }
+    }
+
+    /// Draws a line that is `width` pixels wide across its major axis, the pixel at the end is
+    /// left out so lines in a strip never draw the same pixel twice
+    pub(crate) fn draw_line(
+        &mut self,
+        vertices: [&ShadedVertex; 2],
+        width: f32,
+        pipeline_state: &PipelineState,
+        derivatives: bool,
+        fragment: &mut FragmentStage,
+    ) {
+        let Some([v0, v1]) = clip_line(vertices, &get_clip_planes(self.depth_range)) else {
+            return;
+        };
+        let (v0, v1) = (self.to_window(&v0), self.to_window(&v1));
+
+        // Steps along the major axis one pixel at a time, the minor axis is the one across
+        let delta = v1.position - v0.position;
+        let x_major = delta.x.abs() >= delta.y.abs();
+        let axes = |point: Vector2<f32>| {
+            if x_major {
+                (point.x, point.y)
+            } else {
+                (point.y, point.x)
+            }
+        };
+        let ((major0, minor0), (major1, minor1)) = (axes(v0.position), axes(v1.position));
+        let ((major_start, minor_start), (major_end, minor_end)) = if x_major {
+            ((self.start.x, self.start.y), (self.end.x, self.end.y))
+        } else {
+            ((self.start.y, self.start.x), (self.end.y, self.end.x))
+        };
+        if major0 == major1 {
+            return;
+        }
+
+        let width = width.round().max(1.0);
+        let start = major0.min(major1);
+        let end = major0.max(major1);
+        let start_major = ((start - 0.5).ceil().max(0.0) as usize).max(major_start);
+        let end_major = ((end - 0.5).ceil().max(0.0) as usize).min(major_end);
+
+        let mut varyings = vec![0.0; v0.varyings.len()];
+        let mut varyings_next = vec![0.0; v0.varyings.len()];
+        let mut outputs = vec![Vector4::zero(); self.colors.len()];
+        for major in start_major..end_major {
+            let t = (major as f32 + 0.5 - major0) / (major1 - major0);
+            let depth = v0.depth + (v1.depth - v0.depth) * t;
+            let start_minor = (minor0 + (minor1 - minor0) * t - width * 0.5 - 0.5).ceil();
+            let end_minor = ((start_minor + width).max(0.0) as usize).min(minor_end);
+            let start_minor = (start_minor.max(0.0) as usize).max(minor_start);
+
+            interpolate_line([&v0, &v1], t, &mut varyings);
+            // The varyings only change along the major axis
+            let neighbours = if derivatives {
+                let step = 1.0 / (major1 - major0);
+                interpolate_line([&v0, &v1], t + step, &mut varyings_next);
+                if x_major {
+                    Some((&varyings_next[..], &varyings[..]))
+                } else {
+                    Some((&varyings[..], &varyings_next[..]))
+                }
+            } else {
+                None
+            };
+
+            for minor in start_minor..end_minor {
+                let (x, y) = if x_major {
+                    (major, minor)
+                } else {
+                    (minor, major)
+                };
+                let index = y * self.size.x + x;
+                if !self.test(index, depth, pipeline_state) {
+                    continue;
+                }
+                outputs.fill(Vector4::zero());
+                fragment(&varyings, neighbours, &mut outputs);
+                self.blend(index, &outputs, pipeline_state);
+            }
+        }
+    }
+
+    pub(crate) fn draw_triangle(
+        &mut self,
+        vertices: [&ShadedVertex; 3],
+        pipeline_state: &PipelineState,
+        cull_face: &CullFace,
+        derivatives: bool,
+        fragment: &mut FragmentStage,
+    ) {
+        let polygon = clip_polygon(
+            vertices.into_iter().cloned().collect(),
+            &get_clip_planes(self.depth_range),
+        );
+        if polygon.len() < 3 {
+            return;
+        }
+
+        let window_vertices: Vec<WindowVertex> = polygon
+            .iter()
+            .map(|vertex| self.to_window(vertex))
+            .collect();
+
+        for i in 1..window_vertices.len() - 1 {
+            self.rasterize_triangle(
+                [
+                    &window_vertices[0],
+                    &window_vertices[i],
+                    &window_vertices[i + 1],
+                ],
+                pipeline_state,
+                cull_face,
+                derivatives,
+                fragment,
+            );
+        }
+    }
+
+    fn rasterize_triangle(
+        &mut self,
+        [v0, v1, v2]: [&WindowVertex; 3],
+        pipeline_state: &PipelineState,
+        cull_face: &CullFace,
+        derivatives: bool,
+        fragment: &mut FragmentStage,
+    ) {
+        let area = edge(v0.position, v1.position, v2.position);
+        let culled = match cull_face {
+            CullFace::None => false,
+            CullFace::Clockwise => area > 0.0,
+            CullFace::CounterClockwise => area < 0.0,
+        };
+        if culled || area == 0.0 {
+            return;
+        }
+
+        // Make the winding counter clockwise so every edge function is positive on the inside
+        let (v1, v2, area) = if area < 0.0 {
+            (v2, v1, -area)
+        } else {
+            (v1, v2, area)
+        };
+
+        let min_x = v0.position.x.min(v1.position.x).min(v2.position.x);
+        let max_x = v0.position.x.max(v1.position.x).max(v2.position.x);
+        let min_y = v0.position.y.min(v1.position.y).min(v2.position.y);
+        let max_y = v0.position.y.max(v1.position.y).max(v2.position.y);
+        // Triangles are not clipped by the sides of clip space, so they are kept inside of the
+        // viewport here
+        let viewport_end = self.viewport.position + self.viewport.size;
+        let start_x = (min_x.floor().max(0.0) as usize)
+            .max(self.start.x)
+            .max(self.viewport.position.x);
+        let end_x = (max_x.ceil().max(0.0) as usize)
+            .min(self.end.x)
+            .min(viewport_end.x);
+        let start_y = (min_y.floor().max(0.0) as usize)
+            .max(self.start.y)
+            .max(self.viewport.position.y);
+        let end_y = (max_y.ceil().max(0.0) as usize)
+            .min(self.end.y)
+            .min(viewport_end.y);
+
+        let mut varyings = vec![0.0; v0.varyings.len()];
+        let mut varyings_right = vec![0.0; v0.varyings.len()];
+        let mut varyings_above = vec![0.0; v0.varyings.len()];
+        let mut outputs = vec![Vector4::zero(); self.colors.len()];
+        for y in start_y..end_y {
+            for x in start_x..end_x {
+                let point = Vector2::from((x as f32 + 0.5, y as f32 + 0.5));
+                let w0 = edge(v1.position, v2.position, point);
+                let w1 = edge(v2.position, v0.position, point);
+                let w2 = edge(v0.position, v1.position, point);
+                if !covers(w0, v1.position, v2.position)
+                    || !covers(w1, v2.position, v0.position)
+                    || !covers(w2, v0.position, v1.position)
+                {
+                    continue;
+                }
+                let (l0, l1, l2) = (w0 / area, w1 / area, w2 / area);
+
+                let index = y * self.size.x + x;
+                let depth = l0 * v0.depth + l1 * v1.depth + l2 * v2.depth;
+                if !self.test(index, depth, pipeline_state) {
+                    continue;
+                }
+
+                interpolate([v0, v1, v2], area, point, &mut varyings);
+                let neighbours = if derivatives {
+                    interpolate(
+                        [v0, v1, v2],
+                        area,
+                        point + Vector2::<f32>::from((1.0, 0.0)),
+                        &mut varyings_right,
+                    );
+                    interpolate(
+                        [v0, v1, v2],
+                        area,
+                        point + Vector2::<f32>::from((0.0, 1.0)),
+                        &mut varyings_above,
+                    );
+                    Some((&varyings_right[..], &varyings_above[..]))
+                } else {
+                    None
+                };
+
+                outputs.fill(Vector4::zero());
+                fragment(&varyings, neighbours, &mut outputs);
+                self.blend(index, &outputs, pipeline_state);
+            }
+        }
+    }
+
+    fn to_window<'a>(&self, vertex: &'a ShadedVertex) -> WindowVertex<'a> {
+        let inverse_w = 1.0 / vertex.position.w;
+        WindowVertex {
+            position: (
+                (vertex.position.x * inverse_w * 0.5 + 0.5) * self.viewport.size.x as f32
+                    + self.viewport.position.x as f32,
+                (vertex.position.y * inverse_w * 0.5 + 0.5) * self.viewport.size.y as f32
+                    + self.viewport.position.y as f32,
+            )
+                .into(),
+            depth: match self.depth_range {
+                DepthRange::NegativeOneToOne => vertex.position.z * inverse_w * 0.5 + 0.5,
+                DepthRange::ZeroToOne => vertex.position.z * inverse_w,
+            },
+            inverse_w,
+            varyings: &vertex.varyings,
+        }
+    }
+
+    /// Whether the pixel at `index` passes the stencil and depth tests, the stencil and depth get
+    /// written the way `pipeline_state` says to. Tests without a buffer to test against pass
+    fn test(&mut self, index: usize, depth: f32, pipeline_state: &PipelineState) -> bool {
+        let stencil = match (&pipeline_state.stencil, &mut self.stencil) {
+            (Some(state), Some(stencils)) => Some((state, &mut stencils[index])),
+            _ => None,
+        };
+        if let Some((state, stored)) = stencil {
+            if !state
+                .compare
+                .compare(state.reference & state.read_mask, *stored & state.read_mask)
+            {
+                update_stencil(stored, state, state.fail);
+                return false;
+            }
+        }
+
+        let passed = match (&pipeline_state.depth, &mut self.depth) {
+            (Some(state), Some(depths)) => {
+                let passed = state.compare.compare(depth, depths[index]);
+                if passed && state.write {
+                    depths[index] = depth;
+                }
+                passed
+            }
+            _ => true,
+        };
+
+        if let (Some(state), Some(stencils)) = (&pipeline_state.stencil, &mut self.stencil) {
+            let operation = if passed { state.pass } else { state.depth_fail };
+            update_stencil(&mut stencils[index], state, operation);
+        }
+        passed
+    }
+
+    fn blend(&mut self, index: usize, outputs: &[Vector4<f32>], pipeline_state: &PipelineState) {
+        let mask = pipeline_state.color_mask;
+        for (colors, &output) in self.colors.iter_mut().zip(outputs) {
+            let source = clamp_color(output);
+            let destination = colors[index];
+            let alpha = Vector4::from(source.w);
+            let one_minus_alpha = Vector4::from(1.0) - alpha;
+            let blended = clamp_color(match pipeline_state.blend {
+                BlendMode::None => source,
+                BlendMode::Alpha => source * alpha + destination * one_minus_alpha,
+                BlendMode::Additive => source * alpha + destination,
+                BlendMode::Multiply => source * destination,
+                BlendMode::PremultipliedAlpha => source + destination * one_minus_alpha,
+            });
+            colors[index] = (
+                if mask.red { blended.x } else { destination.x },
+                if mask.green { blended.y } else { destination.y },
+                if mask.blue { blended.z } else { destination.z },
+                if mask.alpha { blended.w } else { destination.w },
+            )
+                .into();
+        }
+    }
+}
+
+/// The signed distance of a clip space position to a plane, it is inside when it is positive
+type ClipPlane = fn(&Vector4<f32>) -> f32;
+
+/// The near and far planes of `depth_range`, and one that makes sure that `w` is positive so the
+/// perspective divide is safe, triangles outside of the sides are skipped during rasterization
+fn get_clip_planes(depth_range: DepthRange) -> [ClipPlane; 3] {
+    [
+        |position| position.w - 1e-5,
+        match depth_range {
+            DepthRange::NegativeOneToOne => |position| position.w + position.z,
+            DepthRange::ZeroToOne => |position| position.z,
+        },
+        |position| position.w - position.z,
+    ]
+}
+
+/// The sides of clip space, points and lines are clipped by them because they can be drawn wider
+/// than the viewport
+const SIDE_PLANES: [ClipPlane; 4] = [
+    |position| position.w + position.x,
+    |position| position.w - position.x,
+    |position| position.w + position.y,
+    |position| position.w - position.y,
+];
+
+fn clip_polygon(mut polygon: Vec<ShadedVertex>, planes: &[ClipPlane]) -> Vec<ShadedVertex> {
+    for plane in planes {
+        if polygon.is_empty() {
+            break;
+        }
+        let mut clipped = Vec::with_capacity(polygon.len() + 1);
+        for i in 0..polygon.len() {
+            let current = &polygon[i];
+            let next = &polygon[(i + 1) % polygon.len()];
+            let current_distance = plane(&current.position);
+            let next_distance = plane(&next.position);
+            if current_distance >= 0.0 {
+                clipped.push(current.clone());
+            }
+            if (current_distance >= 0.0) != (next_distance >= 0.0) {
+                let t = current_distance / (current_distance - next_distance);
+                clipped.push(current.lerp(next, t));
+            }
+        }
+        polygon = clipped;
+    }
+    polygon
+}
+
+fn clip_line([a, b]: [&ShadedVertex; 2], planes: &[ClipPlane]) -> Option<[ShadedVertex; 2]> {
+    let (mut a, mut b) = (a.clone(), b.clone());
+    for plane in planes.iter().chain(&SIDE_PLANES) {
+        let (a_distance, b_distance) = (plane(&a.position), plane(&b.position));
+        let t = a_distance / (a_distance - b_distance);
+        match (a_distance >= 0.0, b_distance >= 0.0) {
+            (true, true) => {}
+            (false, false) => return None,
+            (true, false) => b = a.lerp(&b, t),
+            (false, true) => a = a.lerp(&b, t),
+        }
+    }
+    Some([a, b])
+}
+
+/// Perspective correct interpolation of the varyings at `t` along a line, where `t` goes from `0`
+/// at the first vertex to `1` at the second one in window space
+fn interpolate_line([v0, v1]: [&WindowVertex; 2], t: f32, varyings: &mut [f32]) {
+    let (p0, p1) = ((1.0 - t) * v0.inverse_w, t * v1.inverse_w);
+    let inverse_w = p0 + p1;
+    for (i, varying) in varyings.iter_mut().enumerate() {
+        *varying = (p0 * v0.varyings[i] + p1 * v1.varyings[i]) / inverse_w;
+    }
+}
+
+/// Perspective correct interpolation of the varyings at `point`, which can be outside of the
+/// triangle
+fn interpolate(
+    [v0, v1, v2]: [&WindowVertex; 3],
+    area: f32,
+    point: Vector2<f32>,
+    varyings: &mut [f32],
+) {
+    let l0 = edge(v1.position, v2.position, point) / area;
+    let l1 = edge(v2.position, v0.position, point) / area;
+    let l2 = edge(v0.position, v1.position, point) / area;
+    let (p0, p1, p2) = (l0 * v0.inverse_w, l1 * v1.inverse_w, l2 * v2.inverse_w);
+    let inverse_w = p0 + p1 + p2;
+    for (i, varying) in varyings.iter_mut().enumerate() {
+        *varying = (p0 * v0.varyings[i] + p1 * v1.varyings[i] + p2 * v2.varyings[i]) / inverse_w;
+    }
+}
+
+fn edge(a: Vector2<f32>, b: Vector2<f32>, point: Vector2<f32>) -> f32 {
+    (b.x - a.x) * (point.y - a.y) - (b.y - a.y) * (point.x - a.x)
+}
+
+/// Pixels exactly on an edge are only drawn for top and left edges, so triangles that share an
+/// edge never draw the same pixel twice
+fn covers(weight: f32, a: Vector2<f32>, b: Vector2<f32>) -> bool {
+    if weight != 0.0 {
+        return weight > 0.0;
+    }
+    let top = a.y == b.y && b.x < a.x;
+    let left = b.y < a.y;
+    top || left
+}
+
+/// Only the bits in the write mask change
+fn update_stencil(stored: &mut u8, state: &StencilState, operation: StencilOperation) {
+    let value = operation.apply(*stored, state.reference);
+    *stored = (*stored & !state.write_mask) | (value & state.write_mask);
+}
+
+pub(crate) fn clamp_color(color: Vector4<f32>) -> Vector4<f32> {
+    (
+        color.x.clamp(0.0, 1.0),
+        color.y.clamp(0.0, 1.0),
+        color.z.clamp(0.0, 1.0),
+        color.w.clamp(0.0, 1.0),
+    )
+        .into()
+}
@@ -0,0 +1,1012 @@
+use std::{cell::RefCell, collections::HashMap, marker::PhantomData, pin::Pin};
+
+use crate::{
+    math::{Matrix4x4, Vector2, Vector3, Vector4, Zero},
+    platform::Surface,
+    renderer::{
+        framebuffer::check_attachment_sizes,
+        index_buffer::check_indices,
+        material::StoredMaterial,
+        renderer::check_vertex_count,
+        shader::check_vertex_layout,
+        shader_files::WatchedShader,
+        software::{
+            clamp_color, ShadedVertex, SoftwareDepth, SoftwareFramebuffer, SoftwareIndexBuffer,
+            SoftwareRenderTarget, SoftwareSampling, SoftwareShader, SoftwareTexture,
+            SoftwareUniformBuffer, SoftwareUniformValue, SoftwareVertexBuffer,
+        },
+        uniform_buffer::get_camera_block_data,
+        vertex_buffer::check_instance_buffer,
+        ClearDescription, CullFace, DepthAttachment, Framebuffer, FramebufferID, GlslShaderFiles,
+        Image, IndexBuffer, IndexBufferID, Material, MaterialID, PipelineState, Pixels,
+        PrimitiveType, Rect, Renderer, RendererDrawContext, RendererError, SamplerDescription,
+        Shader, ShaderDescription, ShaderID, SoftwareShaderUniforms, Texture, TextureFormat,
+        TextureID, UniformBuffer, UniformBufferID, UniformValue, VertexBuffer, VertexBufferElement,
+        VertexBufferID, BUILTIN_UNIFORM_BLOCKS,
+    },
+    scene::{Camera, DepthConvention},
+    PhantomUnsend, PhantomUnsync,
+};
+
+pub(crate) struct SoftwareRenderer {
+    surface: Option<Pin<Box<Surface>>>,
+    surface_target: SoftwareRenderTarget,
+    shaders: HashMap<ShaderID, SoftwareShader>,
+    watched_shaders: HashMap<ShaderID, WatchedShader>,
+    vertex_buffers: HashMap<VertexBufferID, SoftwareVertexBuffer>,
+    index_buffers: HashMap<IndexBufferID, SoftwareIndexBuffer>,
+    textures: HashMap<TextureID, SoftwareTexture>,
+    materials: HashMap<MaterialID, StoredMaterial>,
+    framebuffers: HashMap<FramebufferID, SoftwareFramebuffer>,
+    uniform_buffers: HashMap<UniformBufferID, SoftwareUniformBuffer>,
+    uniform_buffer_bindings: HashMap<usize, UniformBufferID>,
+    default_white_pixel: SoftwareTexture,
+    depth_convention: DepthConvention,
+    _send: PhantomUnsend,
+    _sync: PhantomUnsync,
+}
+
+impl SoftwareRenderer {
+    pub(crate) fn new(surface: Pin<Box<Surface>>) -> SoftwareRenderer {
+        SoftwareRenderer {
+            surface_target: SoftwareRenderTarget::with_depth_stencil(surface.get_size()),
+            surface: Some(surface),
+            shaders: HashMap::new(),
+            watched_shaders: HashMap::new(),
+            vertex_buffers: HashMap::new(),
+            index_buffers: HashMap::new(),
+            textures: HashMap::new(),
+            materials: HashMap::new(),
+            framebuffers: HashMap::new(),
+            uniform_buffers: HashMap::new(),
+            uniform_buffer_bindings: HashMap::new(),
+            default_white_pixel: SoftwareTexture::new(
+                (1, 1).into(),
+                Pixels::RGBA(&[(255, 255, 255, 255).into()]),
+                None,
+                SamplerDescription::default(),
+            )
+            .expect("A single white pixel is a valid texture"),
+            depth_convention: DepthConvention::default(),
+            _send: PhantomData,
+            _sync: PhantomData,
+        }
+    }
+
+    /// Moves the buffers that `framebuffer` draws to into a render target, they have to be given
+    /// back with `return_render_target` before the attached textures are used again
+    fn take_render_target(
+        &mut self,
+        framebuffer: Option<FramebufferID>,
+    ) -> Result<SoftwareRenderTarget, RendererError> {
+        let Some(id) = framebuffer else {
+            let size = self.surface_target.get_size();
+            return Ok(std::mem::replace(
+                &mut self.surface_target,
+                SoftwareRenderTarget::new(size, vec![], None, None),
+            ));
+        };
+        let framebuffer = self
+            .framebuffers
+            .get_mut(&id)
+            .ok_or(RendererError::InvalidFramebuffer(id))?;
+        let size = framebuffer.get_size();
+        check_attachment_sizes(size, framebuffer.get_attached_textures(), &self.textures)?;
+        let colors = framebuffer
+            .get_color_attachments()
+            .iter()
+            .map(|id| match self.textures.get_mut(id) {
+                Some(texture) => texture.take_pixels(),
+                // A destroyed attachment gets drawn to a buffer that is thrown away
+                None => vec![Vector4::zero(); size.x * size.y],
+            })
+            .collect();
+        let (depth, stencil) = match framebuffer.get_depth_mut() {
+            SoftwareDepth::None => (None, None),
+            SoftwareDepth::Buffer { depth, stencil } => {
+                (Some(std::mem::take(depth)), Some(std::mem::take(stencil)))
+            }
+            SoftwareDepth::Texture(id) => (
+                Some(match self.textures.get_mut(id) {
+                    Some(texture) => texture
+                        .take_pixels()
+                        .into_iter()
+                        .map(|pixel| pixel.x)
+                        .collect(),
+                    None => vec![0.0; size.x * size.y],
+                }),
+                None,
+            ),
+        };
+        Ok(SoftwareRenderTarget::new(size, colors, depth, stencil))
+    }
+
+    fn return_render_target(
+        &mut self,
+        framebuffer: Option<FramebufferID>,
+        render_target: SoftwareRenderTarget,
+    ) {
+        let Some(id) = framebuffer else {
+            self.surface_target = render_target;
+            return;
+        };
+        let framebuffer = self.framebuffers.get_mut(&id).unwrap();
+        let (colors, depth, stencil) = render_target.into_parts();
+        for (id, color) in framebuffer.get_color_attachments().iter().zip(colors) {
+            if let Some(texture) = self.textures.get_mut(id) {
+                texture.set_stored_pixels(color);
+            }
+        }
+        match (framebuffer.get_depth_mut(), depth, stencil) {
+            (
+                SoftwareDepth::Buffer {
+                    depth: depth_buffer,
+                    stencil: stencil_buffer,
+                },
+                Some(depth),
+                Some(stencil),
+            ) => {
+                *depth_buffer = depth;
+                *stencil_buffer = stencil;
+            }
+            (SoftwareDepth::Texture(id), Some(depth), _) => {
+                if let Some(texture) = self.textures.get_mut(id) {
+                    texture.set_stored_pixels(
+                        depth
+                            .into_iter()
+                            .map(|depth| (depth, 0.0, 0.0, 1.0).into())
+                            .collect(),
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Renderer for SoftwareRenderer {
+    fn get_surface(&self) -> &Surface {
+        self.surface.as_ref().unwrap()
+    }
+
+    fn get_surface_mut(&mut self) -> &mut Surface {
+        self.surface.as_mut().unwrap()
+    }
+
+    fn take_surface(mut self) -> Pin<Box<Surface>> {
+        self.surface.take().unwrap()
+    }
+
+    fn create_shared_renderer(
+        &self,
+        _surface: Pin<Box<Surface>>,
+    ) -> Result<Box<dyn Renderer>, RendererError> {
+        Err(RendererError::ResourceSharingUnsupported)
+    }
+
+    fn create_shader(&mut self, description: ShaderDescription) -> Result<ShaderID, RendererError> {
+        let shader = SoftwareShader::new(&description)?;
+        let id = shader.get_id();
+        assert!(self.shaders.insert(id, shader).is_none());
+        Ok(id)
+    }
+
+    fn create_shader_from_files(
+        &mut self,
+        description: ShaderDescription,
+        files: GlslShaderFiles,
+    ) -> Result<ShaderID, RendererError> {
+        let mut watched_shader = WatchedShader::new(&description, files);
+        let shader = watched_shader.with_description(SoftwareShader::new)?;
+        let id = shader.get_id();
+        assert!(self.shaders.insert(id, shader).is_none());
+        self.watched_shaders.insert(id, watched_shader);
+        Ok(id)
+    }
+
+    fn reload_shaders(&mut self) -> Vec<(ShaderID, Result<(), RendererError>)> {
+        let mut results = vec![];
+        for (&id, watched_shader) in &mut self.watched_shaders {
+            if !watched_shader.has_changed() {
+                continue;
+            }
+            let shader = self.shaders.get_mut(&id).unwrap();
+            results.push((
+                id,
+                watched_shader.with_description(|description| shader.reload(description)),
+            ));
+        }
+        results
+    }
+
+    fn destroy_shader(&mut self, id: ShaderID) {
+        self.shaders.remove(&id);
+        self.watched_shaders.remove(&id);
+    }
+
+    fn get_shader(&self, id: ShaderID) -> Option<&dyn Shader> {
+        self.shaders.get(&id).map(|shader| shader as &dyn Shader)
+    }
+
+    fn get_shader_mut(&mut self, id: ShaderID) -> Option<&mut dyn Shader> {
+        self.shaders
+            .get_mut(&id)
+            .map(|shader| shader as &mut dyn Shader)
+    }
+
+    fn create_vertex_buffer(
+        &mut self,
+        layout: &[VertexBufferElement],
+        data: &[u8],
+    ) -> Result<VertexBufferID, RendererError> {
+        let vertex_buffer = SoftwareVertexBuffer::new(layout, data)?;
+        let id = vertex_buffer.get_id();
+        assert!(self.vertex_buffers.insert(id, vertex_buffer).is_none());
+        Ok(id)
+    }
+
+    fn share_vertex_buffer(&mut self, id: VertexBufferID) -> Result<(), RendererError> {
+        if self.vertex_buffers.contains_key(&id) {
+            Ok(())
+        } else {
+            Err(RendererError::ResourceSharingUnsupported)
+        }
+    }
+
+    fn destroy_vertex_buffer(&mut self, id: VertexBufferID) {
+        self.vertex_buffers.remove(&id);
+    }
+
+    fn get_vertex_buffer(&self, id: VertexBufferID) -> Option<&dyn VertexBuffer> {
+        self.vertex_buffers
+            .get(&id)
+            .map(|vertex_buffer| vertex_buffer as &dyn VertexBuffer)
+    }
+
+    fn get_vertex_buffer_mut(&mut self, id: VertexBufferID) -> Option<&mut dyn VertexBuffer> {
+        self.vertex_buffers
+            .get_mut(&id)
+            .map(|vertex_buffer| vertex_buffer as &mut dyn VertexBuffer)
+    }
+
+    fn create_index_buffer(&mut self, indices: &[u32]) -> IndexBufferID {
+        let index_buffer = SoftwareIndexBuffer::new(indices);
+        let id = index_buffer.get_id();
+        assert!(self.index_buffers.insert(id, index_buffer).is_none());
+        id
+    }
+
+    fn share_index_buffer(&mut self, id: IndexBufferID) -> Result<(), RendererError> {
+        if self.index_buffers.contains_key(&id) {
+            Ok(())
+        } else {
+            Err(RendererError::ResourceSharingUnsupported)
+        }
+    }
+
+    fn destroy_index_buffer(&mut self, id: IndexBufferID) {
+        self.index_buffers.remove(&id);
+    }
+
+    fn get_index_buffer(&self, id: IndexBufferID) -> Option<&dyn IndexBuffer> {
+        self.index_buffers
+            .get(&id)
+            .map(|index_buffer| index_buffer as &dyn IndexBuffer)
+    }
+
+    fn get_index_buffer_mut(&mut self, id: IndexBufferID) -> Option<&mut dyn IndexBuffer> {
+        self.index_buffers
+            .get_mut(&id)
+            .map(|index_buffer| index_buffer as &mut dyn IndexBuffer)
+    }
+
+    fn create_uniform_buffer(&mut self, data: &[u8]) -> UniformBufferID {
+        let uniform_buffer = SoftwareUniformBuffer::new(data);
+        let id = uniform_buffer.get_id();
+        assert!(self.uniform_buffers.insert(id, uniform_buffer).is_none());
+        id
+    }
+
+    fn share_uniform_buffer(&mut self, id: UniformBufferID) -> Result<(), RendererError> {
+        if self.uniform_buffers.contains_key(&id) {
+            Ok(())
+        } else {
+            Err(RendererError::ResourceSharingUnsupported)
+        }
+    }
+
+    fn destroy_uniform_buffer(&mut self, id: UniformBufferID) {
+        self.uniform_buffers.remove(&id);
+    }
+
+    fn get_uniform_buffer(&self, id: UniformBufferID) -> Option<&dyn UniformBuffer> {
+        self.uniform_buffers
+            .get(&id)
+            .map(|uniform_buffer| uniform_buffer as &dyn UniformBuffer)
+    }
+
+    fn get_uniform_buffer_mut(&mut self, id: UniformBufferID) -> Option<&mut dyn UniformBuffer> {
+        self.uniform_buffers
+            .get_mut(&id)
+            .map(|uniform_buffer| uniform_buffer as &mut dyn UniformBuffer)
+    }
+
+    fn set_uniform_buffer_binding(
+        &mut self,
+        binding: usize,
+        uniform_buffer: Option<UniformBufferID>,
+    ) -> Result<(), RendererError> {
+        if BUILTIN_UNIFORM_BLOCKS
+            .iter()
+            .any(|builtin| builtin.binding == binding)
+        {
+            return Err(RendererError::BuiltinUniformBufferBinding(binding));
+        }
+        match uniform_buffer {
+            Some(id) => {
+                if !self.uniform_buffers.contains_key(&id) {
+                    return Err(RendererError::InvalidUniformBuffer(id));
+                }
+                self.uniform_buffer_bindings.insert(binding, id)
+            }
+            None => self.uniform_buffer_bindings.remove(&binding),
+        };
+        Ok(())
+    }
+
+    fn create_texture(
+        &mut self,
+        size: Vector2<usize>,
+        data: Pixels,
+        format: Option<TextureFormat>,
+        sampler: SamplerDescription,
+    ) -> Result<TextureID, RendererError> {
+        let texture = SoftwareTexture::new(size, data, format, sampler)?;
+        let id = texture.get_id();
+        assert!(self.textures.insert(id, texture).is_none());
+        Ok(id)
+    }
+
+    fn share_texture(&mut self, id: TextureID) -> Result<(), RendererError> {
+        if self.textures.contains_key(&id) {
+            Ok(())
+        } else {
+            Err(RendererError::ResourceSharingUnsupported)
+        }
+    }
+
+    fn destroy_texture(&mut self, id: TextureID) {
+        self.textures.remove(&id);
+    }
+
+    fn get_texture(&self, id: TextureID) -> Option<&dyn Texture> {
+        self.textures
+            .get(&id)
+            .map(|texture| texture as &dyn Texture)
+    }
+
+    fn get_texture_mut(&mut self, id: TextureID) -> Option<&mut dyn Texture> {
+        self.textures
+            .get_mut(&id)
+            .map(|texture| texture as &mut dyn Texture)
+    }
+
+    fn create_material(&mut self, shader: ShaderID) -> Result<MaterialID, RendererError> {
+        let material = StoredMaterial::new(
+            shader,
+            self.shaders
+                .get(&shader)
+                .ok_or(RendererError::InvalidShader(shader))?
+                .get_declared_uniforms(),
+        );
+        let id = material.get_id();
+        assert!(self.materials.insert(id, material).is_none());
+        Ok(id)
+    }
+
+    fn destroy_material(&mut self, id: MaterialID) {
+        self.materials.remove(&id);
+    }
+
+    fn get_material(&self, id: MaterialID) -> Option<&dyn Material> {
+        self.materials
+            .get(&id)
+            .map(|material| material as &dyn Material)
+    }
+
+    fn get_material_mut(&mut self, id: MaterialID) -> Option<&mut dyn Material> {
+        self.materials
+            .get_mut(&id)
+            .map(|material| material as &mut dyn Material)
+    }
+
+    fn create_framebuffer(
+        &mut self,
+        size: Vector2<usize>,
+        color_attachments: &[TextureID],
+        depth_attachment: DepthAttachment,
+    ) -> Result<FramebufferID, RendererError> {
+        let framebuffer =
+            SoftwareFramebuffer::new(size, color_attachments, depth_attachment, &self.textures)?;
+        let id = framebuffer.get_id();
+        assert!(self.framebuffers.insert(id, framebuffer).is_none());
+        Ok(id)
+    }
+
+    fn destroy_framebuffer(&mut self, id: FramebufferID) {
+        self.framebuffers.remove(&id);
+    }
+
+    fn get_framebuffer(&self, id: FramebufferID) -> Option<&dyn Framebuffer> {
+        self.framebuffers
+            .get(&id)
+            .map(|framebuffer| framebuffer as &dyn Framebuffer)
+    }
+
+    fn get_framebuffer_mut(&mut self, id: FramebufferID) -> Option<&mut dyn Framebuffer> {
+        self.framebuffers
+            .get_mut(&id)
+            .map(|framebuffer| framebuffer as &mut dyn Framebuffer)
+    }
+
+    fn resize(&mut self, size: Vector2<usize>) -> Result<(), RendererError> {
+        self.surface_target = SoftwareRenderTarget::with_depth_stencil(size);
+        Ok(())
+    }
+
+    fn present(&mut self) {
+        let size = self.surface_target.get_size();
+        let pixels: Vec<u32> = self
+            .surface_target
+            .get_color(0)
+            .iter()
+            .map(|&color| {
+                let color = clamp_color(color);
+                let red = (color.x * 255.0).round() as u32;
+                let green = (color.y * 255.0).round() as u32;
+                let blue = (color.z * 255.0).round() as u32;
+                (red << 16) | (green << 8) | blue
+            })
+            .collect();
+        self.surface.as_mut().unwrap().present_pixels(size, &pixels);
+    }
+
+    fn read_pixels(&self) -> Image {
+        Image {
+            size: self.surface_target.get_size(),
+            pixels: self.surface_target.get_color(0).to_vec(),
+        }
+    }
+
+    fn set_depth_convention(
+        &mut self,
+        depth_convention: DepthConvention,
+    ) -> Result<(), RendererError> {
+        self.depth_convention = depth_convention;
+        Ok(())
+    }
+
+    fn get_depth_convention(&self) -> DepthConvention {
+        self.depth_convention
+    }
+
+    fn clear(
+        &mut self,
+        framebuffer: Option<FramebufferID>,
+        description: ClearDescription,
+    ) -> Result<(), RendererError> {
+        let mut render_target = self.take_render_target(framebuffer)?;
+        render_target.clear(&description);
+        self.return_render_target(framebuffer, render_target);
+        Ok(())
+    }
+
+    fn drawing_context<'a>(
+        &'a mut self,
+        framebuffer: Option<FramebufferID>,
+        viewport: Option<Rect>,
+        scissor: Option<Rect>,
+        camera: Camera<f32>,
+        pipeline_state: PipelineState,
+        cull_face: CullFace,
+    ) -> Result<Box<dyn RendererDrawContext + 'a>, RendererError> {
+        let target_size = match framebuffer {
+            Some(id) => {
+                let framebuffer = self
+                    .framebuffers
+                    .get(&id)
+                    .ok_or(RendererError::InvalidFramebuffer(id))?;
+                check_attachment_sizes(
+                    framebuffer.get_size(),
+                    framebuffer.get_attached_textures(),
+                    &self.textures,
+                )?;
+                framebuffer.get_size()
+            }
+            None => self.surface_target.get_size(),
+        };
+        let view_matrix = camera.transform.into();
+        let projection_matrix = camera.projection_type.to_matrix(self.depth_convention);
+        Ok(Box::new(SoftwareRendererDrawContext {
+            renderer: self,
+            framebuffer,
+            viewport: viewport.unwrap_or(Rect::new(Vector2::zero(), target_size)),
+            scissor,
+            view_matrix,
+            projection_matrix,
+            camera_block: get_camera_block_data(projection_matrix, view_matrix),
+            pipeline_state,
+            cull_face,
+            point_size: 1.0,
+            line_width: 1.0,
+            _send: PhantomData,
+            _sync: PhantomData,
+        }))
+    }
+}
+
+pub struct SoftwareRendererDrawContext<'a> {
+    renderer: &'a mut SoftwareRenderer,
+    framebuffer: Option<FramebufferID>,
+    viewport: Rect,
+    scissor: Option<Rect>,
+    view_matrix: Matrix4x4<f32>,
+    projection_matrix: Matrix4x4<f32>,
+    camera_block: Vec<u8>,
+    pipeline_state: PipelineState,
+    cull_face: CullFace,
+    point_size: f32,
+    line_width: f32,
+    _send: PhantomUnsend,
+    _sync: PhantomUnsync,
+}
+
+/// The vertices of every primitive that gets drawn, in the order opengl draws them
+enum Primitives {
+    Points(Vec<u32>),
+    Lines(Vec<[u32; 2]>),
+    Triangles(Vec<[u32; 3]>),
+}
+
+impl Primitives {
+    /// The number of indices has to be checked with `check_vertex_count` and every index with
+    /// `check_indices` first
+    fn new(typ: PrimitiveType, indices: &[u32]) -> Primitives {
+        match typ {
+            PrimitiveType::Points => Primitives::Points(indices.to_vec()),
+            PrimitiveType::Lines => Primitives::Lines(
+                indices
+                    .chunks_exact(2)
+                    .map(|line| [line[0], line[1]])
+                    .collect(),
+            ),
+            PrimitiveType::LineStrip => {
+                Primitives::Lines(indices.windows(2).map(|line| [line[0], line[1]]).collect())
+            }
+            PrimitiveType::LineLoop => Primitives::Lines(
+                indices
+                    .windows(2)
+                    .map(|line| [line[0], line[1]])
+                    .chain(match indices {
+                        [first, .., last] => Some([*last, *first]),
+                        _ => None,
+                    })
+                    .collect(),
+            ),
+            PrimitiveType::Triangle => Primitives::Triangles(
+                indices
+                    .chunks_exact(3)
+                    .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+                    .collect(),
+            ),
+            // Every other triangle in a strip is flipped so they all keep the same winding
+            PrimitiveType::TriangleStrip => Primitives::Triangles(
+                indices
+                    .windows(3)
+                    .enumerate()
+                    .map(|(i, triangle)| {
+                        if i % 2 == 0 {
+                            [triangle[0], triangle[1], triangle[2]]
+                        } else {
+                            [triangle[1], triangle[0], triangle[2]]
+                        }
+                    })
+                    .collect(),
+            ),
+            PrimitiveType::TriangleFan => Primitives::Triangles(match indices {
+                [first, rest @ ..] => rest
+                    .windows(2)
+                    .map(|pair| [*first, pair[0], pair[1]])
+                    .collect(),
+                [] => vec![],
+            }),
+        }
+    }
+}
+
+impl<'a> SoftwareRendererDrawContext<'a> {
+    #[allow(clippy::too_many_arguments)]
+    fn draw_vertices(
+        &mut self,
+        typ: PrimitiveType,
+        shader: ShaderID,
+        vertex_buffer: VertexBufferID,
+        indices: Option<IndexBufferID>,
+        instances: Option<(VertexBufferID, usize)>,
+        texture: Option<TextureID>,
+        model_matrix: Matrix4x4<f32>,
+        color: Vector3<f32>,
+    ) -> Result<(), RendererError> {
+        let mut render_target = self.renderer.take_render_target(self.framebuffer)?;
+        render_target.set_viewport(self.viewport, self.scissor);
+        render_target.set_depth_range(self.renderer.depth_convention.range);
+        // The render target is given back even if drawing fails so the framebuffer is not lost
+        let result = self.draw_to(
+            &mut render_target,
+            typ,
+            shader,
+            vertex_buffer,
+            indices,
+            instances,
+            texture,
+            model_matrix,
+            color,
+        );
+        self.renderer
+            .return_render_target(self.framebuffer, render_target);
+        result
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn draw_to(
+        &self,
+        render_target: &mut SoftwareRenderTarget,
+        typ: PrimitiveType,
+        shader: ShaderID,
+        vertex_buffer: VertexBufferID,
+        indices: Option<IndexBufferID>,
+        instances: Option<(VertexBufferID, usize)>,
+        texture: Option<TextureID>,
+        model_matrix: Matrix4x4<f32>,
+        color: Vector3<f32>,
+    ) -> Result<(), RendererError> {
+        let renderer = &*self.renderer;
+        let shader = renderer
+            .shaders
+            .get(&shader)
+            .ok_or(RendererError::InvalidShader(shader))?;
+        let vertex_buffer = renderer
+            .vertex_buffers
+            .get(&vertex_buffer)
+            .ok_or(RendererError::InvalidVertexBuffer(vertex_buffer))?;
+        let indices: Vec<u32> = match indices {
+            Some(id) => renderer
+                .index_buffers
+                .get(&id)
+                .ok_or(RendererError::InvalidIndexBuffer(id))?
+                .get_indices()
+                .to_vec(),
+            None => (0..vertex_buffer.get_count() as u32).collect(),
+        };
+        check_vertex_count(typ, indices.len())?;
+        check_indices(&indices, vertex_buffer.get_count())?;
+        let (instance_buffer, instance_count) = match instances {
+            Some((id, instance_count)) => {
+                let instance_buffer = renderer
+                    .vertex_buffers
+                    .get(&id)
+                    .ok_or(RendererError::InvalidVertexBuffer(id))?;
+                check_instance_buffer(
+                    vertex_buffer.get_layout(),
+                    instance_buffer.get_layout(),
+                    instance_buffer.get_count(),
+                    instance_count,
+                )?;
+                (Some(instance_buffer), instance_count)
+            }
+            None => (None, 1),
+        };
+        check_vertex_layout(
+            shader.get_inputs(),
+            vertex_buffer.get_layout(),
+            instance_buffer.map(|instance_buffer| instance_buffer.get_layout()),
+        )?;
+        let texture = match texture {
+            Some(id) => renderer
+                .textures
+                .get(&id)
+                .ok_or(RendererError::InvalidTexture(id))?,
+            None => &renderer.default_white_pixel,
+        };
+
+        let builtins = [
+            (
+                "u_ProjectionMatrix",
+                UniformValue::Matrix4x4(self.projection_matrix),
+            ),
+            ("u_ViewMatrix", UniformValue::Matrix4x4(self.view_matrix)),
+            ("u_ModelMatrix", UniformValue::Matrix4x4(model_matrix)),
+            ("u_Color", UniformValue::Float3(color)),
+        ]
+        .into_iter()
+        .map(|(name, value)| (name, SoftwareUniformValue::Value(value)))
+        .chain([("u_Texture", SoftwareUniformValue::Texture(texture))])
+        .filter(|(name, _)| shader.is_uniform_declared(name));
+        let values = shader.get_values().iter().map(|(name, value)| {
+            let value = match value.as_value() {
+                UniformValue::Texture(id) => SoftwareUniformValue::Texture(
+                    renderer
+                        .textures
+                        .get(&id)
+                        .unwrap_or(&renderer.default_white_pixel),
+                ),
+                value => SoftwareUniformValue::Value(value),
+            };
+            (name.as_str(), value)
+        });
+        // A block whose binding has no buffer is left out, `get_block` returns `None` for it
+        let blocks = shader
+            .get_uniform_blocks()
+            .iter()
+            .filter_map(|(name, binding)| {
+                // Only the builtin `Camera` block can be at binding 0
+                let data = if *binding == 0 {
+                    &self.camera_block[..]
+                } else {
+                    let id = renderer.uniform_buffer_bindings.get(binding)?;
+                    renderer.uniform_buffers.get(id)?.get_data()
+                };
+                Some((name.as_str(), data))
+            })
+            .collect();
+        // The builtin uniforms come first so they are found before a value that was set for them
+        let uniforms = SoftwareShaderUniforms {
+            values: builtins.chain(values).collect(),
+            blocks,
+            sampling: RefCell::new(SoftwareSampling::WithoutDerivatives),
+        };
+        let derivatives = uniforms.values.iter().any(|(_, value)| {
+            matches!(value, SoftwareUniformValue::Texture(texture) if texture.needs_derivatives())
+        });
+        let source = shader.get_source();
+
+        let record_tex_coords = |varyings: &[f32], outputs: &mut [Vector4<f32>]| {
+            uniforms
+                .sampling
+                .replace(SoftwareSampling::Recording(vec![]));
+            (source.fragment)(varyings, &uniforms, outputs);
+            match uniforms
+                .sampling
+                .replace(SoftwareSampling::WithoutDerivatives)
+            {
+                SoftwareSampling::Recording(tex_coords) => tex_coords,
+                _ => unreachable!(),
+            }
+        };
+        let mut fragment = |varyings: &[f32],
+                            neighbours: Option<(&[f32], &[f32])>,
+                            outputs: &mut [Vector4<f32>]| {
+            if let Some((varyings_right, varyings_above)) = neighbours {
+                let right = record_tex_coords(varyings_right, outputs);
+                let above = record_tex_coords(varyings_above, outputs);
+                uniforms.sampling.replace(SoftwareSampling::Replaying {
+                    right,
+                    above,
+                    next: 0,
+                });
+                outputs.fill(Vector4::zero());
+            }
+            (source.fragment)(varyings, &uniforms, outputs);
+        };
+
+        let primitives = Primitives::new(typ, &indices);
+        let mut inputs = vec![Vector4::from((0.0, 0.0, 0.0, 1.0)); shader.get_inputs().len()];
+        for instance in 0..instance_count {
+            if let Some(instance_buffer) = instance_buffer {
+                for attribute in instance_buffer.get_attributes() {
+                    if let Some(input) = inputs.get_mut(attribute.location) {
+                        *input = instance_buffer.get_element(instance, attribute);
+                    }
+                }
+            }
+            let vertices: Vec<ShadedVertex> = (0..vertex_buffer.get_count())
+                .map(|vertex| {
+                    for attribute in vertex_buffer.get_attributes() {
+                        if let Some(input) = inputs.get_mut(attribute.location) {
+                            *input = vertex_buffer.get_element(vertex, attribute);
+                        }
+                    }
+                    let mut varyings = Vec::new();
+                    let position = (source.vertex)(&inputs, &uniforms, &mut varyings);
+                    ShadedVertex { position, varyings }
+                })
+                .collect();
+            if let Some(pair) = vertices
+                .windows(2)
+                .find(|pair| pair[0].varyings.len() != pair[1].varyings.len())
+            {
+                return Err(RendererError::VaryingCountMismatch {
+                    expected: pair[0].varyings.len(),
+                    actual: pair[1].varyings.len(),
+                });
+            }
+
+            match &primitives {
+                Primitives::Points(points) => {
+                    for a in points {
+                        render_target.draw_point(
+                            &vertices[*a as usize],
+                            self.point_size,
+                            &self.pipeline_state,
+                            derivatives,
+                            &mut fragment,
+                        );
+                    }
+                }
+                Primitives::Lines(lines) => {
+                    for [a, b] in lines {
+                        render_target.draw_line(
+                            [&vertices[*a as usize], &vertices[*b as usize]],
+                            self.line_width,
+                            &self.pipeline_state,
+                            derivatives,
+                            &mut fragment,
+                        );
+                    }
+                }
+                Primitives::Triangles(triangles) => {
+                    for [a, b, c] in triangles {
+                        render_target.draw_triangle(
+                            [
+                                &vertices[*a as usize],
+                                &vertices[*b as usize],
+                                &vertices[*c as usize],
+                            ],
+                            &self.pipeline_state,
+                            &self.cull_face,
+                            derivatives,
+                            &mut fragment,
+                        );
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'a> RendererDrawContext for SoftwareRendererDrawContext<'a> {
+    fn set_uniform(
+        &mut self,
+        shader: ShaderID,
+        name: &str,
+        value: UniformValue,
+    ) -> Result<(), RendererError> {
+        self.renderer
+            .shaders
+            .get_mut(&shader)
+            .ok_or(RendererError::InvalidShader(shader))?
+            .set_uniform(name, value)
+    }
+
+    fn bind_material(&mut self, material: MaterialID) -> Result<ShaderID, RendererError> {
+        let material = self
+            .renderer
+            .materials
+            .get(&material)
+            .ok_or(RendererError::InvalidMaterial(material))?;
+        let shader = self
+            .renderer
+            .shaders
+            .get_mut(&material.get_shader())
+            .ok_or(RendererError::InvalidShader(material.get_shader()))?;
+        material.apply(shader, self.renderer.default_white_pixel.get_id())?;
+        Ok(material.get_shader())
+    }
+
+    fn set_pipeline_state(&mut self, pipeline_state: PipelineState) {
+        self.pipeline_state = pipeline_state;
+    }
+
+    fn get_pipeline_state(&self) -> PipelineState {
+        self.pipeline_state
+    }
+
+    fn set_point_size(&mut self, size: f32) {
+        self.point_size = size.max(1.0);
+    }
+
+    fn set_line_width(&mut self, width: f32) {
+        self.line_width = width.max(1.0);
+    }
+
+    fn draw(
+        &mut self,
+        typ: PrimitiveType,
+        shader: ShaderID,
+        vertex_buffer: VertexBufferID,
+        texture: Option<TextureID>,
+        model_matrix: Matrix4x4<f32>,
+        color: Vector3<f32>,
+    ) -> Result<(), RendererError> {
+        self.draw_vertices(
+            typ,
+            shader,
+            vertex_buffer,
+            None,
+            None,
+            texture,
+            model_matrix,
+            color,
+        )
+    }
+
+    fn draw_indexed(
+        &mut self,
+        typ: PrimitiveType,
+        shader: ShaderID,
+        vertex_buffer: VertexBufferID,
+        index_buffer: IndexBufferID,
+        texture: Option<TextureID>,
+        model_matrix: Matrix4x4<f32>,
+        color: Vector3<f32>,
+    ) -> Result<(), RendererError> {
+        self.draw_vertices(
+            typ,
+            shader,
+            vertex_buffer,
+            Some(index_buffer),
+            None,
+            texture,
+            model_matrix,
+            color,
+        )
+    }
+
+    fn draw_instanced(
+        &mut self,
+        typ: PrimitiveType,
+        shader: ShaderID,
+        vertex_buffer: VertexBufferID,
+        instance_buffer: VertexBufferID,
+        instance_count: usize,
+        texture: Option<TextureID>,
+        model_matrix: Matrix4x4<f32>,
+        color: Vector3<f32>,
+    ) -> Result<(), RendererError> {
+        self.draw_vertices(
+            typ,
+            shader,
+            vertex_buffer,
+            None,
+            Some((instance_buffer, instance_count)),
+            texture,
+            model_matrix,
+            color,
+        )
+    }
+
+    fn draw_indexed_instanced(
+        &mut self,
+        typ: PrimitiveType,
+        shader: ShaderID,
+        vertex_buffer: VertexBufferID,
+        index_buffer: IndexBufferID,
+        instance_buffer: VertexBufferID,
+        instance_count: usize,
+        texture: Option<TextureID>,
+        model_matrix: Matrix4x4<f32>,
+        color: Vector3<f32>,
+    ) -> Result<(), RendererError> {
+        self.draw_vertices(
+            typ,
+            shader,
+            vertex_buffer,
+            Some(index_buffer),
+            Some((instance_buffer, instance_count)),
+            texture,
+            model_matrix,
+            color,
+        )
+    }
+}
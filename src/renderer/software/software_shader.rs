@@ -0,0 +1,268 @@
+use std::{cell::RefCell, marker::PhantomData, sync::atomic::AtomicUsize};
+
+use crate::{
+    math::{Matrix3x3, Matrix4x4, Vector2, Vector3, Vector4},
+    renderer::{
+        shader::{
+            check_uniform_array_size, check_uniform_value, get_declared_variables,
+            StoredUniformValue,
+        },
+        software::SoftwareTexture,
+        RendererError, Shader, ShaderDataType, ShaderDescription, ShaderID, ShaderInput,
+        ShaderVariable, SoftwareShaderSource, UniformValue,
+    },
+    PhantomUnsend, PhantomUnsync,
+};
+
+pub(crate) struct SoftwareShader {
+    id: ShaderID,
+    inputs: Vec<ShaderVariable>,
+    uniforms: Vec<ShaderVariable>,
+    declared_uniforms: Vec<(String, ShaderDataType)>,
+    uniform_blocks: Vec<(String, usize)>,
+    values: Vec<(String, StoredUniformValue)>,
+    source: SoftwareShaderSource,
+    _send: PhantomUnsend,
+    _sync: PhantomUnsync,
+}
+
+impl SoftwareShader {
+    pub(crate) fn new(description: &ShaderDescription) -> Result<SoftwareShader, RendererError> {
+        description.validate()?;
+        let Some(source) = description.software else {
+            return Err(RendererError::MissingShaderSource);
+        };
+        Ok(SoftwareShader {
+            id: {
+                static ID: AtomicUsize = AtomicUsize::new(1);
+                ShaderID(
+                    ID.fetch_add(1, std::sync::atomic::Ordering::AcqRel),
+                    PhantomData,
+                    PhantomData,
+                )
+            },
+            inputs: to_shader_variables(description.inputs),
+            uniforms: get_declared_variables(description.uniforms),
+            declared_uniforms: description
+                .uniforms
+                .iter()
+                .map(|uniform| (uniform.name.to_string(), uniform.typ))
+                .collect(),
+            uniform_blocks: description
+                .uniform_blocks
+                .iter()
+                .map(|block| (block.name.to_string(), block.binding))
+                .collect(),
+            values: vec![],
+            source,
+            _send: PhantomData,
+            _sync: PhantomData,
+        })
+    }
+
+    /// Replaces the shader with one made from `description` but keeps the id, the current shader is
+    /// kept if `description` is not valid
+    pub(crate) fn reload(&mut self, description: &ShaderDescription) -> Result<(), RendererError> {
+        let mut shader = SoftwareShader::new(description)?;
+        shader.id = self.id;
+        *self = shader;
+        Ok(())
+    }
+
+    pub(crate) fn get_source(&self) -> SoftwareShaderSource {
+        self.source
+    }
+
+    pub(crate) fn get_declared_uniforms(&self) -> &[(String, ShaderDataType)] {
+        &self.declared_uniforms
+    }
+
+    pub(crate) fn is_uniform_declared(&self, name: &str) -> bool {
+        self.declared_uniforms
+            .iter()
+            .any(|(uniform, _)| uniform == name)
+    }
+
+    /// The names and bindings of the declared uniform blocks
+    pub(crate) fn get_uniform_blocks(&self) -> &[(String, usize)] {
+        &self.uniform_blocks
+    }
+
+    /// The values that were set with `set_uniform`
+    pub(crate) fn get_values(&self) -> &[(String, StoredUniformValue)] {
+        &self.values
+    }
+}
+
+impl Shader for SoftwareShader {
+    fn get_id(&self) -> ShaderID {
+        self.id
+    }
+
+    fn get_inputs(&self) -> &[ShaderVariable] {
+        &self.inputs
+    }
+
+    fn get_uniforms(&self) -> &[ShaderVariable] {
+        &self.uniforms
+    }
+
+    fn set_uniform(&mut self, name: &str, value: UniformValue) -> Result<(), RendererError> {
+        check_uniform_value(&self.declared_uniforms, name, &value)?;
+        check_uniform_array_size(&self.uniforms, name, &value)?;
+        let value = StoredUniformValue::from(value);
+        match self.values.iter_mut().find(|(uniform, _)| uniform == name) {
+            Some((_, stored)) => *stored = value,
+            None => self.values.push((name.to_string(), value)),
+        }
+        Ok(())
+    }
+}
+
+/// Software shaders can not be inspected, so every declared input is reported
+fn to_shader_variables(inputs: &[ShaderInput]) -> Vec<ShaderVariable> {
+    inputs
+        .iter()
+        .enumerate()
+        .map(|(location, input)| ShaderVariable {
+            name: input.name.to_string(),
+            typ: input.typ,
+            array_size: 1,
+            location,
+        })
+        .collect()
+}
+
+pub(crate) enum SoftwareUniformValue<'a> {
+    Value(UniformValue<'a>),
+    Texture(&'a SoftwareTexture),
+}
+
+/// Where the derivatives for texture sampling come from, the fragment shader gets run for the
+/// pixels to the right and above first to record the texture coordinates they sample at
+pub(crate) enum SoftwareSampling {
+    WithoutDerivatives,
+    Recording(Vec<Vector2<f32>>),
+    Replaying {
+        right: Vec<Vector2<f32>>,
+        above: Vec<Vector2<f32>>,
+        next: usize,
+    },
+}
+
+/// The uniforms a software shader is run with, every getter returns `None` if the uniform was not
+/// set or has a different type
+pub struct SoftwareShaderUniforms<'a> {
+    pub(crate) values: Vec<(&'a str, SoftwareUniformValue<'a>)>,
+    pub(crate) blocks: Vec<(&'a str, &'a [u8])>,
+    pub(crate) sampling: RefCell<SoftwareSampling>,
+}
+
+impl<'a> SoftwareShaderUniforms<'a> {
+    fn get(&self, name: &str) -> Option<&SoftwareUniformValue<'a>> {
+        self.values
+            .iter()
+            .find(|(uniform, _)| *uniform == name)
+            .map(|(_, value)| value)
+    }
+
+    /// Gets the bytes of the uniform buffer bound to a declared uniform block, they are laid out
+    /// like `Std140Writer` writes them
+    pub fn get_block(&self, name: &str) -> Option<&'a [u8]> {
+        self.blocks
+            .iter()
+            .find(|(block, _)| *block == name)
+            .map(|&(_, data)| data)
+    }
+
+    /// Gets any uniform that is not a texture, textures can only be sampled with `sample`
+    pub fn get_value(&self, name: &str) -> Option<UniformValue<'a>> {
+        match self.get(name)? {
+            SoftwareUniformValue::Value(value) => Some(*value),
+            SoftwareUniformValue::Texture(_) => None,
+        }
+    }
+
+    pub fn get_float(&self, name: &str) -> Option<f32> {
+        match self.get_value(name)? {
+            UniformValue::Float(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_vector2(&self, name: &str) -> Option<Vector2<f32>> {
+        match self.get_value(name)? {
+            UniformValue::Float2(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_vector3(&self, name: &str) -> Option<Vector3<f32>> {
+        match self.get_value(name)? {
+            UniformValue::Float3(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_vector4(&self, name: &str) -> Option<Vector4<f32>> {
+        match self.get_value(name)? {
+            UniformValue::Float4(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_int(&self, name: &str) -> Option<i32> {
+        match self.get_value(name)? {
+            UniformValue::Int(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_uint(&self, name: &str) -> Option<u32> {
+        match self.get_value(name)? {
+            UniformValue::UInt(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_matrix3(&self, name: &str) -> Option<Matrix3x3<f32>> {
+        match self.get_value(name)? {
+            UniformValue::Matrix3x3(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_matrix(&self, name: &str) -> Option<Matrix4x4<f32>> {
+        match self.get_value(name)? {
+            UniformValue::Matrix4x4(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Samples the texture `name` at `tex_coord`, the same way `texture` does in glsl
+    pub fn sample(&self, name: &str, tex_coord: Vector2<f32>) -> Option<Vector4<f32>> {
+        match self.get(name)? {
+            SoftwareUniformValue::Texture(texture) => {
+                let derivatives = match &mut *self.sampling.borrow_mut() {
+                    SoftwareSampling::WithoutDerivatives => None,
+                    SoftwareSampling::Recording(tex_coords) => {
+                        tex_coords.push(tex_coord);
+                        None
+                    }
+                    SoftwareSampling::Replaying { right, above, next } => {
+                        let index = *next;
+                        *next += 1;
+                        match (right.get(index), above.get(index)) {
+                            (Some(&right), Some(&above)) => {
+                                Some((right - tex_coord, above - tex_coord))
+                            }
+                            _ => None,
+                        }
+                    }
+                };
+                Some(texture.sample(tex_coord, derivatives))
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,432 @@
+use std::{marker::PhantomData, sync::atomic::AtomicUsize};
+
+use crate::{
+    math::{Vector2, Vector4, Zero},
+    renderer::{
+        texture::{check_pixel_count, check_region_in_bounds},
+        Pixels, RendererError, SamplerDescription, Texture, TextureFilter, TextureFormat,
+        TextureID, TextureWrap,
+    },
+    PhantomUnsend, PhantomUnsync,
+};
+
+pub(crate) struct SoftwareTexture {
+    id: TextureID,
+    size: Vector2<usize>,
+    pixels: Vec<Vector4<f32>>,
+    /// Every level after the full size one
+    mipmaps: Vec<(Vector2<usize>, Vec<Vector4<f32>>)>,
+    format: TextureFormat,
+    sampler: SamplerDescription,
+    _send: PhantomUnsend,
+    _sync: PhantomUnsync,
+}
+
+impl SoftwareTexture {
+    pub(crate) fn new(
+        size: Vector2<usize>,
+        pixels: Pixels,
+        format: Option<TextureFormat>,
+        sampler: SamplerDescription,
+    ) -> Result<SoftwareTexture, RendererError> {
+        let mut texture = SoftwareTexture {
+            id: {
+                static ID: AtomicUsize = AtomicUsize::new(1);
+                TextureID(
+                    ID.fetch_add(1, std::sync::atomic::Ordering::AcqRel),
+                    PhantomData,
+                    PhantomData,
+                )
+            },
+            size: 0.into(),
+            pixels: vec![],
+            mipmaps: vec![],
+            format: format.unwrap_or_else(|| TextureFormat::from_pixels(&pixels)),
+            sampler,
+            _send: PhantomData,
+            _sync: PhantomData,
+        };
+        texture.set_pixels(size, pixels)?;
+        Ok(texture)
+    }
+
+    /// Moves the full size image out so it can be drawn to, it has to be given back with
+    /// `set_stored_pixels`
+    pub(crate) fn take_pixels(&mut self) -> Vec<Vector4<f32>> {
+        std::mem::take(&mut self.pixels)
+    }
+
+    /// Stores `pixels` with the precision and channels of the format, without changing the size
+    pub(crate) fn set_stored_pixels(&mut self, pixels: Vec<Vector4<f32>>) {
+        assert_eq!(pixels.len(), self.size.x * self.size.y);
+        let format = self.format;
+        self.pixels = pixels
+            .into_iter()
+            .map(|pixel| quantize(pixel, format))
+            .collect();
+        if self.sampler.mipmaps {
+            self.generate_mipmaps();
+        }
+    }
+
+    /// Depth textures can only be set from single channel pixels
+    fn check_pixels_match_format(&self, pixels: &Pixels) -> Result<(), RendererError> {
+        if self.format.is_depth()
+            && !matches!(pixels, Pixels::R(_) | Pixels::R16(_) | Pixels::RF(_))
+        {
+            return Err(RendererError::PixelFormatMismatch(self.format));
+        }
+        Ok(())
+    }
+
+    /// Whether `sample` needs the derivatives of the texture coordinate to pick a filter or a mip
+    /// level
+    pub(crate) fn needs_derivatives(&self) -> bool {
+        let without_mipmaps = |filter| match filter {
+            TextureFilter::Trilinear => TextureFilter::Linear,
+            filter => filter,
+        };
+        !self.mipmaps.is_empty()
+            || without_mipmaps(self.sampler.min_filter) != without_mipmaps(self.sampler.mag_filter)
+    }
+
+    /// Each level is a box filter of the one before it, like `glGenerateMipmap`
+    fn generate_mipmaps(&mut self) {
+        self.mipmaps.clear();
+        let (mut size, mut pixels) = (self.size, &self.pixels);
+        while size.x > 1 || size.y > 1 {
+            let next_size: Vector2<usize> = ((size.x / 2).max(1), (size.y / 2).max(1)).into();
+            let mut next_pixels = Vec::with_capacity(next_size.x * next_size.y);
+            for y in 0..next_size.y {
+                for x in 0..next_size.x {
+                    let mut sum = Vector4::<f32>::zero();
+                    for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                        let source_x = (x * 2 + dx).min(size.x - 1);
+                        let source_y = (y * 2 + dy).min(size.y - 1);
+                        sum += pixels[source_y * size.x + source_x];
+                    }
+                    next_pixels.push(sum * Vector4::from(0.25));
+                }
+            }
+            self.mipmaps.push((next_size, next_pixels));
+            (size, pixels) = {
+                let (size, pixels) = self.mipmaps.last().unwrap();
+                (*size, pixels)
+            };
+        }
+    }
+
+    fn get_level(&self, level: usize) -> (Vector2<usize>, &[Vector4<f32>]) {
+        match level {
+            0 => (self.size, &self.pixels),
+            level => {
+                let (size, pixels) = &self.mipmaps[level - 1];
+                (*size, pixels)
+            }
+        }
+    }
+
+    fn sample_level(
+        &self,
+        level: usize,
+        tex_coord: Vector2<f32>,
+        filter: TextureFilter,
+    ) -> Vector4<f32> {
+        let (size, pixels) = self.get_level(level);
+        let texel = |x: isize, y: isize| {
+            let x = wrap(x, size.x, self.sampler.wrap_u);
+            let y = wrap(y, size.y, self.sampler.wrap_v);
+            let pixel = pixels[y * size.x + x];
+            if self.format == TextureFormat::SRGB8Alpha8 {
+                let to_linear = |value: f32| {
+                    if value <= 0.04045 {
+                        value / 12.92
+                    } else {
+                        ((value + 0.055) / 1.055).powf(2.4)
+                    }
+                };
+                (
+                    to_linear(pixel.x),
+                    to_linear(pixel.y),
+                    to_linear(pixel.z),
+                    pixel.w,
+                )
+                    .into()
+            } else {
+                pixel
+            }
+        };
+
+        let x = tex_coord.x * size.x as f32;
+        let y = tex_coord.y * size.y as f32;
+        match filter {
+            TextureFilter::Nearest => texel(x.floor() as isize, y.floor() as isize),
+            TextureFilter::Linear | TextureFilter::Trilinear => {
+                let (x, y) = (x - 0.5, y - 0.5);
+                let (left, bottom) = (x.floor(), y.floor());
+                let (tx, ty) = (Vector4::from(x - left), Vector4::from(y - bottom));
+                let (left, bottom) = (left as isize, bottom as isize);
+                let one = Vector4::from(1.0);
+                let lower = texel(left, bottom) * (one - tx) + texel(left + 1, bottom) * tx;
+                let upper = texel(left, bottom + 1) * (one - tx) + texel(left + 1, bottom + 1) * tx;
+                lower * (one - ty) + upper * ty
+            }
+        }
+    }
+
+    /// `derivatives` are how much `tex_coord` changes to the next pixel on the right and above,
+    /// without them the texture is magnified
+    pub(crate) fn sample(
+        &self,
+        tex_coord: Vector2<f32>,
+        derivatives: Option<(Vector2<f32>, Vector2<f32>)>,
+    ) -> Vector4<f32> {
+        if self.pixels.is_empty() {
+            return (0.0, 0.0, 0.0, 1.0).into();
+        }
+
+        let lod = match derivatives {
+            Some((dx, dy)) => {
+                let (width, height) = (self.size.x as f32, self.size.y as f32);
+                let scale_x = (dx.x * width).hypot(dx.y * height);
+                let scale_y = (dy.x * width).hypot(dy.y * height);
+                scale_x.max(scale_y).log2()
+            }
+            None => f32::NEG_INFINITY,
+        };
+        if lod.is_nan() || lod <= 0.0 {
+            return self.sample_level(0, tex_coord, self.sampler.mag_filter);
+        }
+
+        let filter = self.sampler.min_filter;
+        let max_level = self.mipmaps.len();
+        if max_level == 0 {
+            return self.sample_level(0, tex_coord, filter);
+        }
+        match filter {
+            TextureFilter::Nearest | TextureFilter::Linear => {
+                let level = if lod <= 0.5 {
+                    0
+                } else {
+                    ((lod + 0.5).ceil() as usize - 1).min(max_level)
+                };
+                self.sample_level(level, tex_coord, filter)
+            }
+            TextureFilter::Trilinear => {
+                let lod = lod.min(max_level as f32);
+                let level = lod.floor() as usize;
+                let lower = self.sample_level(level, tex_coord, filter);
+                if level == max_level {
+                    return lower;
+                }
+                let upper = self.sample_level(level + 1, tex_coord, filter);
+                let t = Vector4::from(lod.fract());
+                lower * (Vector4::from(1.0) - t) + upper * t
+            }
+        }
+    }
+}
+
+/// Converts every type of `Pixels` to floats, integers are normalized to 0 to 1 and channels that
+/// are not there are `(0, 0, 0, 1)`
+pub(crate) fn convert_pixels(pixels: &Pixels) -> Vec<Vector4<f32>> {
+    fn from_u8(value: u8) -> f32 {
+        value as f32 / u8::MAX as f32
+    }
+    fn from_u16(value: u16) -> f32 {
+        value as f32 / u16::MAX as f32
+    }
+
+    match pixels {
+        Pixels::R(pixels) => pixels
+            .iter()
+            .map(|&r| (from_u8(r), 0.0, 0.0, 1.0).into())
+            .collect(),
+        Pixels::RG(pixels) => pixels
+            .iter()
+            .map(|pixel| (from_u8(pixel.x), from_u8(pixel.y), 0.0, 1.0).into())
+            .collect(),
+        Pixels::RGB(pixels) => pixels
+            .iter()
+            .map(|pixel| (from_u8(pixel.x), from_u8(pixel.y), from_u8(pixel.z), 1.0).into())
+            .collect(),
+        Pixels::RGBA(pixels) => pixels
+            .iter()
+            .map(|pixel| {
+                (
+                    from_u8(pixel.x),
+                    from_u8(pixel.y),
+                    from_u8(pixel.z),
+                    from_u8(pixel.w),
+                )
+                    .into()
+            })
+            .collect(),
+        Pixels::R16(pixels) => pixels
+            .iter()
+            .map(|&r| (from_u16(r), 0.0, 0.0, 1.0).into())
+            .collect(),
+        Pixels::RG16(pixels) => pixels
+            .iter()
+            .map(|pixel| (from_u16(pixel.x), from_u16(pixel.y), 0.0, 1.0).into())
+            .collect(),
+        Pixels::RGB16(pixels) => pixels
+            .iter()
+            .map(|pixel| (from_u16(pixel.x), from_u16(pixel.y), from_u16(pixel.z), 1.0).into())
+            .collect(),
+        Pixels::RGBA16(pixels) => pixels
+            .iter()
+            .map(|pixel| {
+                (
+                    from_u16(pixel.x),
+                    from_u16(pixel.y),
+                    from_u16(pixel.z),
+                    from_u16(pixel.w),
+                )
+                    .into()
+            })
+            .collect(),
+        Pixels::RF(pixels) => pixels.iter().map(|&r| (r, 0.0, 0.0, 1.0).into()).collect(),
+        Pixels::RGF(pixels) => pixels
+            .iter()
+            .map(|pixel| (pixel.x, pixel.y, 0.0, 1.0).into())
+            .collect(),
+        Pixels::RGBF(pixels) => pixels
+            .iter()
+            .map(|pixel| (pixel.x, pixel.y, pixel.z, 1.0).into())
+            .collect(),
+        Pixels::RGBAF(pixels) => pixels.to_vec(),
+    }
+}
+
+/// Rounds `pixel` to what `format` can store
+fn quantize(pixel: Vector4<f32>, format: TextureFormat) -> Vector4<f32> {
+    fn unorm(value: f32, max: f32) -> f32 {
+        (value.clamp(0.0, 1.0) * max).round() / max
+    }
+    fn half(value: f32) -> f32 {
+        // Rounds away the 13 bits of mantissa that a half float does not have
+        let value = value.clamp(-65504.0, 65504.0);
+        f32::from_bits((value.to_bits() + 0x1000) & !0x1FFF)
+    }
+
+    let (channels, round): (usize, &dyn Fn(f32) -> f32) = match format {
+        TextureFormat::R8 => (1, &|value| unorm(value, 255.0)),
+        TextureFormat::RG8 => (2, &|value| unorm(value, 255.0)),
+        TextureFormat::RGB8 => (3, &|value| unorm(value, 255.0)),
+        TextureFormat::RGBA8 | TextureFormat::SRGB8Alpha8 => (4, &|value| unorm(value, 255.0)),
+        TextureFormat::R16 => (1, &|value| unorm(value, 65535.0)),
+        TextureFormat::RG16 => (2, &|value| unorm(value, 65535.0)),
+        TextureFormat::RGB16 => (3, &|value| unorm(value, 65535.0)),
+        TextureFormat::RGBA16 => (4, &|value| unorm(value, 65535.0)),
+        TextureFormat::R16F => (1, &half),
+        TextureFormat::RG16F => (2, &half),
+        TextureFormat::RGB16F => (3, &half),
+        TextureFormat::RGBA16F => (4, &half),
+        TextureFormat::R32F => (1, &|value| value),
+        TextureFormat::RG32F => (2, &|value| value),
+        TextureFormat::RGB32F => (3, &|value| value),
+        TextureFormat::RGBA32F => (4, &|value| value),
+        TextureFormat::Depth16 => (1, &|value| unorm(value, 65535.0)),
+        TextureFormat::Depth24 => (1, &|value| unorm(value, 16777215.0)),
+        TextureFormat::Depth32F => (1, &|value: f32| value.clamp(0.0, 1.0)),
+    };
+    let (x, y, z, w) = pixel.into();
+    (
+        round(x),
+        if channels > 1 { round(y) } else { 0.0 },
+        if channels > 2 { round(z) } else { 0.0 },
+        if channels > 3 { round(w) } else { 1.0 },
+    )
+        .into()
+}
+
+fn wrap(coord: isize, size: usize, wrap: TextureWrap) -> usize {
+    let size = size as isize;
+    (match wrap {
+        TextureWrap::Repeat => coord.rem_euclid(size),
+        TextureWrap::MirroredRepeat => {
+            let coord = coord.rem_euclid(size * 2);
+            if coord < size {
+                coord
+            } else {
+                size * 2 - 1 - coord
+            }
+        }
+        TextureWrap::ClampToEdge => coord.clamp(0, size - 1),
+    }) as usize
+}
+
+impl Texture for SoftwareTexture {
+    fn get_id(&self) -> TextureID {
+        self.id
+    }
+
+    fn get_size(&self) -> Vector2<usize> {
+        self.size
+    }
+
+    fn get_format(&self) -> TextureFormat {
+        self.format
+    }
+
+    fn get_sampler(&self) -> SamplerDescription {
+        self.sampler
+    }
+
+    fn set_sampler(&mut self, sampler: SamplerDescription) {
+        let generate_mipmaps = sampler.mipmaps && !self.sampler.mipmaps;
+        self.sampler = sampler;
+        if generate_mipmaps {
+            self.generate_mipmaps();
+        } else if !sampler.mipmaps {
+            self.mipmaps.clear();
+        }
+    }
+
+    fn set_pixels(&mut self, size: Vector2<usize>, pixels: Pixels) -> Result<(), RendererError> {
+        check_pixel_count(size, &pixels)?;
+        self.check_pixels_match_format(&pixels)?;
+        self.size = size;
+        self.set_stored_pixels(convert_pixels(&pixels));
+        Ok(())
+    }
+
+    fn set_sub_pixels(
+        &mut self,
+        offset: Vector2<usize>,
+        size: Vector2<usize>,
+        pixels: Pixels,
+    ) -> Result<(), RendererError> {
+        check_region_in_bounds(self.size, offset, size)?;
+        check_pixel_count(size, &pixels)?;
+        self.check_pixels_match_format(&pixels)?;
+        let format = self.format;
+        for (i, pixel) in convert_pixels(&pixels).into_iter().enumerate() {
+            let (x, y) = (offset.x + i % size.x, offset.y + i / size.x);
+            self.pixels[y * self.size.x + x] = quantize(pixel, format);
+        }
+        if self.sampler.mipmaps {
+            self.generate_mipmaps();
+        }
+        Ok(())
+    }
+
+    fn get_pixels(&self) -> Vec<Vector4<f32>> {
+        self.pixels.clone()
+    }
+
+    fn get_sub_pixels(
+        &self,
+        offset: Vector2<usize>,
+        size: Vector2<usize>,
+    ) -> Result<Vec<Vector4<f32>>, RendererError> {
+        check_region_in_bounds(self.size, offset, size)?;
+        Ok((offset.y..offset.y + size.y)
+            .flat_map(|y| {
+                let start = y * self.size.x + offset.x;
+                self.pixels[start..start + size.x].iter().copied()
+            })
+            .collect())
+    }
+}
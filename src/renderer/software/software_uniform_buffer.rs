@@ -0,0 +1,58 @@
+use std::{marker::PhantomData, sync::atomic::AtomicUsize};
+
+use crate::{
+    renderer::{
+        uniform_buffer::check_range_in_bounds, RendererError, UniformBuffer, UniformBufferID,
+    },
+    PhantomUnsend, PhantomUnsync,
+};
+
+pub(crate) struct SoftwareUniformBuffer {
+    id: UniformBufferID,
+    data: Vec<u8>,
+    _send: PhantomUnsend,
+    _sync: PhantomUnsync,
+}
+
+impl SoftwareUniformBuffer {
+    pub(crate) fn new(data: &[u8]) -> SoftwareUniformBuffer {
+        SoftwareUniformBuffer {
+            id: {
+                static ID: AtomicUsize = AtomicUsize::new(1);
+                UniformBufferID(
+                    ID.fetch_add(1, std::sync::atomic::Ordering::AcqRel),
+                    PhantomData,
+                    PhantomData,
+                )
+            },
+            data: data.to_vec(),
+            _send: PhantomData,
+            _sync: PhantomData,
+        }
+    }
+
+    pub(crate) fn get_data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+impl UniformBuffer for SoftwareUniformBuffer {
+    fn get_id(&self) -> UniformBufferID {
+        self.id
+    }
+
+    fn get_size(&self) -> usize {
+        self.data.len()
+    }
+
+    fn set_data(&mut self, data: &[u8]) {
+        self.data.clear();
+        self.data.extend_from_slice(data);
+    }
+
+    fn set_sub_data(&mut self, offset: usize, data: &[u8]) -> Result<(), RendererError> {
+        check_range_in_bounds(self.data.len(), offset, data.len())?;
+        self.data[offset..offset + data.len()].copy_from_slice(data);
+        Ok(())
+    }
+}
@@ -0,0 +1,212 @@
+use std::{marker::PhantomData, sync::atomic::AtomicUsize};
+
+use crate::{
+    math::Vector4,
+    renderer::{
+        vertex_buffer::{
+            get_vertex_attributes, get_vertex_count, VertexAttribute, VertexComponentType,
+        },
+        RendererError, VertexBuffer, VertexBufferElement, VertexBufferID,
+    },
+    PhantomUnsend, PhantomUnsync,
+};
+
+pub(crate) struct SoftwareVertexBuffer {
+    id: VertexBufferID,
+    layout: Vec<VertexBufferElement>,
+    attributes: Vec<VertexAttribute>,
+    stride: usize,
+    count: usize,
+    data: Vec<u8>,
+    _send: PhantomUnsend,
+    _sync: PhantomUnsync,
+}
+
+impl SoftwareVertexBuffer {
+    pub(crate) fn new(
+        layout: &[VertexBufferElement],
+        data: &[u8],
+    ) -> Result<SoftwareVertexBuffer, RendererError> {
+        let mut vertex_buffer = SoftwareVertexBuffer {
+            id: {
+                static ID: AtomicUsize = AtomicUsize::new(1);
+                VertexBufferID(
+                    ID.fetch_add(1, std::sync::atomic::Ordering::AcqRel),
+                    PhantomData,
+                    PhantomData,
+                )
+            },
+            layout: vec![],
+            attributes: vec![],
+            stride: 0,
+            count: 0,
+            data: vec![],
+            _send: PhantomData,
+            _sync: PhantomData,
+        };
+        vertex_buffer.set_layout(layout, data)?;
+        Ok(vertex_buffer)
+    }
+
+    /// The elements of the layout that are not a `Location`
+    pub(crate) fn get_attributes(&self) -> &[VertexAttribute] {
+        &self.attributes
+    }
+
+    /// Reads an attribute of a vertex the same way opengl fetches attributes, missing components
+    /// are filled in from `(0, 0, 0, 1)`
+    pub(crate) fn get_element(&self, vertex: usize, attribute: &VertexAttribute) -> Vector4<f32> {
+        let (typ, component_count) = attribute.element.get_components().unwrap();
+        let offset = vertex * self.stride + attribute.offset;
+        let mut components = [0.0, 0.0, 0.0, 1.0];
+        for (i, component) in components.iter_mut().take(component_count).enumerate() {
+            let start = offset + i * typ.get_size();
+            let bytes = &self.data[start..start + typ.get_size()];
+            *component = match typ {
+                VertexComponentType::Float => f32::from_ne_bytes(bytes.try_into().unwrap()),
+                VertexComponentType::Half => {
+                    half_to_f32(u16::from_ne_bytes(bytes.try_into().unwrap()))
+                }
+                VertexComponentType::Int => i32::from_ne_bytes(bytes.try_into().unwrap()) as f32,
+                VertexComponentType::UInt => u32::from_ne_bytes(bytes.try_into().unwrap()) as f32,
+                VertexComponentType::UByteNormalized => bytes[0] as f32 / u8::MAX as f32,
+                VertexComponentType::ByteNormalized => {
+                    (bytes[0] as i8 as f32 / i8::MAX as f32).max(-1.0)
+                }
+                VertexComponentType::UShortNormalized => {
+                    u16::from_ne_bytes(bytes.try_into().unwrap()) as f32 / u16::MAX as f32
+                }
+                VertexComponentType::ShortNormalized => {
+                    let value = i16::from_ne_bytes(bytes.try_into().unwrap());
+                    (value as f32 / i16::MAX as f32).max(-1.0)
+                }
+            };
+        }
+        let [x, y, z, w] = components;
+        (x, y, z, w).into()
+    }
+}
+
+/// Converts an IEEE 754 half precision float
+fn half_to_f32(half: u16) -> f32 {
+    let sign = if half & 0x8000 == 0 { 1.0 } else { -1.0 };
+    let exponent = ((half >> 10) & 0x1f) as i32;
+    let mantissa = (half & 0x3ff) as f32;
+    sign * match exponent {
+        0 => mantissa * 2.0f32.powi(-24),
+        0x1f if mantissa == 0.0 => f32::INFINITY,
+        0x1f => f32::NAN,
+        exponent => (1.0 + mantissa / 1024.0) * 2.0f32.powi(exponent - 15),
+    }
+}
+
+impl VertexBuffer for SoftwareVertexBuffer {
+    fn get_id(&self) -> VertexBufferID {
+        self.id
+    }
+
+    fn get_count(&self) -> usize {
+        self.count
+    }
+
+    fn get_layout(&self) -> &[VertexBufferElement] {
+        &self.layout
+    }
+
+    fn set_layout(
+        &mut self,
+        layout: &[VertexBufferElement],
+        data: &[u8],
+    ) -> Result<(), RendererError> {
+        // Checked before anything changes so the buffer is left as it was
+        let (attributes, stride) = get_vertex_attributes(layout)?;
+        get_vertex_count(stride, data)?;
+
+        self.layout = layout.to_vec();
+        self.attributes = attributes;
+        self.stride = stride;
+
+        self.set_data(data)
+    }
+
+    fn set_data(&mut self, data: &[u8]) -> Result<(), RendererError> {
+        self.count = get_vertex_count(self.stride, data)?;
+        self.data.clear();
+        self.data.extend_from_slice(data);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn half_floats() {
+        assert_eq!(half_to_f32(0x0000), 0.0);
+        assert!(half_to_f32(0x8000).is_sign_negative());
+        assert_eq!(half_to_f32(0x3c00), 1.0);
+        assert_eq!(half_to_f32(0xc000), -2.0);
+        assert_eq!(half_to_f32(0x3555), 0.33325195);
+        // The smallest and the largest subnormal
+        assert_eq!(half_to_f32(0x0001), 2.0f32.powi(-24));
+        assert_eq!(half_to_f32(0x03ff), 1023.0 * 2.0f32.powi(-24));
+        assert_eq!(half_to_f32(0x7bff), 65504.0);
+        assert_eq!(half_to_f32(0x7c00), f32::INFINITY);
+        assert_eq!(half_to_f32(0xfc00), f32::NEG_INFINITY);
+        assert!(half_to_f32(0x7e00).is_nan());
+        assert!(half_to_f32(0xfc01).is_nan());
+    }
+
+    fn read(element: VertexBufferElement, data: &[u8]) -> Vector4<f32> {
+        let vertex_buffer = SoftwareVertexBuffer::new(&[element], data).unwrap();
+        vertex_buffer.get_element(0, &vertex_buffer.get_attributes()[0])
+    }
+
+    #[test]
+    fn normalized_bytes() {
+        assert_eq!(
+            read(VertexBufferElement::UByte4Normalized, &[0, 51, 255, 255]),
+            (0.0, 0.2, 1.0, 1.0).into()
+        );
+        assert_eq!(
+            read(
+                VertexBufferElement::Byte4Normalized,
+                &[127, 0, (-127i8) as u8, (-128i8) as u8]
+            ),
+            (1.0, 0.0, -1.0, -1.0).into()
+        );
+    }
+
+    #[test]
+    fn normalized_shorts() {
+        let data: Vec<u8> = [i16::MAX, i16::MIN + 1, i16::MIN, 0]
+            .iter()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect();
+        assert_eq!(
+            read(VertexBufferElement::Short4Normalized, &data),
+            (1.0, -1.0, -1.0, 0.0).into()
+        );
+        let data: Vec<u8> = [u16::MAX, 0]
+            .iter()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect();
+        assert_eq!(
+            read(VertexBufferElement::UShort2Normalized, &data),
+            (1.0, 0.0, 0.0, 1.0).into()
+        );
+    }
+
+    #[test]
+    fn integers() {
+        let data: Vec<u8> = [-5i32, 1 << 24]
+            .iter()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect();
+        assert_eq!(
+            read(VertexBufferElement::Int2, &data),
+            (-5.0, 16777216.0, 0.0, 1.0).into()
+        );
+    }
+}
@@ -1,9 +1,10 @@
 use crate::{
     math::{Vector2, Vector3, Vector4},
+    renderer::RendererError,
     PhantomUnsend, PhantomUnsync,
 };
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct TextureID(
     pub(crate) usize,
     pub(crate) PhantomUnsend,
@@ -11,15 +12,201 @@ pub struct TextureID(
 );
 
 pub enum Pixels<'a> {
+    R(&'a [u8]),
+    RG(&'a [Vector2<u8>]),
     RGB(&'a [Vector3<u8>]),
     RGBA(&'a [Vector4<u8>]),
+    R16(&'a [u16]),
+    RG16(&'a [Vector2<u16>]),
+    RGB16(&'a [Vector3<u16>]),
+    RGBA16(&'a [Vector4<u16>]),
+    RF(&'a [f32]),
+    RGF(&'a [Vector2<f32>]),
     RGBF(&'a [Vector3<f32>]),
     RGBAF(&'a [Vector4<f32>]),
 }
 
+impl<'a> Pixels<'a> {
+    pub fn len(&self) -> usize {
+        match self {
+            Pixels::R(pixels) => pixels.len(),
+            Pixels::RG(pixels) => pixels.len(),
+            Pixels::RGB(pixels) => pixels.len(),
+            Pixels::RGBA(pixels) => pixels.len(),
+            Pixels::R16(pixels) => pixels.len(),
+            Pixels::RG16(pixels) => pixels.len(),
+            Pixels::RGB16(pixels) => pixels.len(),
+            Pixels::RGBA16(pixels) => pixels.len(),
+            Pixels::RF(pixels) => pixels.len(),
+            Pixels::RGF(pixels) => pixels.len(),
+            Pixels::RGBF(pixels) => pixels.len(),
+            Pixels::RGBAF(pixels) => pixels.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// How a texture stores its pixels, the integer formats are normalized to 0 to 1 when sampled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureFormat {
+    R8,
+    RG8,
+    RGB8,
+    RGBA8,
+    /// Stored in srgb and converted to linear when sampled
+    SRGB8Alpha8,
+    R16,
+    RG16,
+    RGB16,
+    RGBA16,
+    R16F,
+    RG16F,
+    RGB16F,
+    RGBA16F,
+    R32F,
+    RG32F,
+    RGB32F,
+    RGBA32F,
+    /// Sampled as `(depth, 0, 0, 1)`, and set from single channel `Pixels`
+    Depth16,
+    /// Sampled as `(depth, 0, 0, 1)`, and set from single channel `Pixels`
+    Depth24,
+    /// Sampled as `(depth, 0, 0, 1)`, and set from single channel `Pixels`
+    Depth32F,
+}
+
+impl TextureFormat {
+    /// The format that stores `pixels` without losing anything
+    pub fn from_pixels(pixels: &Pixels) -> TextureFormat {
+        match pixels {
+            Pixels::R(_) => TextureFormat::R8,
+            Pixels::RG(_) => TextureFormat::RG8,
+            Pixels::RGB(_) => TextureFormat::RGB8,
+            Pixels::RGBA(_) => TextureFormat::RGBA8,
+            Pixels::R16(_) => TextureFormat::R16,
+            Pixels::RG16(_) => TextureFormat::RG16,
+            Pixels::RGB16(_) => TextureFormat::RGB16,
+            Pixels::RGBA16(_) => TextureFormat::RGBA16,
+            Pixels::RF(_) => TextureFormat::R32F,
+            Pixels::RGF(_) => TextureFormat::RG32F,
+            Pixels::RGBF(_) => TextureFormat::RGB32F,
+            Pixels::RGBAF(_) => TextureFormat::RGBA32F,
+        }
+    }
+
+    pub fn is_depth(self) -> bool {
+        matches!(
+            self,
+            TextureFormat::Depth16 | TextureFormat::Depth24 | TextureFormat::Depth32F
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureFilter {
+    Nearest,
+    Linear,
+    /// Linear within and between mip levels, it is the same as `Linear` if the texture has no mipmaps
+    Trilinear,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextureWrap {
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SamplerDescription {
+    /// Used when the texture is drawn smaller than it is
+    pub min_filter: TextureFilter,
+    /// Used when the texture is drawn bigger than it is, `Trilinear` is the same as `Linear` here
+    pub mag_filter: TextureFilter,
+    pub wrap_u: TextureWrap,
+    pub wrap_v: TextureWrap,
+    /// Generates the mip chain every time the pixels are set
+    pub mipmaps: bool,
+    /// The most samples taken for anisotropic filtering, `1.0` turns it off, it is clamped to what
+    /// the api supports and ignored by `RendererAPI::Software`
+    pub max_anisotropy: f32,
+}
+
+impl Default for SamplerDescription {
+    fn default() -> Self {
+        Self {
+            min_filter: TextureFilter::Linear,
+            mag_filter: TextureFilter::Nearest,
+            wrap_u: TextureWrap::Repeat,
+            wrap_v: TextureWrap::Repeat,
+            mipmaps: false,
+            max_anisotropy: 1.0,
+        }
+    }
+}
+
 pub trait Texture {
     fn get_id(&self) -> TextureID;
     fn get_size(&self) -> Vector2<usize>;
-    fn set_pixels(&mut self, size: Vector2<usize>, pixels: Pixels);
+    fn get_format(&self) -> TextureFormat;
+    fn get_sampler(&self) -> SamplerDescription;
+    fn set_sampler(&mut self, sampler: SamplerDescription);
+    /// The pixels are converted to the format the texture was created with
+    fn set_pixels(&mut self, size: Vector2<usize>, pixels: Pixels) -> Result<(), RendererError>;
+    /// Replaces the `size` pixels starting at `offset` without resizing the texture, the region
+    /// has to be inside of `get_size`
+    fn set_sub_pixels(
+        &mut self,
+        offset: Vector2<usize>,
+        size: Vector2<usize>,
+        pixels: Pixels,
+    ) -> Result<(), RendererError>;
+    /// Returns the pixels the way they are stored, channels the format does not have are
+    /// `(0, 0, 0, 1)`
     fn get_pixels(&self) -> Vec<Vector4<f32>>;
+    /// Like `get_pixels` but only the `size` pixels starting at `offset`, the region has to be
+    /// inside of `get_size`
+    fn get_sub_pixels(
+        &self,
+        offset: Vector2<usize>,
+        size: Vector2<usize>,
+    ) -> Result<Vec<Vector4<f32>>, RendererError>;
+}
+
+pub(crate) fn check_region_in_bounds(
+    texture_size: Vector2<usize>,
+    offset: Vector2<usize>,
+    size: Vector2<usize>,
+) -> Result<(), RendererError> {
+    let inside = |offset: usize, size: usize, texture_size: usize| {
+        offset
+            .checked_add(size)
+            .is_some_and(|end| end <= texture_size)
+    };
+    if inside(offset.x, size.x, texture_size.x) && inside(offset.y, size.y, texture_size.y) {
+        Ok(())
+    } else {
+        Err(RendererError::RegionOutOfBounds {
+            offset,
+            size,
+            texture_size,
+        })
+    }
+}
+
+pub(crate) fn check_pixel_count(
+    size: Vector2<usize>,
+    pixels: &Pixels,
+) -> Result<(), RendererError> {
+    if size.x * size.y == pixels.len() {
+        Ok(())
+    } else {
+        Err(RendererError::PixelCountMismatch {
+            expected: size.x * size.y,
+            actual: pixels.len(),
+        })
+    }
 }
@@ -0,0 +1,362 @@
+use crate::{
+    math::{Matrix3x3, Matrix4x4, Vector4},
+    renderer::{RendererError, UniformValue},
+    PhantomUnsend, PhantomUnsync,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UniformBufferID(
+    pub(crate) usize,
+    pub(crate) PhantomUnsend,
+    pub(crate) PhantomUnsync,
+);
+
+pub trait UniformBuffer {
+    fn get_id(&self) -> UniformBufferID;
+    fn get_size(&self) -> usize;
+    fn set_data(&mut self, data: &[u8]);
+    /// Replaces the bytes starting at `offset` without resizing the buffer, they have to be inside
+    /// of `get_size`
+    fn set_sub_data(&mut self, offset: usize, data: &[u8]) -> Result<(), RendererError>;
+}
+
+pub(crate) fn check_range_in_bounds(
+    size: usize,
+    offset: usize,
+    length: usize,
+) -> Result<(), RendererError> {
+    if offset.checked_add(length).is_some_and(|end| end <= size) {
+        Ok(())
+    } else {
+        Err(RendererError::BufferRangeOutOfBounds {
+            offset,
+            length,
+            size,
+        })
+    }
+}
+
+/// Lays out values the way a `layout(std140)` uniform block expects them, the values have to be
+/// written in the order the block declares its members
+#[derive(Default)]
+pub struct Std140Writer {
+    bytes: Vec<u8>,
+}
+
+impl Std140Writer {
+    pub fn new() -> Std140Writer {
+        Std140Writer::default()
+    }
+
+    /// Writes `value` after the previous one and returns the offset it was written at, every
+    /// element of an array is padded to 16 bytes. Fails for textures, which can not be in a block
+    pub fn write(&mut self, value: UniformValue) -> Result<usize, RendererError> {
+        fn floats(values: &[f32]) -> Vec<u8> {
+            values
+                .iter()
+                .flat_map(|value| value.to_ne_bytes())
+                .collect()
+        }
+        fn ints(values: &[i32]) -> Vec<u8> {
+            values
+                .iter()
+                .flat_map(|value| value.to_ne_bytes())
+                .collect()
+        }
+        fn uints(values: &[u32]) -> Vec<u8> {
+            values
+                .iter()
+                .flat_map(|value| value.to_ne_bytes())
+                .collect()
+        }
+        fn vector4(value: Vector4<f32>) -> Vec<u8> {
+            floats(&[value.x, value.y, value.z, value.w])
+        }
+        // Each row of the matrix is a column in glsl, because matrices are uploaded untransposed
+        fn matrix4x4(value: &Matrix4x4<f32>) -> Vec<u8> {
+            (0..4).flat_map(|i| floats(&value[i])).collect()
+        }
+        fn matrix3x3_columns(value: &Matrix3x3<f32>) -> impl Iterator<Item = Vec<u8>> + '_ {
+            (0..3).map(|i| floats(&[value[i][0], value[i][1], value[i][2]]))
+        }
+
+        // Arrays and the columns of matrices have every element padded to 16 bytes
+        let (alignment, padded, elements): (usize, bool, Vec<Vec<u8>>) = match value {
+            UniformValue::Float(value) => (4, false, vec![floats(&[value])]),
+            UniformValue::Float2(value) => (8, false, vec![floats(&[value.x, value.y])]),
+            UniformValue::Float3(value) => (16, false, vec![floats(&[value.x, value.y, value.z])]),
+            UniformValue::Float4(value) => (16, false, vec![vector4(value)]),
+            UniformValue::Int(value) => (4, false, vec![ints(&[value])]),
+            UniformValue::Int2(value) => (8, false, vec![ints(&[value.x, value.y])]),
+            UniformValue::Int3(value) => (16, false, vec![ints(&[value.x, value.y, value.z])]),
+            UniformValue::Int4(value) => {
+                (16, false, vec![ints(&[value.x, value.y, value.z, value.w])])
+            }
+            UniformValue::UInt(value) => (4, false, vec![uints(&[value])]),
+            UniformValue::UInt2(value) => (8, false, vec![uints(&[value.x, value.y])]),
+            UniformValue::UInt3(value) => (16, false, vec![uints(&[value.x, value.y, value.z])]),
+            UniformValue::UInt4(value) => (
+                16,
+                false,
+                vec![uints(&[value.x, value.y, value.z, value.w])],
+            ),
+            UniformValue::Matrix3x3(value) => (16, true, matrix3x3_columns(&value).collect()),
+            UniformValue::Matrix4x4(value) => (16, true, vec![matrix4x4(&value)]),
+            UniformValue::FloatArray(values) => (
+                16,
+                true,
+                values.iter().map(|&value| floats(&[value])).collect(),
+            ),
+            UniformValue::Float2Array(values) => (
+                16,
+                true,
+                values
+                    .iter()
+                    .map(|value| floats(&[value.x, value.y]))
+                    .collect(),
+            ),
+            UniformValue::Float3Array(values) => (
+                16,
+                true,
+                values
+                    .iter()
+                    .map(|value| floats(&[value.x, value.y, value.z]))
+                    .collect(),
+            ),
+            UniformValue::Float4Array(values) => (
+                16,
+                true,
+                values.iter().map(|&value| vector4(value)).collect(),
+            ),
+            UniformValue::IntArray(values) => (
+                16,
+                true,
+                values.iter().map(|&value| ints(&[value])).collect(),
+            ),
+            UniformValue::Int2Array(values) => (
+                16,
+                true,
+                values
+                    .iter()
+                    .map(|value| ints(&[value.x, value.y]))
+                    .collect(),
+            ),
+            UniformValue::Int3Array(values) => (
+                16,
+                true,
+                values
+                    .iter()
+                    .map(|value| ints(&[value.x, value.y, value.z]))
+                    .collect(),
+            ),
+            UniformValue::Int4Array(values) => (
+                16,
+                true,
+                values
+                    .iter()
+                    .map(|value| ints(&[value.x, value.y, value.z, value.w]))
+                    .collect(),
+            ),
+            UniformValue::UIntArray(values) => (
+                16,
+                true,
+                values.iter().map(|&value| uints(&[value])).collect(),
+            ),
+            UniformValue::UInt2Array(values) => (
+                16,
+                true,
+                values
+                    .iter()
+                    .map(|value| uints(&[value.x, value.y]))
+                    .collect(),
+            ),
+            UniformValue::UInt3Array(values) => (
+                16,
+                true,
+                values
+                    .iter()
+                    .map(|value| uints(&[value.x, value.y, value.z]))
+                    .collect(),
+            ),
+            UniformValue::UInt4Array(values) => (
+                16,
+                true,
+                values
+                    .iter()
+                    .map(|value| uints(&[value.x, value.y, value.z, value.w]))
+                    .collect(),
+            ),
+            UniformValue::Matrix3x3Array(values) => (
+                16,
+                true,
+                values.iter().flat_map(matrix3x3_columns).collect(),
+            ),
+            UniformValue::Matrix4x4Array(values) => {
+                (16, true, values.iter().map(matrix4x4).collect())
+            }
+            UniformValue::Texture(_) => return Err(RendererError::TextureInUniformBlock),
+        };
+
+        self.pad_to(alignment);
+        let offset = self.bytes.len();
+        for element in elements {
+            self.bytes.extend(element);
+            if padded {
+                self.pad_to(16);
+            }
+        }
+        Ok(offset)
+    }
+
+    /// Pads to the alignment of a struct, which has to be done before and after writing the
+    /// members of a struct
+    pub fn align_struct(&mut self) {
+        self.pad_to(16);
+    }
+
+    pub fn get_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    fn pad_to(&mut self, alignment: usize) {
+        self.bytes
+            .resize(self.bytes.len().next_multiple_of(alignment), 0);
+    }
+}
+
+/// The contents of the builtin `Camera` uniform block
+pub(crate) fn get_camera_block_data(
+    projection_matrix: Matrix4x4<f32>,
+    view_matrix: Matrix4x4<f32>,
+) -> Vec<u8> {
+    let mut writer = Std140Writer::new();
+    for matrix in [projection_matrix, view_matrix] {
+        writer
+            .write(UniformValue::Matrix4x4(matrix))
+            .expect("Matrices can be in a uniform block");
+    }
+    writer.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::TextureID;
+
+    fn floats_at(bytes: &[u8], offset: usize, count: usize) -> Vec<f32> {
+        bytes[offset..offset + count * 4]
+            .chunks_exact(4)
+            .map(|float| f32::from_ne_bytes(float.try_into().unwrap()))
+            .collect()
+    }
+
+    #[test]
+    fn scalar_and_vector_alignment() {
+        let mut writer = Std140Writer::new();
+        assert_eq!(writer.write(UniformValue::Float(1.0)).unwrap(), 0);
+        assert_eq!(
+            writer
+                .write(UniformValue::Float2((2.0, 3.0).into()))
+                .unwrap(),
+            8
+        );
+        assert_eq!(writer.write(UniformValue::Float(4.0)).unwrap(), 16);
+        // A vec3 is aligned like a vec4, but a scalar fits in after it
+        assert_eq!(
+            writer
+                .write(UniformValue::Float3((5.0, 6.0, 7.0).into()))
+                .unwrap(),
+            32
+        );
+        assert_eq!(writer.write(UniformValue::Int(8)).unwrap(), 44);
+        assert_eq!(writer.get_bytes().len(), 48);
+        assert_eq!(floats_at(writer.get_bytes(), 0, 3), [1.0, 0.0, 2.0]);
+        assert_eq!(floats_at(writer.get_bytes(), 32, 3), [5.0, 6.0, 7.0]);
+    }
+
+    #[test]
+    fn matrix_columns_are_padded() {
+        let mut matrix = Matrix3x3::identity();
+        matrix[1][2] = 5.0;
+        let mut writer = Std140Writer::new();
+        assert_eq!(writer.write(UniformValue::Float(1.0)).unwrap(), 0);
+        assert_eq!(writer.write(UniformValue::Matrix3x3(matrix)).unwrap(), 16);
+        assert_eq!(writer.write(UniformValue::Float(2.0)).unwrap(), 64);
+        let bytes = writer.get_bytes();
+        assert_eq!(floats_at(bytes, 16, 3), [1.0, 0.0, 0.0]);
+        assert_eq!(floats_at(bytes, 32, 3), [0.0, 1.0, 5.0]);
+        assert_eq!(floats_at(bytes, 48, 3), [0.0, 0.0, 1.0]);
+    }
+
+    #[test]
+    fn matrix_array_columns_are_padded() {
+        let mut second = Matrix3x3::identity();
+        second[0][1] = 3.0;
+        let mut writer = Std140Writer::new();
+        assert_eq!(
+            writer
+                .write(UniformValue::Matrix3x3Array(&[
+                    Matrix3x3::identity(),
+                    second
+                ]))
+                .unwrap(),
+            0
+        );
+        assert_eq!(writer.write(UniformValue::UInt(2)).unwrap(), 96);
+        let bytes = writer.get_bytes();
+        assert_eq!(floats_at(bytes, 32, 3), [0.0, 0.0, 1.0]);
+        assert_eq!(floats_at(bytes, 48, 3), [1.0, 3.0, 0.0]);
+    }
+
+    #[test]
+    fn array_stride_is_16_bytes() {
+        let mut writer = Std140Writer::new();
+        assert_eq!(writer.write(UniformValue::Float(1.0)).unwrap(), 0);
+        assert_eq!(
+            writer
+                .write(UniformValue::FloatArray(&[2.0, 3.0, 4.0]))
+                .unwrap(),
+            16
+        );
+        assert_eq!(
+            writer
+                .write(UniformValue::Float3Array(&[(5.0, 6.0, 7.0).into(); 2]))
+                .unwrap(),
+            64
+        );
+        assert_eq!(writer.get_bytes().len(), 96);
+        let bytes = writer.get_bytes();
+        assert_eq!(floats_at(bytes, 16, 1), [2.0]);
+        assert_eq!(floats_at(bytes, 32, 1), [3.0]);
+        assert_eq!(floats_at(bytes, 48, 1), [4.0]);
+        assert_eq!(floats_at(bytes, 80, 3), [5.0, 6.0, 7.0]);
+    }
+
+    #[test]
+    fn struct_alignment() {
+        let mut writer = Std140Writer::new();
+        writer.write(UniformValue::Float(1.0)).unwrap();
+        writer.align_struct();
+        assert_eq!(writer.write(UniformValue::Float(2.0)).unwrap(), 16);
+    }
+
+    #[test]
+    fn textures_are_rejected() {
+        let texture = TextureID(1, std::marker::PhantomData, std::marker::PhantomData);
+        assert!(matches!(
+            Std140Writer::new().write(UniformValue::Texture(texture)),
+            Err(RendererError::TextureInUniformBlock)
+        ));
+    }
+
+    #[test]
+    fn range_past_the_end() {
+        assert!(check_range_in_bounds(16, 8, 8).is_ok());
+        assert!(check_range_in_bounds(16, 8, 9).is_err());
+        assert!(check_range_in_bounds(16, usize::MAX, 2).is_err());
+    }
+}
@@ -1,22 +1,275 @@
-use crate::{PhantomUnsend, PhantomUnsync};
+use crate::{renderer::RendererError, PhantomUnsend, PhantomUnsync};
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct VertexBufferID(
     pub(crate) usize,
     pub(crate) PhantomUnsend,
     pub(crate) PhantomUnsync,
 );
 
+/// An attribute of the vertices in a vertex buffer, the elements of a layout are tightly packed in
+/// the order they are listed and read by the shader input at the same location
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum VertexBufferElement {
     Float,
     Float2,
     Float3,
     Float4,
+    /// 16 bit floats, read by float inputs
+    Half,
+    Half2,
+    Half3,
+    Half4,
+    /// 32 bit integers, read by int inputs without being converted. Software shaders get every
+    /// input as floats, which only hold integers up to `2^24` exactly
+    Int,
+    Int2,
+    Int3,
+    Int4,
+    /// 32 bit unsigned integers, read by uint inputs without being converted, except in software
+    /// shaders like `Int`
+    UInt,
+    UInt2,
+    UInt3,
+    UInt4,
+    /// Read by float inputs as `value / 255`, like a packed RGBA8 color
+    UByte4Normalized,
+    /// Read by float inputs as `value / 127` clamped to `-1`
+    Byte4Normalized,
+    /// Read by float inputs as `value / 65535`
+    UShort2Normalized,
+    UShort4Normalized,
+    /// Read by float inputs as `value / 32767` clamped to `-1`, like a compressed normal
+    Short2Normalized,
+    Short4Normalized,
+    /// Takes up no space, the next element is at `location` instead of the one after the
+    /// previous element, like `layout(location = n)` in glsl. Locations can only go up and there
+    /// are `MAX_VERTEX_LOCATIONS` of them
+    Location(usize),
+}
+
+/// How the components of a `VertexBufferElement` are stored and what the shader reads them as
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum VertexComponentType {
+    Float,
+    Half,
+    Int,
+    UInt,
+    UByteNormalized,
+    ByteNormalized,
+    UShortNormalized,
+    ShortNormalized,
+}
+
+impl VertexComponentType {
+    pub(crate) fn get_size(&self) -> usize {
+        match self {
+            VertexComponentType::Float | VertexComponentType::Int | VertexComponentType::UInt => 4,
+            VertexComponentType::Half
+            | VertexComponentType::UShortNormalized
+            | VertexComponentType::ShortNormalized => 2,
+            VertexComponentType::UByteNormalized | VertexComponentType::ByteNormalized => 1,
+        }
+    }
+
+    /// Integer components are read by int and uint inputs, everything else by float inputs
+    pub(crate) fn is_integer(&self) -> bool {
+        matches!(self, VertexComponentType::Int | VertexComponentType::UInt)
+    }
+}
+
+impl VertexBufferElement {
+    /// The type and number of components, `None` for `Location`
+    pub(crate) fn get_components(&self) -> Option<(VertexComponentType, usize)> {
+        use VertexComponentType as Type;
+        Some(match self {
+            VertexBufferElement::Float => (Type::Float, 1),
+            VertexBufferElement::Float2 => (Type::Float, 2),
+            VertexBufferElement::Float3 => (Type::Float, 3),
+            VertexBufferElement::Float4 => (Type::Float, 4),
+            VertexBufferElement::Half => (Type::Half, 1),
+            VertexBufferElement::Half2 => (Type::Half, 2),
+            VertexBufferElement::Half3 => (Type::Half, 3),
+            VertexBufferElement::Half4 => (Type::Half, 4),
+            VertexBufferElement::Int => (Type::Int, 1),
+            VertexBufferElement::Int2 => (Type::Int, 2),
+            VertexBufferElement::Int3 => (Type::Int, 3),
+            VertexBufferElement::Int4 => (Type::Int, 4),
+            VertexBufferElement::UInt => (Type::UInt, 1),
+            VertexBufferElement::UInt2 => (Type::UInt, 2),
+            VertexBufferElement::UInt3 => (Type::UInt, 3),
+            VertexBufferElement::UInt4 => (Type::UInt, 4),
+            VertexBufferElement::UByte4Normalized => (Type::UByteNormalized, 4),
+            VertexBufferElement::Byte4Normalized => (Type::ByteNormalized, 4),
+            VertexBufferElement::UShort2Normalized => (Type::UShortNormalized, 2),
+            VertexBufferElement::UShort4Normalized => (Type::UShortNormalized, 4),
+            VertexBufferElement::Short2Normalized => (Type::ShortNormalized, 2),
+            VertexBufferElement::Short4Normalized => (Type::ShortNormalized, 4),
+            VertexBufferElement::Location(_) => return None,
+        })
+    }
+
+    /// The size in bytes
+    pub(crate) fn get_size(&self) -> usize {
+        self.get_components()
+            .map_or(0, |(typ, count)| typ.get_size() * count)
+    }
+}
+
+/// The number of locations a layout can use, the least that opengl guarantees
+pub const MAX_VERTEX_LOCATIONS: usize = 16;
+
+/// An element of a layout that is not a `Location`, with where it is in the vertex
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct VertexAttribute {
+    pub(crate) element: VertexBufferElement,
+    pub(crate) location: usize,
+    /// In bytes from the start of the vertex
+    pub(crate) offset: usize,
+}
+
+/// The attributes of a layout and its stride
+pub(crate) fn get_vertex_attributes(
+    layout: &[VertexBufferElement],
+) -> Result<(Vec<VertexAttribute>, usize), RendererError> {
+    let mut attributes = Vec::with_capacity(layout.len());
+    let mut location = 0;
+    let mut offset = 0;
+    for element in layout {
+        match element {
+            VertexBufferElement::Location(next) if *next < location => {
+                return Err(RendererError::InvalidVertexLayout(format!(
+                    "Location {next} is below location {location} where the next element would be"
+                )));
+            }
+            VertexBufferElement::Location(next) => location = *next,
+            element => {
+                if location >= MAX_VERTEX_LOCATIONS {
+                    return Err(RendererError::InvalidVertexLayout(format!(
+                        "{element:?} is at location {location} but there are only {MAX_VERTEX_LOCATIONS} locations"
+                    )));
+                }
+                attributes.push(VertexAttribute {
+                    element: *element,
+                    location,
+                    offset,
+                });
+                location += 1;
+                offset += element.get_size();
+            }
+        }
+    }
+    Ok((attributes, offset))
 }
 
 pub trait VertexBuffer {
     fn get_id(&self) -> VertexBufferID;
     fn get_count(&self) -> usize;
-    fn set_layout(&mut self, layout: &[VertexBufferElement], data: &[u8]);
-    fn set_data(&mut self, data: &[u8]);
+    fn get_layout(&self) -> &[VertexBufferElement];
+    fn set_layout(
+        &mut self,
+        layout: &[VertexBufferElement],
+        data: &[u8],
+    ) -> Result<(), RendererError>;
+    fn set_data(&mut self, data: &[u8]) -> Result<(), RendererError>;
+}
+
+/// The number of vertices in `data`, which has to be a whole number of vertices
+pub(crate) fn get_vertex_count(stride: usize, data: &[u8]) -> Result<usize, RendererError> {
+    match (stride, data.len()) {
+        (0, 0) => Ok(0),
+        (0, length) => Err(RendererError::VertexDataSizeMismatch { stride, length }),
+        (stride, length) if length.is_multiple_of(stride) => Ok(length / stride),
+        (stride, length) => Err(RendererError::VertexDataSizeMismatch { stride, length }),
+    }
+}
+
+/// Checks that the instance buffer has a vertex for every instance and that its layout does not
+/// use any location of the vertex buffer layout
+pub(crate) fn check_instance_buffer(
+    layout: &[VertexBufferElement],
+    instance_layout: &[VertexBufferElement],
+    instance_buffer_count: usize,
+    instance_count: usize,
+) -> Result<(), RendererError> {
+    if instance_count > instance_buffer_count {
+        return Err(RendererError::InstanceCountOutOfBounds {
+            count: instance_count,
+            available: instance_buffer_count,
+        });
+    }
+    let (attributes, _) = get_vertex_attributes(layout)?;
+    let (instance_attributes, _) = get_vertex_attributes(instance_layout)?;
+    if let Some(attribute) = instance_attributes.iter().find(|instance_attribute| {
+        attributes
+            .iter()
+            .any(|attribute| attribute.location == instance_attribute.location)
+    }) {
+        return Err(RendererError::InvalidVertexLayout(format!(
+            "Location {} is used by both the vertex buffer and the instance buffer",
+            attribute.location
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locations_and_offsets() {
+        let (attributes, stride) = get_vertex_attributes(&[
+            VertexBufferElement::Float3,
+            VertexBufferElement::Location(4),
+            VertexBufferElement::UByte4Normalized,
+            VertexBufferElement::Half2,
+        ])
+        .unwrap();
+        let layout: Vec<_> = attributes
+            .iter()
+            .map(|attribute| (attribute.location, attribute.offset))
+            .collect();
+        assert_eq!(layout, [(0, 0), (4, 12), (5, 16)]);
+        assert_eq!(stride, 20);
+    }
+
+    #[test]
+    fn decreasing_location() {
+        assert!(matches!(
+            get_vertex_attributes(&[
+                VertexBufferElement::Float,
+                VertexBufferElement::Float,
+                VertexBufferElement::Location(1),
+            ]),
+            Err(RendererError::InvalidVertexLayout(_))
+        ));
+        // Going back to the same location that the next element would be at is fine
+        assert!(get_vertex_attributes(&[
+            VertexBufferElement::Float,
+            VertexBufferElement::Location(1),
+            VertexBufferElement::Float,
+        ])
+        .is_ok());
+    }
+
+    #[test]
+    fn too_many_locations() {
+        assert!(get_vertex_attributes(&[
+            VertexBufferElement::Location(MAX_VERTEX_LOCATIONS - 1),
+            VertexBufferElement::Float,
+        ])
+        .is_ok());
+        assert!(matches!(
+            get_vertex_attributes(&[
+                VertexBufferElement::Location(MAX_VERTEX_LOCATIONS),
+                VertexBufferElement::Float,
+            ]),
+            Err(RendererError::InvalidVertexLayout(_))
+        ));
+        assert!(matches!(
+            get_vertex_attributes(&[VertexBufferElement::Float; MAX_VERTEX_LOCATIONS + 1]),
+            Err(RendererError::InvalidVertexLayout(_))
+        ));
+    }
 }
@@ -3,7 +3,9 @@ use crate::{
     scene::Transform,
 };
 
+#[derive(Default)]
 pub enum CameraProjectionType<T> {
+    #[default]
     None,
     Orthographic {
         left: T,
@@ -19,9 +21,124 @@ pub enum CameraProjectionType<T> {
         near: T,
         far: T,
     },
+    /// A perspective without a far plane, with a reversed `DepthConvention` the depth gets close to
+    /// `0` far away but never reaches it
+    InfinitePerspective {
+        fov: T,
+        aspect: T,
+        near: T,
+    },
+}
+
+/// The range clip space depth goes over before it is divided by `w`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DepthRange {
+    /// From `-w` to `w` like opengl has by default, the depth that is stored is `z / w * 0.5 + 0.5`
+    NegativeOneToOne,
+    /// From `0` to `w`, the depth that is stored is `z / w`. Opengl needs clip control for it
+    ZeroToOne,
+}
+
+/// Where projections put the near and far planes in depth, set on the renderer with
+/// `Renderer::set_depth_convention` so it builds the projection of the camera the same way
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DepthConvention {
+    /// The near plane gets the biggest depth and the far plane the smallest, together with
+    /// `DepthRange::ZeroToOne` and a float depth buffer this keeps precision far away
+    pub reversed: bool,
+    pub range: DepthRange,
 }
 
-impl<T> Into<Matrix4x4<T>> for CameraProjectionType<T>
+impl DepthConvention {
+    /// What opengl does by default, nearer is smaller
+    pub const STANDARD: DepthConvention = DepthConvention {
+        reversed: false,
+        range: DepthRange::NegativeOneToOne,
+    };
+    pub const REVERSED_Z: DepthConvention = DepthConvention {
+        reversed: true,
+        range: DepthRange::ZeroToOne,
+    };
+
+    /// The stored depth of the near plane
+    pub fn get_near_depth(&self) -> f32 {
+        if self.reversed {
+            1.0
+        } else {
+            0.0
+        }
+    }
+
+    /// The stored depth of the far plane, which is what the depth buffer gets cleared to
+    pub fn get_far_depth(&self) -> f32 {
+        if self.reversed {
+            0.0
+        } else {
+            1.0
+        }
+    }
+
+    /// Clip space depth divided by `w` at the near and far plane
+    fn get_clip_depths<T: Zero + One + std::ops::Neg<Output = T>>(&self) -> (T, T) {
+        let (near, far) = match self.range {
+            DepthRange::NegativeOneToOne => (-T::one(), T::one()),
+            DepthRange::ZeroToOne => (T::zero(), T::one()),
+        };
+        if self.reversed {
+            (far, near)
+        } else {
+            (near, far)
+        }
+    }
+}
+
+impl Default for DepthConvention {
+    /// Reversed depth from `-1` to `1`, which needs no clip control
+    fn default() -> Self {
+        Self {
+            reversed: true,
+            range: DepthRange::NegativeOneToOne,
+        }
+    }
+}
+
+impl<T> CameraProjectionType<T>
+where
+    T: Clone
+        + Two
+        + std::ops::Add<T, Output = T>
+        + std::ops::Sub<T, Output = T>
+        + std::ops::Mul<T, Output = T>
+        + std::ops::Div<T, Output = T>,
+{
+    /// Makes the projection fit a viewport with the aspect ratio `aspect`, like the one from
+    /// `Rect::aspect_ratio`. An orthographic projection keeps its height and center
+    pub fn set_aspect(&mut self, aspect: T) {
+        match self {
+            CameraProjectionType::None => {}
+            CameraProjectionType::Orthographic {
+                left,
+                right,
+                top,
+                bottom,
+                ..
+            } => {
+                let center = (left.clone() + right.clone()) / T::two();
+                let half_width = aspect * (top.clone() - bottom.clone()) / T::two();
+                *left = center.clone() - half_width.clone();
+                *right = center + half_width;
+            }
+            CameraProjectionType::Perspective {
+                aspect: current, ..
+            }
+            | CameraProjectionType::InfinitePerspective {
+                aspect: current, ..
+            } => *current = aspect,
+        }
+    }
+}
+
+impl<T> CameraProjectionType<T>
 where
     T: Clone
         + Zero
@@ -36,8 +153,11 @@ where
         + std::ops::Div<T, Output = T>
         + std::ops::Neg<Output = T>,
 {
-    fn into(self) -> Matrix4x4<T> {
-        match self {
+    /// The projection matrix that puts the near and far planes where `depth_convention` says,
+    /// the renderer uses the convention from `Renderer::get_depth_convention`
+    pub fn to_matrix(&self, depth_convention: DepthConvention) -> Matrix4x4<T> {
+        let (near_depth, far_depth): (T, T) = depth_convention.get_clip_depths();
+        match self.clone() {
             CameraProjectionType::None => Matrix4x4::identity(),
             CameraProjectionType::Orthographic {
                 left,
@@ -62,13 +182,13 @@ where
                 [
                     T::zero(),
                     T::zero(),
-                    T::two() / (far.clone() - near.clone()),
+                    (far_depth.clone() - near_depth.clone()) / (far.clone() - near.clone()),
                     T::zero(),
                 ],
                 [
                     -(right.clone() + left.clone()) / (right - left),
                     -(top.clone() + bottom.clone()) / (top - bottom),
-                    -(far.clone() + near.clone()) / (far - near),
+                    (near_depth * far.clone() - far_depth * near.clone()) / (far - near),
                     T::one(),
                 ],
             ]),
@@ -77,31 +197,74 @@ where
                 aspect,
                 near,
                 far,
-            } => {
-                let fov_radians = fov.to_radians();
-                let tan_fov_over_2 = (fov_radians / T::two()).tan();
-                Matrix4x4::new([
-                    [
-                        (aspect * tan_fov_over_2.clone()).recip(),
-                        T::zero(),
-                        T::zero(),
-                        T::zero(),
-                    ],
-                    [T::zero(), tan_fov_over_2.recip(), T::zero(), T::zero()],
-                    [
-                        T::zero(),
-                        T::zero(),
-                        -(far.clone() + near.clone()) / (far.clone() - near.clone()),
-                        (T::two() * far.clone() * near.clone()) / (far - near),
-                    ],
-                    [T::zero(), T::zero(), T::one(), T::zero()],
-                ])
-                .transpose()
-            }
+            } => perspective(
+                fov,
+                aspect,
+                (far_depth.clone() * far.clone() - near_depth.clone() * near.clone())
+                    / (far.clone() - near.clone()),
+                (near_depth - far_depth) * near.clone() * far.clone() / (far - near),
+            ),
+            CameraProjectionType::InfinitePerspective { fov, aspect, near } => perspective(
+                fov,
+                aspect,
+                far_depth.clone(),
+                (near_depth - far_depth) * near,
+            ),
         }
     }
 }
 
+/// A perspective projection where clip space depth is `depth_scale * z + depth_offset`, and `w`
+/// is `z`
+fn perspective<T>(fov: T, aspect: T, depth_scale: T, depth_offset: T) -> Matrix4x4<T>
+where
+    T: Clone
+        + Zero
+        + One
+        + Two
+        + ToRadians
+        + Tan
+        + Recip
+        + std::ops::Mul<T, Output = T>
+        + std::ops::Div<T, Output = T>,
+{
+    let fov_radians = fov.to_radians();
+    let tan_fov_over_2 = (fov_radians / T::two()).tan();
+    Matrix4x4::new([
+        [
+            (aspect * tan_fov_over_2.clone()).recip(),
+            T::zero(),
+            T::zero(),
+            T::zero(),
+        ],
+        [T::zero(), tan_fov_over_2.recip(), T::zero(), T::zero()],
+        [T::zero(), T::zero(), depth_scale, depth_offset],
+        [T::zero(), T::zero(), T::one(), T::zero()],
+    ])
+    .transpose()
+}
+
+impl<T> From<CameraProjectionType<T>> for Matrix4x4<T>
+where
+    T: Clone
+        + Zero
+        + One
+        + Two
+        + ToRadians
+        + Tan
+        + Recip
+        + std::ops::Add<T, Output = T>
+        + std::ops::Sub<T, Output = T>
+        + std::ops::Mul<T, Output = T>
+        + std::ops::Div<T, Output = T>
+        + std::ops::Neg<Output = T>,
+{
+    /// Uses `DepthConvention::default()`
+    fn from(projection_type: CameraProjectionType<T>) -> Self {
+        projection_type.to_matrix(DepthConvention::default())
+    }
+}
+
 impl<T> Clone for CameraProjectionType<T>
 where
     T: Clone,
@@ -135,18 +298,17 @@ where
                 near: near.clone(),
                 far: far.clone(),
             },
+            Self::InfinitePerspective { fov, aspect, near } => Self::InfinitePerspective {
+                fov: fov.clone(),
+                aspect: aspect.clone(),
+                near: near.clone(),
+            },
         }
     }
 }
 
 impl<T> Copy for CameraProjectionType<T> where T: Copy {}
 
-impl<T> Default for CameraProjectionType<T> {
-    fn default() -> Self {
-        CameraProjectionType::None
-    }
-}
-
 pub struct Camera<T> {
     pub transform: Transform<T>,
     pub projection_type: CameraProjectionType<T>,
@@ -177,3 +339,136 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::math::Vector4;
+
+    const CONVENTIONS: [DepthConvention; 3] = [
+        DepthConvention::STANDARD,
+        DepthConvention::REVERSED_Z,
+        DepthConvention {
+            reversed: true,
+            range: DepthRange::NegativeOneToOne,
+        },
+    ];
+
+    /// The depth that gets stored for a point at distance `z` in front of the camera
+    fn stored_depth(
+        projection_type: CameraProjectionType<f32>,
+        depth_convention: DepthConvention,
+        z: f32,
+    ) -> f32 {
+        let clip = projection_type.to_matrix(depth_convention) * Vector4::new(0.0, 0.0, z, 1.0);
+        let depth = clip.z / clip.w;
+        match depth_convention.range {
+            DepthRange::NegativeOneToOne => depth * 0.5 + 0.5,
+            DepthRange::ZeroToOne => depth,
+        }
+    }
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-4, "{a} != {b}");
+    }
+
+    #[test]
+    fn default_is_reversed_negative_one_to_one() {
+        assert_eq!(DepthConvention::default(), CONVENTIONS[2]);
+    }
+
+    #[test]
+    fn perspective_near_and_far() {
+        let projection_type = CameraProjectionType::Perspective {
+            fov: 60.0,
+            aspect: 1.5,
+            near: 0.1,
+            far: 100.0,
+        };
+        for depth_convention in CONVENTIONS {
+            assert_close(
+                stored_depth(projection_type, depth_convention, 0.1),
+                depth_convention.get_near_depth(),
+            );
+            assert_close(
+                stored_depth(projection_type, depth_convention, 100.0),
+                depth_convention.get_far_depth(),
+            );
+        }
+    }
+
+    #[test]
+    fn infinite_perspective_near_and_far() {
+        let projection_type = CameraProjectionType::InfinitePerspective {
+            fov: 60.0,
+            aspect: 1.5,
+            near: 0.1,
+        };
+        for depth_convention in CONVENTIONS {
+            assert_close(
+                stored_depth(projection_type, depth_convention, 0.1),
+                depth_convention.get_near_depth(),
+            );
+            // The far plane is only reached in the limit
+            let far_depth = stored_depth(projection_type, depth_convention, 1e7);
+            assert_close(far_depth, depth_convention.get_far_depth());
+            assert_ne!(far_depth, depth_convention.get_near_depth());
+        }
+    }
+
+    #[test]
+    fn orthographic_near_and_far() {
+        let projection_type = CameraProjectionType::Orthographic {
+            left: -2.0,
+            right: 2.0,
+            top: 1.0,
+            bottom: -1.0,
+            near: 0.5,
+            far: 20.0,
+        };
+        for depth_convention in CONVENTIONS {
+            assert_close(
+                stored_depth(projection_type, depth_convention, 0.5),
+                depth_convention.get_near_depth(),
+            );
+            assert_close(
+                stored_depth(projection_type, depth_convention, 20.0),
+                depth_convention.get_far_depth(),
+            );
+        }
+    }
+
+    #[test]
+    fn default_perspective_is_unchanged() {
+        // What a perspective projection was before depth conventions, near is `1` and far is `-1`
+        let (near, far) = (1.0, 3.0);
+        let old = Matrix4x4::new([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [
+                0.0,
+                0.0,
+                -(far + near) / (far - near),
+                2.0 * far * near / (far - near),
+            ],
+            [0.0, 0.0, 1.0, 0.0],
+        ])
+        .transpose();
+        let projection_type = CameraProjectionType::Perspective {
+            fov: 90.0,
+            aspect: 1.0,
+            near,
+            far,
+        };
+        for matrix in [
+            projection_type.to_matrix(DepthConvention::default()),
+            projection_type.into(),
+        ] {
+            for row in 0..4 {
+                for column in 0..4 {
+                    assert_close(matrix[row][column], old[row][column]);
+                }
+            }
+        }
+    }
+}
@@ -70,7 +70,7 @@ impl<T> Transform<T> {
     }
 }
 
-impl<T> Into<Matrix4x4<T>> for Transform<T>
+impl<T> From<Transform<T>> for Matrix4x4<T>
 where
     T: Clone
         + Zero
@@ -82,11 +82,11 @@ where
         + std::ops::Add<T, Output = T>
         + std::ops::Neg<Output = T>,
 {
-    fn into(self) -> Matrix4x4<T> {
+    fn from(transform: Transform<T>) -> Self {
         // TODO: rotation
-        Matrix4x4::scale(self.scale)
-            * Matrix4x4::rotation(self.rotation)
-            * Matrix4x4::translation(self.position)
+        Matrix4x4::scale(transform.scale)
+            * Matrix4x4::rotation(transform.rotation)
+            * Matrix4x4::translation(transform.position)
     }
 }
 
@@ -0,0 +1,1923 @@
+#![cfg(target_os = "linux")]
+
+use thallium::{math::*, platform::*, renderer::*, scene::*, *};
+
+const SIZE: (usize, usize) = (8, 4);
+const CLEAR_COLOR: (f32, f32, f32, f32) = (0.0, 0.0, 1.0, 1.0);
+const DRAW_COLOR: (f32, f32, f32, f32) = (1.0, 0.0, 0.0, 1.0);
+
+fn create_renderer(api: RendererAPI) -> Box<dyn Renderer> {
+    Surface::new_headless(SIZE.into())
+        .into_renderer(api)
+        .unwrap()
+}
+
+fn create_shader(renderer: &mut Box<dyn Renderer>, vertex: SoftwareVertexShader) -> ShaderID {
+    renderer
+        .create_shader(ShaderDescription {
+            inputs: &[ShaderInput {
+                name: "a_Position",
+                typ: ShaderDataType::Float2,
+            }],
+            uniforms: &[],
+            uniform_blocks: &[],
+            glsl: Some(GlslShaderSource {
+                vertex: r"#version 330 core
+
+in vec2 a_Position;
+
+void main() {
+    gl_Position = vec4(a_Position, 0.0, 1.0);
+}
+",
+                fragment: r"#version 330 core
+
+out vec4 o_Color;
+
+void main() {
+    o_Color = vec4(1.0, 0.0, 0.0, 1.0);
+}
+",
+                preprocessor: GlslPreprocessor::default(),
+            }),
+            software: Some(SoftwareShaderSource {
+                vertex,
+                fragment: |_varyings, _uniforms, outputs| outputs[0] = DRAW_COLOR.into(),
+            }),
+        })
+        .unwrap()
+}
+
+fn draw_indexed(
+    renderer: &mut Box<dyn Renderer>,
+    typ: PrimitiveType,
+    shader: ShaderID,
+    vertex_buffer: VertexBufferID,
+    index_buffer: IndexBufferID,
+) -> Result<(), RendererError> {
+    let mut draw_context = renderer
+        .drawing_context(
+            None,
+            None,
+            None,
+            Camera::default(),
+            PipelineState::default(),
+            CullFace::None,
+        )
+        .unwrap();
+    draw_context.draw_indexed(
+        typ,
+        shader,
+        vertex_buffer,
+        index_buffer,
+        None,
+        Matrix4x4::identity(),
+        (1.0, 1.0, 1.0).into(),
+    )
+}
+
+fn draw_left_half(api: RendererAPI) -> Image {
+    let mut renderer = create_renderer(api);
+    let shader = create_shader(&mut renderer, |inputs, _uniforms, _varyings| inputs[0]);
+
+    let vertices: &[f32] = &[-1.0, -1.0, 0.0, -1.0, 0.0, 1.0, -1.0, 1.0];
+    let vertex_buffer = renderer
+        .create_vertex_buffer(&[VertexBufferElement::Float2], slice_to_bytes(vertices))
+        .unwrap();
+    let index_buffer = renderer.create_index_buffer(&[0, 1, 2, 0, 2, 3]);
+
+    renderer
+        .clear(
+            None,
+            ClearDescription {
+                color: Some(CLEAR_COLOR.into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    draw_indexed(
+        &mut renderer,
+        PrimitiveType::Triangle,
+        shader,
+        vertex_buffer,
+        index_buffer,
+    )
+    .unwrap();
+    renderer.read_pixels()
+}
+
+fn check_left_half(image: Image) {
+    check_half(image, true);
+}
+
+fn check_half(image: Image, left: bool) {
+    assert_eq!(image.size, SIZE.into());
+    for y in 0..SIZE.1 {
+        for x in 0..SIZE.0 {
+            let expected = if (x < SIZE.0 / 2) == left {
+                DRAW_COLOR
+            } else {
+                CLEAR_COLOR
+            };
+            assert_eq!(
+                image.pixels[y * SIZE.0 + x],
+                expected.into(),
+                "pixel ({x}, {y})"
+            );
+        }
+    }
+}
+
+#[test]
+fn opengl_draw_indexed() {
+    check_left_half(draw_left_half(RendererAPI::OpenGL));
+}
+
+#[test]
+fn software_draw_indexed() {
+    check_left_half(draw_left_half(RendererAPI::Software));
+}
+
+#[test]
+fn opengl_resize() {
+    let mut renderer = create_renderer(RendererAPI::OpenGL);
+    assert!(renderer.resize(SIZE.into()).is_ok());
+    assert!(matches!(
+        renderer.resize((16, 16).into()),
+        Err(RendererError::HeadlessResizeUnsupported { .. })
+    ));
+}
+
+#[test]
+fn opengl_shared_objects_outlive_renderer() {
+    let mut renderer = create_renderer(RendererAPI::OpenGL);
+    let pixels = [(255, 0, 0, 255).into(); 2];
+    let texture = renderer
+        .create_texture(
+            (2, 1).into(),
+            Pixels::RGBA(&pixels),
+            None,
+            SamplerDescription::default(),
+        )
+        .unwrap();
+    let vertices: &[f32] = &[-1.0, -1.0, 0.0, -1.0, 0.0, 1.0, -1.0, 1.0];
+    let vertex_buffer = renderer
+        .create_vertex_buffer(&[VertexBufferElement::Float2], slice_to_bytes(vertices))
+        .unwrap();
+    let index_buffer = renderer.create_index_buffer(&[0, 1, 2, 0, 2, 3]);
+
+    let mut shared = renderer
+        .create_shared_renderer(Surface::new_headless(SIZE.into()))
+        .unwrap();
+    shared.share_texture(texture).unwrap();
+    shared.share_vertex_buffer(vertex_buffer).unwrap();
+    shared.share_index_buffer(index_buffer).unwrap();
+    drop(renderer);
+
+    assert!(shared
+        .get_texture(texture)
+        .unwrap()
+        .get_pixels()
+        .iter()
+        .all(|&pixel| pixel == DRAW_COLOR.into()));
+    let shader = create_shader(&mut shared, |inputs, _uniforms, _varyings| inputs[0]);
+    shared
+        .clear(
+            None,
+            ClearDescription {
+                color: Some(CLEAR_COLOR.into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    draw_indexed(
+        &mut shared,
+        PrimitiveType::Triangle,
+        shader,
+        vertex_buffer,
+        index_buffer,
+    )
+    .unwrap();
+    check_left_half(shared.read_pixels());
+}
+
+#[test]
+fn software_resize() {
+    let mut renderer = create_renderer(RendererAPI::Software);
+    renderer.resize((16, 16).into()).unwrap();
+    assert_eq!(renderer.read_pixels().size, (16, 16).into());
+}
+
+#[test]
+fn software_index_out_of_bounds() {
+    let mut renderer = create_renderer(RendererAPI::Software);
+    let shader = create_shader(&mut renderer, |inputs, _uniforms, _varyings| inputs[0]);
+    let vertices: &[f32] = &[-1.0, -1.0, 1.0, -1.0, 1.0, 1.0];
+    let vertex_buffer = renderer
+        .create_vertex_buffer(&[VertexBufferElement::Float2], slice_to_bytes(vertices))
+        .unwrap();
+    let index_buffer = renderer.create_index_buffer(&[0, 1, 7]);
+    assert!(matches!(
+        draw_indexed(
+            &mut renderer,
+            PrimitiveType::Triangle,
+            shader,
+            vertex_buffer,
+            index_buffer
+        ),
+        Err(RendererError::IndexOutOfBounds {
+            index: 7,
+            vertex_count: 3
+        })
+    ));
+}
+
+#[test]
+fn software_index_out_of_bounds_for_every_primitive_type() {
+    let mut renderer = create_renderer(RendererAPI::Software);
+    let shader = create_shader(&mut renderer, |inputs, _uniforms, _varyings| inputs[0]);
+    let vertices: &[f32] = &[-1.0, -1.0, 1.0, -1.0, 1.0, 1.0];
+    let vertex_buffer = renderer
+        .create_vertex_buffer(&[VertexBufferElement::Float2], slice_to_bytes(vertices))
+        .unwrap();
+    let index_buffer = renderer.create_index_buffer(&[0, 1, 2, 9]);
+    for typ in [
+        PrimitiveType::Points,
+        PrimitiveType::Lines,
+        PrimitiveType::LineStrip,
+        PrimitiveType::TriangleFan,
+    ] {
+        assert!(matches!(
+            draw_indexed(&mut renderer, typ, shader, vertex_buffer, index_buffer),
+            Err(RendererError::IndexOutOfBounds {
+                index: 9,
+                vertex_count: 3
+            })
+        ));
+    }
+}
+
+#[test]
+fn software_varying_count_mismatch() {
+    let mut renderer = create_renderer(RendererAPI::Software);
+    let shader = create_shader(&mut renderer, |inputs, _uniforms, varyings| {
+        if inputs[0].x > 0.0 {
+            varyings.push(1.0);
+        }
+        inputs[0]
+    });
+    let vertices: &[f32] = &[-1.0, -1.0, 1.0, -1.0, 1.0, 1.0];
+    let vertex_buffer = renderer
+        .create_vertex_buffer(&[VertexBufferElement::Float2], slice_to_bytes(vertices))
+        .unwrap();
+    let index_buffer = renderer.create_index_buffer(&[0, 1, 2]);
+    assert!(matches!(
+        draw_indexed(
+            &mut renderer,
+            PrimitiveType::Triangle,
+            shader,
+            vertex_buffer,
+            index_buffer
+        ),
+        Err(RendererError::VaryingCountMismatch {
+            expected: 0,
+            actual: 1
+        })
+    ));
+}
+
+#[test]
+fn opengl_uniform_values() {
+    let mut renderer = create_renderer(RendererAPI::OpenGL);
+    let shader = renderer
+        .create_shader(ShaderDescription {
+            inputs: &[ShaderInput {
+                name: "a_Position",
+                typ: ShaderDataType::Float2,
+            }],
+            uniforms: &[
+                ShaderUniform {
+                    name: "u_Offset",
+                    typ: ShaderDataType::Float3,
+                    array_size: 1,
+                },
+                ShaderUniform {
+                    name: "u_Red",
+                    typ: ShaderDataType::UInt,
+                    array_size: 1,
+                },
+            ],
+            uniform_blocks: &[],
+            glsl: Some(GlslShaderSource {
+                vertex: r"#version 330 core
+
+in vec2 a_Position;
+uniform vec3 u_Offset;
+
+void main() {
+    gl_Position = vec4(a_Position + u_Offset.xy, u_Offset.z, 1.0);
+}
+",
+                fragment: r"#version 330 core
+
+out vec4 o_Color;
+uniform uint u_Red;
+
+void main() {
+    o_Color = vec4(float(u_Red) / 255.0, 0.0, 0.0, 1.0);
+}
+",
+                preprocessor: GlslPreprocessor::default(),
+            }),
+            software: None,
+        })
+        .unwrap();
+
+    let offsets: &[Vector3<f32>] = &[Vector3::zero(), Vector3::zero()];
+    assert!(matches!(
+        renderer
+            .get_shader_mut(shader)
+            .unwrap()
+            .set_uniform("u_Offset", UniformValue::Float3Array(offsets)),
+        Err(RendererError::UniformArraySizeMismatch {
+            length: 2,
+            array_size: 1,
+            ..
+        })
+    ));
+    let shader_mut = renderer.get_shader_mut(shader).unwrap();
+    shader_mut
+        .set_uniform("u_Offset", UniformValue::Float3Array(&offsets[..1]))
+        .unwrap();
+    shader_mut
+        .set_uniform("u_Red", UniformValue::UInt(255))
+        .unwrap();
+
+    let vertices: &[f32] = &[-1.0, -1.0, 1.0, -1.0, 1.0, 1.0, -1.0, 1.0];
+    let vertex_buffer = renderer
+        .create_vertex_buffer(&[VertexBufferElement::Float2], slice_to_bytes(vertices))
+        .unwrap();
+    let index_buffer = renderer.create_index_buffer(&[0, 1, 2, 0, 2, 3]);
+    renderer.clear(None, ClearDescription::default()).unwrap();
+    draw_indexed(
+        &mut renderer,
+        PrimitiveType::Triangle,
+        shader,
+        vertex_buffer,
+        index_buffer,
+    )
+    .unwrap();
+    assert!(renderer
+        .read_pixels()
+        .pixels
+        .iter()
+        .all(|&pixel| pixel == DRAW_COLOR.into()));
+}
+
+fn draw_with_missing_input(api: RendererAPI) -> Result<(), RendererError> {
+    let mut renderer = create_renderer(api);
+    let shader = renderer
+        .create_shader(ShaderDescription {
+            inputs: &[
+                ShaderInput {
+                    name: "a_Position",
+                    typ: ShaderDataType::Float2,
+                },
+                ShaderInput {
+                    name: "a_Color",
+                    typ: ShaderDataType::Float4,
+                },
+            ],
+            uniforms: &[],
+            uniform_blocks: &[],
+            glsl: Some(GlslShaderSource {
+                vertex: r"#version 330 core
+
+in vec2 a_Position;
+in vec4 a_Color;
+out vec4 v_Color;
+
+void main() {
+    v_Color = a_Color;
+    gl_Position = vec4(a_Position, 0.0, 1.0);
+}
+",
+                fragment: r"#version 330 core
+
+in vec4 v_Color;
+out vec4 o_Color;
+
+void main() {
+    o_Color = v_Color;
+}
+",
+                preprocessor: GlslPreprocessor::default(),
+            }),
+            software: Some(SoftwareShaderSource {
+                vertex: |inputs, _uniforms, _varyings| inputs[0],
+                fragment: |_varyings, _uniforms, outputs| outputs[0] = DRAW_COLOR.into(),
+            }),
+        })
+        .unwrap();
+    let vertices: &[f32] = &[-1.0, -1.0, 1.0, -1.0, 1.0, 1.0];
+    let vertex_buffer = renderer
+        .create_vertex_buffer(&[VertexBufferElement::Float2], slice_to_bytes(vertices))
+        .unwrap();
+    let index_buffer = renderer.create_index_buffer(&[0, 1, 2]);
+    draw_indexed(
+        &mut renderer,
+        PrimitiveType::Triangle,
+        shader,
+        vertex_buffer,
+        index_buffer,
+    )
+}
+
+#[test]
+fn missing_vertex_input() {
+    for api in [RendererAPI::OpenGL, RendererAPI::Software] {
+        match draw_with_missing_input(api) {
+            Err(RendererError::VertexLayoutMismatch {
+                input,
+                location: 1,
+                element_type: None,
+                ..
+            }) => assert_eq!(input, "a_Color"),
+            result => panic!("expected a missing input, got {result:?}"),
+        }
+    }
+}
+
+fn create_color_texture(renderer: &mut Box<dyn Renderer>, size: (usize, usize)) -> TextureID {
+    renderer
+        .create_texture(
+            size.into(),
+            Pixels::RGBA(&vec![(0, 0, 0, 255).into(); size.0 * size.1]),
+            None,
+            SamplerDescription::default(),
+        )
+        .unwrap()
+}
+
+#[test]
+fn duplicate_color_attachment() {
+    for api in [RendererAPI::OpenGL, RendererAPI::Software] {
+        let mut renderer = create_renderer(api);
+        let texture = create_color_texture(&mut renderer, SIZE);
+        assert!(matches!(
+            renderer.create_framebuffer(SIZE.into(), &[texture, texture], DepthAttachment::None),
+            Err(RendererError::DuplicateAttachment(id)) if id == texture
+        ));
+    }
+}
+
+#[test]
+fn resized_attachment() {
+    for api in [RendererAPI::OpenGL, RendererAPI::Software] {
+        let mut renderer = create_renderer(api);
+        let texture = create_color_texture(&mut renderer, SIZE);
+        let framebuffer = renderer
+            .create_framebuffer(SIZE.into(), &[texture], DepthAttachment::None)
+            .unwrap();
+        renderer
+            .get_texture_mut(texture)
+            .unwrap()
+            .set_pixels((2, 2).into(), Pixels::RGBA(&[(0, 0, 0, 255).into(); 4]))
+            .unwrap();
+        let description = ClearDescription {
+            color: Some(CLEAR_COLOR.into()),
+            ..Default::default()
+        };
+        assert!(matches!(
+            renderer.clear(Some(framebuffer), description),
+            Err(RendererError::AttachmentSizeMismatch { .. })
+        ));
+        assert!(matches!(
+            renderer.drawing_context(
+                Some(framebuffer),
+                None,
+                None,
+                Camera::default(),
+                PipelineState::default(),
+                CullFace::None,
+            ),
+            Err(RendererError::AttachmentSizeMismatch { .. })
+        ));
+    }
+}
+
+const CAMERA_BLOCK_VERTEX: &str = r"#version 330 core
+
+in vec2 a_Position;
+layout(std140) uniform Camera {
+    mat4 u_ProjectionMatrix;
+    mat4 u_ViewMatrix;
+};
+
+void main() {
+    gl_Position = u_ProjectionMatrix * inverse(u_ViewMatrix) * vec4(a_Position, 0.0, 1.0);
+}
+";
+
+const CAMERA_UNIFORMS_VERTEX: &str = r"#version 330 core
+
+in vec2 a_Position;
+uniform mat4 u_ProjectionMatrix;
+uniform mat4 u_ViewMatrix;
+
+void main() {
+    gl_Position = u_ProjectionMatrix * inverse(u_ViewMatrix) * vec4(a_Position, 0.0, 1.0);
+}
+";
+
+/// Draws a quad that covers the surface with the camera moved right and then, after clearing, with
+/// the camera moved left, which only leaves the right half drawn
+fn draw_with_moving_camera(api: RendererAPI, vertex: &str, uses_block: bool) -> Image {
+    let mut renderer = create_renderer(api);
+    let uniforms = &[
+        ShaderUniform {
+            name: "u_ProjectionMatrix",
+            typ: ShaderDataType::Matrix4x4,
+            array_size: 1,
+        },
+        ShaderUniform {
+            name: "u_ViewMatrix",
+            typ: ShaderDataType::Matrix4x4,
+            array_size: 1,
+        },
+    ];
+    let shader = renderer
+        .create_shader(ShaderDescription {
+            inputs: &[ShaderInput {
+                name: "a_Position",
+                typ: ShaderDataType::Float2,
+            }],
+            uniforms: if uses_block { &[] } else { uniforms },
+            uniform_blocks: if uses_block {
+                BUILTIN_UNIFORM_BLOCKS
+            } else {
+                &[]
+            },
+            glsl: Some(GlslShaderSource {
+                vertex,
+                fragment: r"#version 330 core
+
+out vec4 o_Color;
+
+void main() {
+    o_Color = vec4(1.0, 0.0, 0.0, 1.0);
+}
+",
+                preprocessor: GlslPreprocessor::default(),
+            }),
+            software: Some(SoftwareShaderSource {
+                vertex: |inputs, uniforms, _varyings| {
+                    let projection_matrix = uniforms.get_matrix("u_ProjectionMatrix").unwrap();
+                    let view_matrix = uniforms.get_matrix("u_ViewMatrix").unwrap();
+                    projection_matrix * (view_matrix.inverse().unwrap() * inputs[0])
+                },
+                fragment: |_varyings, _uniforms, outputs| outputs[0] = DRAW_COLOR.into(),
+            }),
+        })
+        .unwrap();
+    let vertices: &[f32] = &[-1.0, -1.0, 1.0, -1.0, 1.0, 1.0, -1.0, 1.0];
+    let vertex_buffer = renderer
+        .create_vertex_buffer(&[VertexBufferElement::Float2], slice_to_bytes(vertices))
+        .unwrap();
+    let index_buffer = renderer.create_index_buffer(&[0, 1, 2, 0, 2, 3]);
+
+    for x in [1.0, -1.0] {
+        renderer
+            .clear(
+                None,
+                ClearDescription {
+                    color: Some(CLEAR_COLOR.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let mut draw_context = renderer
+            .drawing_context(
+                None,
+                None,
+                None,
+                Camera {
+                    transform: Transform::new(
+                        (x, 0.0, 0.0).into(),
+                        Vector3::zero(),
+                        (1.0, 1.0, 1.0).into(),
+                    ),
+                    projection_type: CameraProjectionType::None,
+                },
+                PipelineState::default(),
+                CullFace::None,
+            )
+            .unwrap();
+        for _ in 0..2 {
+            draw_context
+                .draw_indexed(
+                    PrimitiveType::Triangle,
+                    shader,
+                    vertex_buffer,
+                    index_buffer,
+                    None,
+                    Matrix4x4::identity(),
+                    (1.0, 1.0, 1.0).into(),
+                )
+                .unwrap();
+        }
+    }
+    renderer.read_pixels()
+}
+
+#[test]
+fn camera_of_each_drawing_context() {
+    check_half(
+        draw_with_moving_camera(RendererAPI::OpenGL, CAMERA_BLOCK_VERTEX, true),
+        false,
+    );
+    check_half(
+        draw_with_moving_camera(RendererAPI::OpenGL, CAMERA_UNIFORMS_VERTEX, false),
+        false,
+    );
+    check_half(
+        draw_with_moving_camera(RendererAPI::Software, CAMERA_UNIFORMS_VERTEX, false),
+        false,
+    );
+}
+
+#[test]
+fn texture_sub_pixels() {
+    for api in [RendererAPI::OpenGL, RendererAPI::Software] {
+        let mut renderer = create_renderer(api);
+        let id = create_color_texture(&mut renderer, (4, 3));
+        let texture = renderer.get_texture_mut(id).unwrap();
+        let region = [(255, 0, 0, 255).into(), (0, 255, 0, 255).into()];
+        texture
+            .set_sub_pixels((1, 2).into(), (2, 1).into(), Pixels::RGBA(&region))
+            .unwrap();
+        assert_eq!(
+            texture
+                .get_sub_pixels((1, 2).into(), (2, 1).into())
+                .unwrap(),
+            [(1.0, 0.0, 0.0, 1.0).into(), (0.0, 1.0, 0.0, 1.0).into()]
+        );
+        let pixels = texture.get_pixels();
+        for (i, pixel) in pixels.iter().enumerate() {
+            let expected = match i {
+                9 => (1.0, 0.0, 0.0, 1.0),
+                10 => (0.0, 1.0, 0.0, 1.0),
+                _ => (0.0, 0.0, 0.0, 1.0),
+            };
+            assert_eq!(*pixel, expected.into(), "pixel {i}");
+        }
+
+        assert!(matches!(
+            texture.set_sub_pixels((usize::MAX, 0).into(), (2, 1).into(), Pixels::RGBA(&region)),
+            Err(RendererError::RegionOutOfBounds { .. })
+        ));
+        assert!(matches!(
+            texture.get_sub_pixels((3, 0).into(), (2, 1).into()),
+            Err(RendererError::RegionOutOfBounds { .. })
+        ));
+    }
+}
+
+fn draw_with_uniform_array(api: RendererAPI) -> Image {
+    let mut renderer = create_renderer(api);
+    let shader = renderer
+        .create_shader(ShaderDescription {
+            inputs: &[ShaderInput {
+                name: "a_Position",
+                typ: ShaderDataType::Float2,
+            }],
+            uniforms: &[ShaderUniform {
+                name: "u_Channels",
+                typ: ShaderDataType::UInt,
+                array_size: 3,
+            }],
+            uniform_blocks: &[],
+            glsl: Some(GlslShaderSource {
+                vertex: r"#version 330 core
+
+in vec2 a_Position;
+
+void main() {
+    gl_Position = vec4(a_Position, 0.0, 1.0);
+}
+",
+                fragment: r"#version 330 core
+
+out vec4 o_Color;
+uniform uint u_Channels[3];
+
+void main() {
+    o_Color = vec4(float(u_Channels[0]), float(u_Channels[1]), float(u_Channels[2]), 255.0) / 255.0;
+}
+",
+                preprocessor: GlslPreprocessor::default(),
+            }),
+            software: Some(SoftwareShaderSource {
+                vertex: |inputs, _uniforms, _varyings| inputs[0],
+                fragment: |_varyings, uniforms, outputs| {
+                    let Some(UniformValue::UIntArray(channels)) = uniforms.get_value("u_Channels")
+                    else {
+                        panic!("u_Channels is not set");
+                    };
+                    outputs[0] = (
+                        channels[0] as f32 / 255.0,
+                        channels[1] as f32 / 255.0,
+                        channels[2] as f32 / 255.0,
+                        1.0,
+                    )
+                        .into();
+                },
+            }),
+        })
+        .unwrap();
+
+    let shader_mut = renderer.get_shader_mut(shader).unwrap();
+    assert!(matches!(
+        shader_mut.set_uniform("u_Channels", UniformValue::UIntArray(&[0, 0, 0, 0])),
+        Err(RendererError::UniformArraySizeMismatch {
+            length: 4,
+            array_size: 3,
+            ..
+        })
+    ));
+    shader_mut
+        .set_uniform("u_Channels", UniformValue::UIntArray(&[255, 0, 255]))
+        .unwrap();
+
+    let vertices: &[f32] = &[-1.0, -1.0, 1.0, -1.0, 1.0, 1.0, -1.0, 1.0];
+    let vertex_buffer = renderer
+        .create_vertex_buffer(&[VertexBufferElement::Float2], slice_to_bytes(vertices))
+        .unwrap();
+    let index_buffer = renderer.create_index_buffer(&[0, 1, 2, 0, 2, 3]);
+    draw_indexed(
+        &mut renderer,
+        PrimitiveType::Triangle,
+        shader,
+        vertex_buffer,
+        index_buffer,
+    )
+    .unwrap();
+    renderer.read_pixels()
+}
+
+#[test]
+fn uniform_arrays() {
+    for api in [RendererAPI::OpenGL, RendererAPI::Software] {
+        let image = draw_with_uniform_array(api);
+        assert!(image
+            .pixels
+            .iter()
+            .all(|&pixel| pixel == (1.0, 0.0, 1.0, 1.0).into()));
+    }
+}
+
+#[test]
+fn save_png_starts_at_the_top() {
+    for (i, api) in [RendererAPI::OpenGL, RendererAPI::Software]
+        .into_iter()
+        .enumerate()
+    {
+        let mut renderer = create_renderer(api);
+        let shader = create_shader(&mut renderer, |inputs, _uniforms, _varyings| inputs[0]);
+        // Only the top half
+        let vertices: &[f32] = &[-1.0, 0.0, 1.0, 0.0, 1.0, 1.0, -1.0, 1.0];
+        let vertex_buffer = renderer
+            .create_vertex_buffer(&[VertexBufferElement::Float2], slice_to_bytes(vertices))
+            .unwrap();
+        let index_buffer = renderer.create_index_buffer(&[0, 1, 2, 0, 2, 3]);
+        renderer
+            .clear(
+                None,
+                ClearDescription {
+                    color: Some(CLEAR_COLOR.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        draw_indexed(
+            &mut renderer,
+            PrimitiveType::Triangle,
+            shader,
+            vertex_buffer,
+            index_buffer,
+        )
+        .unwrap();
+        let image = renderer.read_pixels();
+        assert_eq!(image.get_pixel((0, 0).into()), CLEAR_COLOR.into());
+        assert_eq!(image.get_pixel((0, SIZE.1 - 1).into()), DRAW_COLOR.into());
+
+        let path =
+            std::env::temp_dir().join(format!("thallium_save_png_{}_{i}.png", std::process::id()));
+        image.save_png(&path).unwrap();
+        let decoder = png::Decoder::new(std::fs::File::open(&path).unwrap());
+        let mut reader = decoder.read_info().unwrap();
+        let mut data = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut data).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!((info.width, info.height), (SIZE.0 as u32, SIZE.1 as u32));
+        assert_eq!(info.color_type, png::ColorType::Rgba);
+        let row = info.line_size;
+        assert_eq!(data[..4], [255, 0, 0, 255], "top left");
+        assert_eq!(
+            data[(SIZE.1 - 1) * row..(SIZE.1 - 1) * row + 4],
+            [0, 0, 255, 255],
+            "bottom left"
+        );
+    }
+
+    let image = Image {
+        size: (1, 1).into(),
+        pixels: vec![(-0.5, 0.5, 2.0, 1.0).into()],
+    };
+    assert_eq!(image.to_rgba8(), [(0, 128, 255, 255).into()]);
+}
+
+fn create_texture_shader(renderer: &mut Box<dyn Renderer>) -> ShaderID {
+    renderer
+        .create_shader(ShaderDescription {
+            inputs: &[
+                ShaderInput {
+                    name: "a_Position",
+                    typ: ShaderDataType::Float2,
+                },
+                ShaderInput {
+                    name: "a_TexCoord",
+                    typ: ShaderDataType::Float2,
+                },
+            ],
+            uniforms: &[ShaderUniform {
+                name: "u_Texture",
+                typ: ShaderDataType::Texture,
+                array_size: 1,
+            }],
+            uniform_blocks: &[],
+            glsl: Some(GlslShaderSource {
+                vertex: r"#version 330 core
+
+in vec2 a_Position;
+in vec2 a_TexCoord;
+out vec2 v_TexCoord;
+
+void main() {
+    v_TexCoord = a_TexCoord;
+    gl_Position = vec4(a_Position, 0.0, 1.0);
+}
+",
+                fragment: r"#version 330 core
+
+in vec2 v_TexCoord;
+out vec4 o_Color;
+uniform sampler2D u_Texture;
+
+void main() {
+    o_Color = texture(u_Texture, v_TexCoord);
+}
+",
+                preprocessor: GlslPreprocessor::default(),
+            }),
+            software: Some(SoftwareShaderSource {
+                vertex: |inputs, _uniforms, varyings| {
+                    varyings.extend([inputs[1].x, inputs[1].y]);
+                    inputs[0]
+                },
+                fragment: |varyings, uniforms, outputs| {
+                    outputs[0] = uniforms
+                        .sample("u_Texture", (varyings[0], varyings[1]).into())
+                        .unwrap();
+                },
+            }),
+        })
+        .unwrap()
+}
+
+/// Draws `texture` over the whole surface, with texture coordinates from `(0, 0)` at the bottom
+/// left to `(scale, 1)` at the top right
+fn draw_textured(renderer: &mut Box<dyn Renderer>, texture: TextureID, scale: f32) -> Image {
+    let shader = create_texture_shader(renderer);
+    let vertices: &[f32] = &[
+        -1.0, -1.0, 0.0, 0.0, 1.0, -1.0, scale, 0.0, 1.0, 1.0, scale, 1.0, -1.0, 1.0, 0.0, 1.0,
+    ];
+    let vertex_buffer = renderer
+        .create_vertex_buffer(
+            &[VertexBufferElement::Float2, VertexBufferElement::Float2],
+            slice_to_bytes(vertices),
+        )
+        .unwrap();
+    let index_buffer = renderer.create_index_buffer(&[0, 1, 2, 0, 2, 3]);
+    renderer.clear(None, ClearDescription::default()).unwrap();
+    let mut draw_context = renderer
+        .drawing_context(
+            None,
+            None,
+            None,
+            Camera::default(),
+            PipelineState::default(),
+            CullFace::None,
+        )
+        .unwrap();
+    draw_context
+        .draw_indexed(
+            PrimitiveType::Triangle,
+            shader,
+            vertex_buffer,
+            index_buffer,
+            Some(texture),
+            Matrix4x4::identity(),
+            (1.0, 1.0, 1.0).into(),
+        )
+        .unwrap();
+    drop(draw_context);
+    renderer.read_pixels()
+}
+
+/// Checks that every channel is within `tolerance` of `expected`
+fn assert_pixel_near(pixel: Vector4<f32>, expected: Vector4<f32>, tolerance: f32, what: &str) {
+    let channels = [
+        (pixel.x, expected.x),
+        (pixel.y, expected.y),
+        (pixel.z, expected.z),
+        (pixel.w, expected.w),
+    ];
+    assert!(
+        channels
+            .iter()
+            .all(|(channel, expected)| (channel - expected).abs() <= tolerance),
+        "{what}: expected {expected:?}, got {pixel:?}"
+    );
+}
+
+#[test]
+fn sampler_wrap_and_filter() {
+    const RED: (f32, f32, f32, f32) = (1.0, 0.0, 0.0, 1.0);
+    const GREEN: (f32, f32, f32, f32) = (0.0, 1.0, 0.0, 1.0);
+    let nearest = |wrap_u| SamplerDescription {
+        min_filter: TextureFilter::Nearest,
+        mag_filter: TextureFilter::Nearest,
+        wrap_u,
+        wrap_v: TextureWrap::ClampToEdge,
+        mipmaps: false,
+        max_anisotropy: 1.0,
+    };
+    // The texture is red then green, and it is drawn twice across the surface
+    let cases = [
+        (
+            nearest(TextureWrap::Repeat),
+            [RED, RED, GREEN, GREEN, RED, RED, GREEN, GREEN],
+        ),
+        (
+            nearest(TextureWrap::ClampToEdge),
+            [RED, RED, GREEN, GREEN, GREEN, GREEN, GREEN, GREEN],
+        ),
+        (
+            nearest(TextureWrap::MirroredRepeat),
+            [RED, RED, GREEN, GREEN, GREEN, GREEN, RED, RED],
+        ),
+        (
+            SamplerDescription {
+                mag_filter: TextureFilter::Linear,
+                ..nearest(TextureWrap::ClampToEdge)
+            },
+            [
+                RED,
+                (0.75, 0.25, 0.0, 1.0),
+                (0.25, 0.75, 0.0, 1.0),
+                GREEN,
+                GREEN,
+                GREEN,
+                GREEN,
+                GREEN,
+            ],
+        ),
+    ];
+    for api in [RendererAPI::OpenGL, RendererAPI::Software] {
+        let mut renderer = create_renderer(api);
+        for (sampler, expected) in cases {
+            let texture = renderer
+                .create_texture(
+                    (2, 1).into(),
+                    Pixels::RGBA(&[(255, 0, 0, 255).into(), (0, 255, 0, 255).into()]),
+                    None,
+                    sampler,
+                )
+                .unwrap();
+            let image = draw_textured(&mut renderer, texture, 2.0);
+            for y in 0..SIZE.1 {
+                for (x, expected) in expected.into_iter().enumerate() {
+                    assert_pixel_near(
+                        image.get_pixel((x, y).into()),
+                        expected.into(),
+                        2.0 / 255.0,
+                        &format!("{sampler:?} at ({x}, {y})"),
+                    );
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn sampler_mipmaps() {
+    // A checkerboard averages to grey at its smallest mip level, without mipmaps every pixel
+    // samples a single texel
+    let pixels: Vec<Vector4<u8>> = (0..64)
+        .map(|i| match (i % 8 + i / 8) % 2 {
+            0 => (0, 0, 0, 255).into(),
+            _ => (255, 255, 255, 255).into(),
+        })
+        .collect();
+    for (min_filter, mipmaps) in [
+        (TextureFilter::Nearest, false),
+        (TextureFilter::Trilinear, true),
+    ] {
+        let mut images = vec![];
+        for api in [RendererAPI::OpenGL, RendererAPI::Software] {
+            let mut renderer = create_renderer(api);
+            let texture = renderer
+                .create_texture(
+                    (8, 8).into(),
+                    Pixels::RGBA(&pixels),
+                    None,
+                    SamplerDescription {
+                        min_filter,
+                        mag_filter: TextureFilter::Nearest,
+                        wrap_u: TextureWrap::Repeat,
+                        wrap_v: TextureWrap::Repeat,
+                        mipmaps,
+                        max_anisotropy: 1.0,
+                    },
+                )
+                .unwrap();
+            // Every pixel covers 8 texels across
+            images.push(draw_textured(&mut renderer, texture, 8.0));
+        }
+        let [opengl, software] = &images[..] else {
+            unreachable!()
+        };
+        for (i, (opengl, software)) in opengl.pixels.iter().zip(&software.pixels).enumerate() {
+            assert_pixel_near(*software, *opengl, 2.0 / 255.0, &format!("pixel {i}"));
+            if mipmaps {
+                assert_pixel_near(
+                    *opengl,
+                    (0.5, 0.5, 0.5, 1.0).into(),
+                    2.0 / 255.0,
+                    &format!("pixel {i}"),
+                );
+            } else {
+                assert!(
+                    *opengl == (0.0, 0.0, 0.0, 1.0).into()
+                        || *opengl == (1.0, 1.0, 1.0, 1.0).into(),
+                    "pixel {i} is {opengl:?} without mipmaps"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn texture_formats() {
+    let color = [(0.25, 0.5, 0.75, 1.0).into()];
+    let cases = [
+        (TextureFormat::R8, (0.25, 0.0, 0.0, 1.0)),
+        (TextureFormat::RG8, (0.25, 0.5, 0.0, 1.0)),
+        (TextureFormat::RGB8, (0.25, 0.5, 0.75, 1.0)),
+        // The pixels are taken as srgb, so sampling converts them to linear
+        (TextureFormat::SRGB8Alpha8, (0.0513, 0.2159, 0.521, 1.0)),
+        (TextureFormat::RG16, (0.25, 0.5, 0.0, 1.0)),
+        (TextureFormat::R16F, (0.25, 0.0, 0.0, 1.0)),
+        (TextureFormat::RGBA16F, (0.25, 0.5, 0.75, 1.0)),
+        (TextureFormat::RGB32F, (0.25, 0.5, 0.75, 1.0)),
+    ];
+    for api in [RendererAPI::OpenGL, RendererAPI::Software] {
+        let mut renderer = create_renderer(api);
+        let mut check = |format, pixels, expected: (f32, f32, f32, f32)| {
+            let texture = renderer
+                .create_texture(
+                    (1, 1).into(),
+                    pixels,
+                    Some(format),
+                    SamplerDescription::default(),
+                )
+                .unwrap();
+            assert_eq!(renderer.get_texture(texture).unwrap().get_format(), format);
+            let image = draw_textured(&mut renderer, texture, 1.0);
+            for (i, &pixel) in image.pixels.iter().enumerate() {
+                assert_pixel_near(
+                    pixel,
+                    expected.into(),
+                    2.0 / 255.0,
+                    &format!("{format:?} pixel {i}"),
+                );
+            }
+        };
+        for (format, expected) in cases {
+            check(format, Pixels::RGBAF(&color), expected);
+        }
+        check(
+            TextureFormat::Depth24,
+            Pixels::RF(&[0.25]),
+            (0.25, 0.0, 0.0, 1.0),
+        );
+    }
+}
+
+/// Draws `shader` over the whole surface
+fn draw_full_screen(renderer: &mut Box<dyn Renderer>, shader: ShaderID) -> Image {
+    let vertices: &[f32] = &[-1.0, -1.0, 1.0, -1.0, 1.0, 1.0, -1.0, 1.0];
+    let vertex_buffer = renderer
+        .create_vertex_buffer(&[VertexBufferElement::Float2], slice_to_bytes(vertices))
+        .unwrap();
+    let index_buffer = renderer.create_index_buffer(&[0, 1, 2, 0, 2, 3]);
+    renderer.clear(None, ClearDescription::default()).unwrap();
+    draw_indexed(
+        renderer,
+        PrimitiveType::Triangle,
+        shader,
+        vertex_buffer,
+        index_buffer,
+    )
+    .unwrap();
+    renderer.read_pixels()
+}
+
+#[test]
+fn shader_hot_reload() {
+    use std::{
+        fs,
+        time::{Duration, SystemTime},
+    };
+
+    let directory =
+        std::env::temp_dir().join(format!("thallium_hot_reload_{}", std::process::id()));
+    fs::create_dir_all(&directory).unwrap();
+    let vertex = directory.join("shader.vert.glsl");
+    let fragment = directory.join("shader.frag.glsl");
+    let color = directory.join("color.glsl");
+    fs::write(
+        &vertex,
+        r"#version 330 core
+
+in vec2 a_Position;
+
+void main() {
+    gl_Position = vec4(a_Position, 0.0, 1.0);
+}
+",
+    )
+    .unwrap();
+    fs::write(
+        &fragment,
+        r#"#version 330 core
+
+#include "color.glsl"
+
+out vec4 o_Color;
+
+void main() {
+    o_Color = get_color();
+}
+"#,
+    )
+    .unwrap();
+    // Only changes to the included file, with a modification time that is surely different
+    let mut seconds = 0;
+    let mut write_color = |source: &str| {
+        fs::write(&color, source).unwrap();
+        seconds += 10;
+        fs::File::options()
+            .write(true)
+            .open(&color)
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(seconds))
+            .unwrap();
+    };
+    write_color("vec4 get_color() { return vec4(1.0, 0.0, 0.0, 1.0); }");
+
+    for api in [RendererAPI::OpenGL, RendererAPI::Software] {
+        let opengl = matches!(api, RendererAPI::OpenGL);
+        let mut renderer = create_renderer(api);
+        let shader = renderer
+            .create_shader_from_files(
+                ShaderDescription {
+                    inputs: &[ShaderInput {
+                        name: "a_Position",
+                        typ: ShaderDataType::Float2,
+                    }],
+                    uniforms: &[],
+                    uniform_blocks: &[],
+                    glsl: None,
+                    software: Some(SoftwareShaderSource {
+                        vertex: |inputs, _uniforms, _varyings| inputs[0],
+                        fragment: |_varyings, _uniforms, outputs| outputs[0] = DRAW_COLOR.into(),
+                    }),
+                },
+                GlslShaderFiles {
+                    vertex: &vertex,
+                    fragment: &fragment,
+                    preprocessor: GlslPreprocessor::default(),
+                },
+            )
+            .unwrap();
+        let draw_color = |renderer: &mut Box<dyn Renderer>| {
+            draw_full_screen(renderer, shader).get_pixel((0, 0).into())
+        };
+        assert_eq!(draw_color(&mut renderer), DRAW_COLOR.into());
+        assert!(renderer.reload_shaders().is_empty());
+
+        write_color("vec4 get_color() { return vec4(0.0, 1.0, 0.0, 1.0); }");
+        let results = renderer.reload_shaders();
+        assert!(matches!(results[..], [(id, Ok(()))] if id == shader));
+        if opengl {
+            assert_eq!(draw_color(&mut renderer), (0.0, 1.0, 0.0, 1.0).into());
+        }
+
+        // A shader that fails keeps the last program, and is not compiled again until it changes
+        write_color("vec4 get_color() { return 1.0; }");
+        let results = renderer.reload_shaders();
+        if opengl {
+            assert!(matches!(
+                results[..],
+                [(id, Err(RendererError::ShaderCompilationFailed { .. }))] if id == shader
+            ));
+            assert_eq!(draw_color(&mut renderer), (0.0, 1.0, 0.0, 1.0).into());
+        } else {
+            assert!(matches!(results[..], [(id, Ok(()))] if id == shader));
+        }
+        assert!(renderer.reload_shaders().is_empty());
+
+        write_color("vec4 get_color() { return vec4(1.0, 0.0, 0.0, 1.0); }");
+        assert_eq!(renderer.reload_shaders().len(), 1);
+        assert_eq!(draw_color(&mut renderer), DRAW_COLOR.into());
+    }
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+fn draw_instanced(
+    api: RendererAPI,
+    instance_layout: &[VertexBufferElement],
+    instance_count: usize,
+) -> Result<Image, RendererError> {
+    let mut renderer = create_renderer(api);
+    let shader = renderer
+        .create_shader(ShaderDescription {
+            inputs: &[
+                ShaderInput {
+                    name: "a_Position",
+                    typ: ShaderDataType::Float2,
+                },
+                ShaderInput {
+                    name: "a_Offset",
+                    typ: ShaderDataType::Float2,
+                },
+            ],
+            uniforms: &[],
+            uniform_blocks: &[],
+            glsl: Some(GlslShaderSource {
+                vertex: r"#version 330 core
+
+in vec2 a_Position;
+in vec2 a_Offset;
+
+void main() {
+    gl_Position = vec4(a_Position + a_Offset, 0.0, 1.0);
+}
+",
+                fragment: r"#version 330 core
+
+out vec4 o_Color;
+
+void main() {
+    o_Color = vec4(1.0, 0.0, 0.0, 1.0);
+}
+",
+                preprocessor: GlslPreprocessor::default(),
+            }),
+            software: Some(SoftwareShaderSource {
+                vertex: |inputs, _uniforms, _varyings| {
+                    (
+                        inputs[0].x + inputs[1].x,
+                        inputs[0].y + inputs[1].y,
+                        0.0,
+                        1.0,
+                    )
+                        .into()
+                },
+                fragment: |_varyings, _uniforms, outputs| outputs[0] = DRAW_COLOR.into(),
+            }),
+        })
+        .unwrap();
+    // The first quarter of the surface, every instance moves it a quarter further
+    let vertices: &[f32] = &[-1.0, -1.0, -0.5, -1.0, -0.5, 1.0, -1.0, 1.0];
+    let vertex_buffer = renderer
+        .create_vertex_buffer(&[VertexBufferElement::Float2], slice_to_bytes(vertices))
+        .unwrap();
+    let offsets: &[f32] = &[0.0, 0.0, 0.5, 0.0, 1.0, 0.0];
+    let instance_buffer = renderer
+        .create_vertex_buffer(instance_layout, slice_to_bytes(offsets))
+        .unwrap();
+    let index_buffer = renderer.create_index_buffer(&[0, 1, 2, 0, 2, 3]);
+    renderer
+        .clear(
+            None,
+            ClearDescription {
+                color: Some(CLEAR_COLOR.into()),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+    let mut draw_context = renderer
+        .drawing_context(
+            None,
+            None,
+            None,
+            Camera::default(),
+            PipelineState::default(),
+            CullFace::None,
+        )
+        .unwrap();
+    draw_context.draw_indexed_instanced(
+        PrimitiveType::Triangle,
+        shader,
+        vertex_buffer,
+        index_buffer,
+        instance_buffer,
+        instance_count,
+        None,
+        Matrix4x4::identity(),
+        (1.0, 1.0, 1.0).into(),
+    )?;
+    drop(draw_context);
+    Ok(renderer.read_pixels())
+}
+
+#[test]
+fn instanced_drawing() {
+    let instance_layout = &[
+        VertexBufferElement::Location(1),
+        VertexBufferElement::Float2,
+    ];
+    // Two of the three instances cover the left half
+    check_left_half(draw_instanced(RendererAPI::OpenGL, instance_layout, 2).unwrap());
+    check_left_half(draw_instanced(RendererAPI::Software, instance_layout, 2).unwrap());
+
+    for api in [RendererAPI::OpenGL, RendererAPI::Software] {
+        assert!(matches!(
+            draw_instanced(api, instance_layout, 4),
+            Err(RendererError::InstanceCountOutOfBounds {
+                count: 4,
+                available: 3
+            })
+        ));
+    }
+    for api in [RendererAPI::OpenGL, RendererAPI::Software] {
+        assert!(matches!(
+            draw_instanced(api, &[VertexBufferElement::Float2], 2),
+            Err(RendererError::InvalidVertexLayout(_))
+        ));
+    }
+}
+
+#[test]
+fn materials() {
+    for api in [RendererAPI::OpenGL, RendererAPI::Software] {
+        let mut renderer = create_renderer(api);
+        let shader = renderer
+            .create_shader(ShaderDescription {
+                inputs: &[ShaderInput {
+                    name: "a_Position",
+                    typ: ShaderDataType::Float2,
+                }],
+                uniforms: &[
+                    ShaderUniform {
+                        name: "u_Base",
+                        typ: ShaderDataType::Texture,
+                        array_size: 1,
+                    },
+                    ShaderUniform {
+                        name: "u_Overlay",
+                        typ: ShaderDataType::Texture,
+                        array_size: 1,
+                    },
+                    ShaderUniform {
+                        name: "u_Tint",
+                        typ: ShaderDataType::Float4,
+                        array_size: 1,
+                    },
+                ],
+                uniform_blocks: &[],
+                glsl: Some(GlslShaderSource {
+                    vertex: r"#version 330 core
+
+in vec2 a_Position;
+
+void main() {
+    gl_Position = vec4(a_Position, 0.0, 1.0);
+}
+",
+                    fragment: r"#version 330 core
+
+out vec4 o_Color;
+uniform sampler2D u_Base;
+uniform sampler2D u_Overlay;
+uniform vec4 u_Tint;
+
+void main() {
+    o_Color = texture(u_Base, vec2(0.5)) * texture(u_Overlay, vec2(0.5)) * u_Tint;
+}
+",
+                    preprocessor: GlslPreprocessor::default(),
+                }),
+                software: Some(SoftwareShaderSource {
+                    vertex: |inputs, _uniforms, _varyings| inputs[0],
+                    fragment: |_varyings, uniforms, outputs| {
+                        let base = uniforms.sample("u_Base", (0.5, 0.5).into()).unwrap();
+                        let overlay = uniforms.sample("u_Overlay", (0.5, 0.5).into()).unwrap();
+                        let tint = uniforms.get_vector4("u_Tint").unwrap();
+                        outputs[0] = (
+                            base.x * overlay.x * tint.x,
+                            base.y * overlay.y * tint.y,
+                            base.z * overlay.z * tint.z,
+                            base.w * overlay.w * tint.w,
+                        )
+                            .into();
+                    },
+                }),
+            })
+            .unwrap();
+        let yellow = renderer
+            .create_texture(
+                (1, 1).into(),
+                Pixels::RGBA(&[(255, 255, 0, 255).into()]),
+                None,
+                SamplerDescription::default(),
+            )
+            .unwrap();
+        let magenta = renderer
+            .create_texture(
+                (1, 1).into(),
+                Pixels::RGBA(&[(255, 0, 255, 255).into()]),
+                None,
+                SamplerDescription::default(),
+            )
+            .unwrap();
+
+        // Only the base texture, the overlay slot stays empty so it is white
+        let green = renderer.create_material(shader).unwrap();
+        let material = renderer.get_material_mut(green).unwrap();
+        material.set_texture("u_Base", Some(yellow)).unwrap();
+        material
+            .set_uniform("u_Tint", UniformValue::Float4((0.0, 1.0, 1.0, 1.0).into()))
+            .unwrap();
+        assert!(matches!(
+            material.set_texture("u_Tint", Some(yellow)),
+            Err(RendererError::UniformTypeMismatch { .. })
+        ));
+        assert!(matches!(
+            material.set_texture("u_Missing", Some(yellow)),
+            Err(RendererError::UndeclaredUniform(_))
+        ));
+        assert_eq!(material.get_texture("u_Overlay"), None);
+
+        // Only the overlay texture, set as a uniform
+        let red = renderer.create_material(shader).unwrap();
+        let material = renderer.get_material_mut(red).unwrap();
+        material
+            .set_uniform("u_Overlay", UniformValue::Texture(magenta))
+            .unwrap();
+        material
+            .set_uniform("u_Tint", UniformValue::Float4((1.0, 1.0, 0.0, 1.0).into()))
+            .unwrap();
+        assert_eq!(material.get_texture("u_Overlay"), Some(magenta));
+
+        let vertices: &[f32] = &[-1.0, -1.0, 1.0, -1.0, 1.0, 1.0, -1.0, 1.0];
+        let vertex_buffer = renderer
+            .create_vertex_buffer(&[VertexBufferElement::Float2], slice_to_bytes(vertices))
+            .unwrap();
+        let index_buffer = renderer.create_index_buffer(&[0, 1, 2, 0, 2, 3]);
+        // The magenta overlay of the second material would make the first one black
+        for (material, expected) in [
+            (green, (0.0, 1.0, 0.0, 1.0)),
+            (red, (1.0, 0.0, 0.0, 1.0)),
+            (green, (0.0, 1.0, 0.0, 1.0)),
+        ] {
+            renderer.clear(None, ClearDescription::default()).unwrap();
+            let mut draw_context = renderer
+                .drawing_context(
+                    None,
+                    None,
+                    None,
+                    Camera::default(),
+                    PipelineState::default(),
+                    CullFace::None,
+                )
+                .unwrap();
+            let shader = draw_context.bind_material(material).unwrap();
+            draw_context
+                .draw_indexed(
+                    PrimitiveType::Triangle,
+                    shader,
+                    vertex_buffer,
+                    index_buffer,
+                    None,
+                    Matrix4x4::identity(),
+                    (1.0, 1.0, 1.0).into(),
+                )
+                .unwrap();
+            drop(draw_context);
+            assert!(renderer
+                .read_pixels()
+                .pixels
+                .iter()
+                .all(|&pixel| pixel == expected.into()));
+        }
+    }
+}
+
+/// A rectangle spanning the whole height between `left` and `right`, at the clip space depth
+/// `depth`, drawn with `color` and an alpha of `0.5`
+struct Rect {
+    state: PipelineState,
+    left: f32,
+    right: f32,
+    depth: f32,
+    color: (f32, f32, f32),
+}
+
+/// What `draw_rects` does, in order
+enum Step {
+    Clear(ClearDescription),
+    Draw(Rect),
+}
+
+/// Clears to `CLEAR_COLOR`, a depth of `1.0` and a stencil of `0` before the steps
+fn draw_rects(api: RendererAPI, steps: &[Step]) -> Image {
+    let mut renderer = create_renderer(api);
+    let shader = renderer
+        .create_shader(ShaderDescription {
+            inputs: &[ShaderInput {
+                name: "a_Position",
+                typ: ShaderDataType::Float3,
+            }],
+            uniforms: &[ShaderUniform {
+                name: "u_Color",
+                typ: ShaderDataType::Float3,
+                array_size: 1,
+            }],
+            uniform_blocks: &[],
+            glsl: Some(GlslShaderSource {
+                vertex: r"#version 330 core
+
+in vec3 a_Position;
+
+void main() {
+    gl_Position = vec4(a_Position, 1.0);
+}
+",
+                fragment: r"#version 330 core
+
+out vec4 o_Color;
+uniform vec3 u_Color;
+
+void main() {
+    o_Color = vec4(u_Color, 0.5);
+}
+",
+                preprocessor: GlslPreprocessor::default(),
+            }),
+            software: Some(SoftwareShaderSource {
+                vertex: |inputs, _uniforms, _varyings| inputs[0],
+                fragment: |_varyings, uniforms, outputs| {
+                    let color = uniforms.get_vector3("u_Color").unwrap();
+                    outputs[0] = (color.x, color.y, color.z, 0.5).into();
+                },
+            }),
+        })
+        .unwrap();
+    let index_buffer = renderer.create_index_buffer(&[0, 1, 2, 0, 2, 3]);
+    renderer
+        .clear(
+            None,
+            ClearDescription {
+                color: Some(CLEAR_COLOR.into()),
+                depth: Some(1.0),
+                stencil: Some(0),
+            },
+        )
+        .unwrap();
+    for step in steps {
+        let rect = match step {
+            Step::Clear(description) => {
+                renderer.clear(None, *description).unwrap();
+                continue;
+            }
+            Step::Draw(rect) => rect,
+        };
+        let (left, right, depth) = (rect.left, rect.right, rect.depth);
+        let vertices: &[f32] = &[
+            left, -1.0, depth, right, -1.0, depth, right, 1.0, depth, left, 1.0, depth,
+        ];
+        let vertex_buffer = renderer
+            .create_vertex_buffer(&[VertexBufferElement::Float3], slice_to_bytes(vertices))
+            .unwrap();
+        let mut draw_context = renderer
+            .drawing_context(
+                None,
+                None,
+                None,
+                Camera::default(),
+                rect.state,
+                CullFace::None,
+            )
+            .unwrap();
+        draw_context
+            .draw_indexed(
+                PrimitiveType::Triangle,
+                shader,
+                vertex_buffer,
+                index_buffer,
+                None,
+                Matrix4x4::identity(),
+                rect.color.into(),
+            )
+            .unwrap();
+    }
+    renderer.read_pixels()
+}
+
+/// Does `steps` with both apis, checks that they agree and returns the color of the leftmost and
+/// the rightmost column
+fn draw_rects_with_both_apis(steps: &[Step]) -> (Vector4<f32>, Vector4<f32>) {
+    let opengl = draw_rects(RendererAPI::OpenGL, steps);
+    let software = draw_rects(RendererAPI::Software, steps);
+    for (i, (opengl, software)) in opengl.pixels.iter().zip(&software.pixels).enumerate() {
+        assert_pixel_near(*software, *opengl, 1.0 / 255.0, &format!("pixel {i}"));
+    }
+    (
+        opengl.get_pixel((0, 0).into()),
+        opengl.get_pixel((SIZE.0 - 1, 0).into()),
+    )
+}
+
+#[test]
+fn pipeline_blending() {
+    let full = |blend| {
+        Step::Draw(Rect {
+            state: PipelineState {
+                blend,
+                ..Default::default()
+            },
+            left: -1.0,
+            right: 1.0,
+            depth: 0.0,
+            color: (1.0, 0.0, 0.0),
+        })
+    };
+    let cases = [
+        (BlendMode::None, (1.0, 0.0, 0.0, 0.5)),
+        (BlendMode::Alpha, (0.5, 0.0, 0.5, 0.75)),
+        (BlendMode::Additive, (0.5, 0.0, 1.0, 1.0)),
+        (BlendMode::Multiply, (0.0, 0.0, 0.0, 0.5)),
+        (BlendMode::PremultipliedAlpha, (1.0, 0.0, 0.5, 1.0)),
+    ];
+    for (blend, expected) in cases {
+        let (color, _) = draw_rects_with_both_apis(&[full(blend)]);
+        assert_pixel_near(color, expected.into(), 1.0 / 255.0, &format!("{blend:?}"));
+    }
+}
+
+#[test]
+fn pipeline_depth_stencil_and_color_mask() {
+    let rect = |state, left, right, depth, color| {
+        Step::Draw(Rect {
+            state,
+            left,
+            right,
+            depth,
+            color,
+        })
+    };
+    let opaque = PipelineState {
+        blend: BlendMode::None,
+        ..Default::default()
+    };
+    let depth = |compare, write| PipelineState {
+        depth: Some(DepthState { compare, write }),
+        ..opaque
+    };
+
+    // Nearer passes, further fails, and nothing is written without depth writes
+    let (left, right) = draw_rects_with_both_apis(&[
+        rect(
+            depth(CompareFunction::Less, true),
+            -1.0,
+            1.0,
+            0.5,
+            (1.0, 0.0, 0.0),
+        ),
+        rect(
+            depth(CompareFunction::Less, true),
+            -1.0,
+            0.0,
+            0.0,
+            (0.0, 1.0, 0.0),
+        ),
+        rect(
+            depth(CompareFunction::Less, true),
+            0.0,
+            1.0,
+            0.8,
+            (0.0, 0.0, 1.0),
+        ),
+        rect(
+            depth(CompareFunction::Always, false),
+            -1.0,
+            0.0,
+            0.9,
+            (1.0, 1.0, 0.0),
+        ),
+        rect(
+            depth(CompareFunction::Less, true),
+            -1.0,
+            0.0,
+            0.7,
+            (0.0, 1.0, 1.0),
+        ),
+    ]);
+    assert_pixel_near(left, (1.0, 1.0, 0.0, 0.5).into(), 1.0 / 255.0, "left");
+    assert_pixel_near(right, (1.0, 0.0, 0.0, 0.5).into(), 1.0 / 255.0, "right");
+
+    // The left half only gets the stencil because its color is masked
+    let (left, right) = draw_rects_with_both_apis(&[
+        rect(
+            PipelineState {
+                stencil: Some(StencilState {
+                    reference: 1,
+                    pass: StencilOperation::Replace,
+                    ..Default::default()
+                }),
+                color_mask: ColorMask::NONE,
+                ..opaque
+            },
+            -1.0,
+            0.0,
+            0.0,
+            (1.0, 1.0, 1.0),
+        ),
+        rect(
+            PipelineState {
+                stencil: Some(StencilState {
+                    compare: CompareFunction::Equal,
+                    reference: 1,
+                    ..Default::default()
+                }),
+                color_mask: ColorMask {
+                    green: false,
+                    ..ColorMask::ALL
+                },
+                ..opaque
+            },
+            -1.0,
+            1.0,
+            0.0,
+            (1.0, 1.0, 0.0),
+        ),
+    ]);
+    assert_pixel_near(left, (1.0, 0.0, 0.0, 0.5).into(), 1.0 / 255.0, "left");
+    assert_pixel_near(right, CLEAR_COLOR.into(), 1.0 / 255.0, "right");
+}
+
+#[test]
+fn partial_clears() {
+    let opaque = PipelineState {
+        blend: BlendMode::None,
+        ..Default::default()
+    };
+    let depth_less = Some(DepthState {
+        compare: CompareFunction::Less,
+        write: true,
+    });
+    let stencil = |compare, pass| {
+        Some(StencilState {
+            compare,
+            reference: 1,
+            pass,
+            ..Default::default()
+        })
+    };
+    let draw = |depth, stencil, left, right, z, color| {
+        Step::Draw(Rect {
+            state: PipelineState {
+                depth,
+                stencil,
+                ..opaque
+            },
+            left,
+            right,
+            depth: z,
+            color,
+        })
+    };
+    let only = |color, depth, stencil| {
+        Step::Clear(ClearDescription {
+            color,
+            depth,
+            stencil,
+        })
+    };
+    let write_red = |right| {
+        draw(
+            depth_less,
+            stencil(CompareFunction::Always, StencilOperation::Replace),
+            -1.0,
+            right,
+            0.5,
+            (1.0, 0.0, 0.0),
+        )
+    };
+
+    // Only the color, the depth still hides what is behind the red
+    let (left, right) = draw_rects_with_both_apis(&[
+        write_red(1.0),
+        only(Some((0.0, 1.0, 0.0, 1.0).into()), None, None),
+        draw(depth_less, None, -1.0, 1.0, 0.8, (0.0, 0.0, 1.0)),
+    ]);
+    assert_pixel_near(left, (0.0, 1.0, 0.0, 1.0).into(), 1.0 / 255.0, "color left");
+    assert_pixel_near(
+        right,
+        (0.0, 1.0, 0.0, 1.0).into(),
+        1.0 / 255.0,
+        "color right",
+    );
+
+    // Only the depth, the stencil of the left half stays
+    let (left, right) = draw_rects_with_both_apis(&[
+        write_red(0.0),
+        only(None, Some(1.0), None),
+        draw(
+            depth_less,
+            stencil(CompareFunction::Equal, StencilOperation::Keep),
+            -1.0,
+            1.0,
+            0.8,
+            (0.0, 0.0, 1.0),
+        ),
+    ]);
+    assert_pixel_near(left, (0.0, 0.0, 1.0, 0.5).into(), 1.0 / 255.0, "depth left");
+    assert_pixel_near(right, CLEAR_COLOR.into(), 1.0 / 255.0, "depth right");
+
+    // Only the stencil, the color and depth stay
+    let (left, right) = draw_rects_with_both_apis(&[
+        write_red(1.0),
+        only(None, None, Some(0)),
+        draw(
+            None,
+            Some(StencilState {
+                compare: CompareFunction::Equal,
+                reference: 0,
+                ..Default::default()
+            }),
+            -1.0,
+            0.0,
+            0.0,
+            (0.0, 1.0, 0.0),
+        ),
+        draw(depth_less, None, 0.0, 1.0, 0.8, (0.0, 0.0, 1.0)),
+    ]);
+    assert_pixel_near(
+        left,
+        (0.0, 1.0, 0.0, 0.5).into(),
+        1.0 / 255.0,
+        "stencil left",
+    );
+    assert_pixel_near(
+        right,
+        (1.0, 0.0, 0.0, 0.5).into(),
+        1.0 / 255.0,
+        "stencil right",
+    );
+
+    // A framebuffer without depth and stencil skips them
+    for api in [RendererAPI::OpenGL, RendererAPI::Software] {
+        let mut renderer = create_renderer(api);
+        let texture = create_color_texture(&mut renderer, SIZE);
+        let framebuffer = renderer
+            .create_framebuffer(SIZE.into(), &[texture], DepthAttachment::None)
+            .unwrap();
+        renderer
+            .clear(
+                Some(framebuffer),
+                ClearDescription {
+                    color: Some(DRAW_COLOR.into()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let pixels = renderer.get_texture(texture).unwrap().get_pixels();
+        assert!(pixels.iter().all(|&pixel| pixel == DRAW_COLOR.into()));
+    }
+}